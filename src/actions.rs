@@ -1,47 +1,75 @@
 //! Editor actions such as move, insert, delete
+pub mod case;
 pub mod change;
+pub mod command;
 pub mod cpaste;
 pub mod delete;
 pub mod insert;
+pub mod mark;
 pub mod motion;
+pub mod number;
+pub mod operator;
 pub mod search;
 pub mod select;
 #[cfg(feature = "system-editor")]
 pub mod system_editor;
-use crate::state::selection::Selection;
+pub mod yank;
+use crate::state::selection::{set_selection_with_lines, Selection};
 use crate::{EditorMode, EditorState};
 use cpaste::PasteOverSelection;
 use delete::DeleteToEndOfLine;
 use enum_dispatch::enum_dispatch;
 use motion::{MoveToFirstRow, MoveToLastRow};
+use operator::MotionKind;
 #[cfg(feature = "system-editor")]
 pub use system_editor::OpenSystemEditor;
 
+pub use self::case::{Case, ChangeSelectionCase, ToggleCase};
 pub use self::change::{
-    ChangeBigWord, ChangeFindForward, ChangeInnerBetween, ChangeInnerBigWord, ChangeInnerWord,
-    ChangeSelection, ChangeTillForward, ChangeWord,
+    ChangeBigWord, ChangeFindBackward, ChangeFindForward, ChangeInnerBetween, ChangeInnerBigWord,
+    ChangeInnerWord, ChangeSelection, ChangeTillBackward, ChangeTillForward, ChangeWord,
 };
-pub use self::cpaste::{CopyLine, CopySelection, Paste, PasteBefore};
+pub use self::command::{
+    AppendCharToCommand, CompleteCommand, ExecuteCommand, RemoveCharFromCommand, StartCommand,
+    StopCommand,
+};
+pub use self::cpaste::{CopyLine, CopySelection, Paste, PasteBefore, SelectRegister};
 pub use self::delete::{
-    DeleteBigWordEnd, DeleteBigWordForward, DeleteChar, DeleteCharForward, DeleteFindForward,
-    DeleteLine, DeleteSelection, DeleteTillForward, DeleteToFirstCharOfLine, DeleteWordBackward,
-    DeleteWordEnd, DeleteWordForward, JoinLineWithLineBelow, RemoveChar, ReplaceChar,
+    DeleteBigWordEnd, DeleteBigWordForward, DeleteChar, DeleteCharForward, DeleteFindBackward,
+    DeleteFindForward, DeleteLine, DeleteSelection, DeleteTillBackward, DeleteTillForward,
+    DeleteToFirstCharOfLine, DeleteWordBackward, DeleteWordEnd, DeleteWordForward,
+    JoinLineWithLineBelow, RemoveChar, ReplaceChar, ReplaceChars,
+};
+pub use self::insert::{
+    AppendBlock, AppendNewline, InsertBlock, InsertChar, InsertNewline, LineBreak, OverwriteChar,
+    RestoreChar,
 };
-pub use self::insert::{AppendNewline, InsertChar, InsertNewline, LineBreak};
+pub use self::mark::{JumpBackward, JumpForward, JumpToMark, JumpToMarkLine, SetMark};
 pub use self::motion::{
-    FindForward, MoveBackward, MoveDown, MoveForward, MoveHalfPageDown, MoveHalfPageUp,
+    FindBackward, FindForward, MoveBackward, MoveBigWordBackward, MoveBigWordForward,
+    MoveBigWordForwardToEndOfWord, MoveDown, MoveForward, MoveHalfPageDown, MoveHalfPageUp,
     MovePageDown, MovePageUp, MoveParagraphBackward, MoveParagraphForward, MoveToEndOfLine,
-    MoveToFirst, MoveToMatchinBracket, MoveToStartOfLine, MoveUp, MoveWordBackward,
-    MoveWordForward, MoveWordForwardToEndOfWord, TillForward,
+    MoveToFirst, MoveToLine, MoveToMatchinBracket, MoveToStartOfLine, MoveUp, MoveWordBackward,
+    MoveWordForward, MoveWordForwardToEndOfWord, RepeatFind, TillBackward, TillForward,
 };
+pub use self::number::{Decrement, Increment};
+pub use self::operator::{Operate, OperateSelection, Operator};
 use self::search::StartSearch;
 pub use self::search::{
-    AppendCharToSearch, FindFirst, FindNext, FindPrevious, RemoveCharFromSearch,
-    SelectCurrentSearch, StopSearch,
+    AppendCharToSearch, ConfirmReplace, FindFirst, FindNext, FindPrevious, NextSearchHistory,
+    PreviousSearchHistory, RemoveCharFromSearch, SearchWordBackward, SearchWordForward,
+    SelectCurrentSearch, StartBackwardSearch, StopSearch, ToggleSearchOption,
 };
 pub use self::select::{
-    DeleteInnerBetween, DeleteInnerBigWord, DeleteInnerWord, SelectInnerBetween,
-    SelectInnerBigWord, SelectInnerWord, SelectLine,
+    DeleteInnerBetween, DeleteInnerBigWord, DeleteInnerWord, SelectAroundBetween,
+    SelectAroundBigWord, SelectAroundParagraph, SelectAroundSentence, SelectAroundTag,
+    SelectAroundWord, SelectInnerBetween, SelectInnerBigWord, SelectInnerParagraph,
+    SelectInnerSentence, SelectInnerTag, SelectInnerWord, SelectLine, SelectLines,
+};
+pub use self::yank::{
+    YankBigWordEnd, YankBigWordForward, YankFindForward, YankInnerBetween, YankInnerBigWord,
+    YankInnerWord, YankLine, YankTillForward, YankToEndOfLine, YankToFirstCharOfLine,
+    YankWordBackward, YankWordEnd, YankWordForward,
 };
 
 #[enum_dispatch(Execute)]
@@ -55,11 +83,15 @@ pub enum Action {
     MoveWordForward(MoveWordForward),
     MoveWordForwardToEndOfWord(MoveWordForwardToEndOfWord),
     MoveWordBackward(MoveWordBackward),
+    MoveBigWordForward(MoveBigWordForward),
+    MoveBigWordForwardToEndOfWord(MoveBigWordForwardToEndOfWord),
+    MoveBigWordBackward(MoveBigWordBackward),
     MoveToStartOfLine(MoveToStartOfLine),
     MoveToFirst(MoveToFirst),
     MoveToEndOfLine(MoveToEndOfLine),
     MoveToFirstRow(MoveToFirstRow),
     MoveToLastRow(MoveToLastRow),
+    MoveToLine(MoveToLine),
     MoveToMatchingBracket(MoveToMatchinBracket),
     MoveHalfPageDown(MoveHalfPageDown),
     MoveHalfPageUp(MoveHalfPageUp),
@@ -69,11 +101,28 @@ pub enum Action {
     MoveParagraphBackward(MoveParagraphBackward),
     FindForward(FindForward),
     TillForward(TillForward),
+    FindBackward(FindBackward),
+    TillBackward(TillBackward),
+    RepeatFind(RepeatFind),
+    SetMark(SetMark),
+    JumpToMark(JumpToMark),
+    JumpToMarkLine(JumpToMarkLine),
+    JumpBackward(JumpBackward),
+    JumpForward(JumpForward),
     InsertChar(InsertChar),
+    OverwriteChar(OverwriteChar),
+    RestoreChar(RestoreChar),
     LineBreak(LineBreak),
     AppendNewline(AppendNewline),
     InsertNewline(InsertNewline),
+    InsertBlock(InsertBlock),
+    AppendBlock(AppendBlock),
     ReplaceChar(ReplaceChar),
+    ReplaceChars(ReplaceChars),
+    ToggleCase(ToggleCase),
+    ChangeSelectionCase(ChangeSelectionCase),
+    Increment(Increment),
+    Decrement(Decrement),
     RemoveChar(RemoveChar),
     DeleteChar(DeleteChar),
     DeleteCharForward(DeleteCharForward),
@@ -84,12 +133,16 @@ pub enum Action {
     DeleteBigWordForward(DeleteBigWordForward),
     DeleteFindForward(DeleteFindForward),
     DeleteTillForward(DeleteTillForward),
+    DeleteFindBackward(DeleteFindBackward),
+    DeleteTillBackward(DeleteTillBackward),
     DeleteWordEnd(DeleteWordEnd),
     DeleteBigWordEnd(DeleteBigWordEnd),
     ChangeWord(ChangeWord),
     ChangeBigWord(ChangeBigWord),
     ChangeFindForward(ChangeFindForward),
     ChangeTillForward(ChangeTillForward),
+    ChangeFindBackward(ChangeFindBackward),
+    ChangeTillBackward(ChangeTillBackward),
     DeleteWordBackward(DeleteWordBackward),
     DeleteSelection(DeleteSelection),
     JoinLineWithLineBelow(JoinLineWithLineBelow),
@@ -100,20 +153,55 @@ pub enum Action {
     ChangeInnerWord(ChangeInnerWord),
     DeleteInnerWord(DeleteInnerWord),
     SelectInnerBigWord(SelectInnerBigWord),
+    SelectAroundWord(SelectAroundWord),
+    SelectAroundBigWord(SelectAroundBigWord),
+    SelectAroundBetween(SelectAroundBetween),
+    SelectInnerParagraph(SelectInnerParagraph),
+    SelectAroundParagraph(SelectAroundParagraph),
+    SelectInnerSentence(SelectInnerSentence),
+    SelectAroundSentence(SelectAroundSentence),
+    SelectInnerTag(SelectInnerTag),
+    SelectAroundTag(SelectAroundTag),
     ChangeInnerBigWord(ChangeInnerBigWord),
     DeleteInnerBigWord(DeleteInnerBigWord),
     ChangeSelection(ChangeSelection),
     SelectLine(SelectLine),
+    SelectLines(SelectLines),
+    Operate(Operate),
+    OperateSelection(OperateSelection),
     Undo(Undo),
     Redo(Redo),
+    UndoEarlier(UndoEarlier),
+    UndoLater(UndoLater),
     RepeatLastChange(RepeatLastChange),
+    RecordMacro(RecordMacro),
+    ReplayMacro(ReplayMacro),
     Paste(Paste),
     PasteBefore(PasteBefore),
     PasteOverSelection(PasteOverSelection),
     CopySelection(CopySelection),
     CopyLine(CopyLine),
+    SelectRegister(SelectRegister),
+    YankWordForward(YankWordForward),
+    YankBigWordForward(YankBigWordForward),
+    YankWordEnd(YankWordEnd),
+    YankBigWordEnd(YankBigWordEnd),
+    YankWordBackward(YankWordBackward),
+    YankLine(YankLine),
+    YankToFirstCharOfLine(YankToFirstCharOfLine),
+    YankToEndOfLine(YankToEndOfLine),
+    YankFindForward(YankFindForward),
+    YankTillForward(YankTillForward),
+    YankInnerWord(YankInnerWord),
+    YankInnerBigWord(YankInnerBigWord),
+    YankInnerBetween(YankInnerBetween),
     Composed(Composed),
     StartSearch(StartSearch),
+    StartBackwardSearch(StartBackwardSearch),
+    PreviousSearchHistory(PreviousSearchHistory),
+    NextSearchHistory(NextSearchHistory),
+    SearchWordForward(SearchWordForward),
+    SearchWordBackward(SearchWordBackward),
     StopSearch(StopSearch),
     FindFirst(FindFirst),
     FindNext(FindNext),
@@ -121,6 +209,14 @@ pub enum Action {
     SelectCurrentSearch(SelectCurrentSearch),
     AppendCharToSearch(AppendCharToSearch),
     RemoveCharFromSearch(RemoveCharFromSearch),
+    ConfirmReplace(ConfirmReplace),
+    ToggleSearchOption(ToggleSearchOption),
+    StartCommand(StartCommand),
+    StopCommand(StopCommand),
+    AppendCharToCommand(AppendCharToCommand),
+    RemoveCharFromCommand(RemoveCharFromCommand),
+    CompleteCommand(CompleteCommand),
+    ExecuteCommand(ExecuteCommand),
     #[cfg(feature = "system-editor")]
     OpenSystemEditor(OpenSystemEditor),
 }
@@ -134,12 +230,60 @@ pub trait Execute {
         false
     }
 
+    /// Whether this action changes the text. A read-only editor refuses such
+    /// actions. Defaults to [`is_repeatable`](Self::is_repeatable), as every
+    /// change the dot-repeat command replays is an edit. Edits that set
+    /// `last_change` themselves must override it.
+    fn is_edit(&self) -> bool {
+        self.is_repeatable()
+    }
+
     /// Returns a handle to this action's character argument if it takes one
     /// (like `f`/`t`). While the inner value is `None`, the action is still
     /// waiting for the key handler to supply the next keystroke through it.
     fn char_arg(&mut self) -> Option<&mut Option<char>> {
         None
     }
+
+    /// Returns a handle to this action's repeat count if it takes one (like
+    /// `w` or `dd`). The key handler writes a count prefix such as the `3`
+    /// in `3w` through it.
+    fn count(&mut self) -> Option<&mut usize> {
+        None
+    }
+
+    /// Returns how an operator treats the text covered by this action if it
+    /// is a motion or text object (like `w` or `iw`). Only actions returning
+    /// `Some` can follow an operator such as `d`.
+    fn motion_kind(&self) -> Option<MotionKind> {
+        None
+    }
+
+    /// Returns a handle to this action's motion if it is an operator (like
+    /// `d`). While the inner value is `None`, the operator is still waiting
+    /// for the key handler to supply a motion through it.
+    fn motion_arg(&mut self) -> Option<&mut Option<Box<Action>>> {
+        None
+    }
+}
+
+impl Action {
+    /// Applies a count prefix (e.g. the `3` in `3w` or the `10` in `10G`).
+    ///
+    /// Actions without a count are left untouched.
+    pub(crate) fn apply_count(&mut self, count: usize) {
+        match self {
+            Action::MoveToFirstRow(_) | Action::MoveToLastRow(_) => {
+                *self = MoveToLine(count).into();
+            }
+            Action::RepeatLastChange(repeat) => repeat.0 = Some(count),
+            _ => {
+                if let Some(slot) = self.count() {
+                    *slot = count;
+                }
+            }
+        }
+    }
 }
 
 pub trait Chainable {
@@ -157,22 +301,44 @@ pub struct SwitchMode(pub EditorMode);
 
 impl Execute for SwitchMode {
     fn execute(&mut self, state: &mut EditorState) {
-        let from_insert = state.mode == EditorMode::Insert;
+        let from_insert = matches!(state.mode, EditorMode::Insert | EditorMode::Replace);
+        let block_insert = state.block_insert.take().filter(|_| from_insert);
 
         state.clamp_column();
         match self.0 {
             EditorMode::Normal => {
                 state.selection = None;
             }
-            EditorMode::Visual => {
-                state.selection = Some(Selection::new(state.cursor, state.cursor));
+            EditorMode::Visual | EditorMode::VisualLine | EditorMode::VisualBlock => {
+                // Switching between visual modes keeps the selection's anchor.
+                let anchor = match &state.selection {
+                    Some(selection) if state.mode.is_visual() => {
+                        selection.anchor.unwrap_or(selection.start)
+                    }
+                    _ => state.cursor,
+                };
+                let selection = Selection::new(anchor, state.cursor);
+                state.selection = Some(match self.0 {
+                    EditorMode::VisualBlock => selection.block_mode(),
+                    EditorMode::VisualLine => selection.line_mode(),
+                    _ => selection,
+                });
+                if self.0 == EditorMode::VisualLine {
+                    set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
+                }
             }
             EditorMode::Insert => {
                 if ![EditorMode::Insert, EditorMode::Search].contains(&state.mode) {
                     state.capture();
                 }
             }
-            EditorMode::Search => {}
+            EditorMode::Replace => {
+                if state.mode != EditorMode::Replace {
+                    state.capture();
+                }
+                state.replaced.clear();
+            }
+            EditorMode::Search | EditorMode::Command => {}
         }
         state.mode = self.0;
 
@@ -187,12 +353,20 @@ impl Execute for SwitchMode {
             // Re-clamp so the cursor never lingers past the end of the line.
             state.clamp_column();
         }
+
+        if let Some(block_insert) = block_insert {
+            block_insert.apply(state);
+        }
     }
 
     fn is_repeatable(&self) -> bool {
         // Entering insert mode begins a repeatable insert session (`.`).
         self.0 == EditorMode::Insert
     }
+
+    fn is_edit(&self) -> bool {
+        matches!(self.0, EditorMode::Insert | EditorMode::Replace)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -202,17 +376,27 @@ impl Execute for Undo {
     fn execute(&mut self, state: &mut EditorState) {
         state.undo();
     }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
 }
 
 /// Repeats the last buffer-changing command (dot-repeat).
+///
+/// A count replaces the count of the repeated command (Vim `3.`).
 #[derive(Clone, Debug)]
-pub struct RepeatLastChange;
+pub struct RepeatLastChange(pub Option<usize>);
 
 impl Execute for RepeatLastChange {
     fn execute(&mut self, state: &mut EditorState) {
         let Some(mut action) = state.last_change.clone() else {
             return;
         };
+        if let Some(count) = self.0 {
+            action.apply_count(count);
+            state.last_change = Some(action.clone());
+        }
         action.execute(state);
 
         if let Some(text) = state.last_insert.clone() {
@@ -222,6 +406,68 @@ impl Execute for RepeatLastChange {
             SwitchMode(EditorMode::Normal).execute(state);
         }
     }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
+}
+
+/// Starts recording the keys into a macro register (Vim `q{a-z}`), or stops
+/// the recording if there is one (Vim `q`).
+///
+/// The register name is `None` until the key handler supplies the next
+/// keystroke via [`Execute::char_arg`]. While recording, the key handler
+/// runs this action without waiting for a name.
+#[derive(Clone, Debug, Copy)]
+pub struct RecordMacro(pub Option<char>);
+
+impl Execute for RecordMacro {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.macros.is_recording() {
+            state.macros.stop();
+        } else if let Some(name) = self.0.filter(char::is_ascii_alphanumeric) {
+            state.macros.start(name);
+        }
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
+/// Replays the keys of a macro register (Vim `@{a-z}`), or of the last
+/// played macro (Vim `@@`).
+///
+/// The keys are replayed by the key handler that runs this action. The
+/// register name is `None` until the key handler supplies the next keystroke
+/// via [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct ReplayMacro {
+    pub name: Option<char>,
+    pub count: usize,
+}
+
+impl ReplayMacro {
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self { name: None, count }
+    }
+}
+
+impl Execute for ReplayMacro {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(name) = self.name {
+            state.macros.play(name, self.count);
+        }
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.name)
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -231,6 +477,46 @@ impl Execute for Redo {
     fn execute(&mut self, state: &mut EditorState) {
         state.redo();
     }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
+}
+
+/// Moves back in time through the undo history, across branches (`g-`).
+#[derive(Clone, Debug)]
+pub struct UndoEarlier(pub usize);
+
+impl Execute for UndoEarlier {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.undo_earlier(self.0);
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
+}
+
+/// Moves forward in time through the undo history, across branches (`g+`).
+#[derive(Clone, Debug)]
+pub struct UndoLater(pub usize);
+
+impl Execute for UndoLater {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.undo_later(self.0);
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
 }
 
 /// Executes multiple actions one after the other.
@@ -251,15 +537,31 @@ impl Composed {
 }
 
 impl Execute for Composed {
+    /// Executes the actions as a single undo step.
     fn execute(&mut self, state: &mut EditorState) {
+        state.begin_undo_group();
         for action in &mut self.0 {
             action.execute(state);
         }
+        state.end_undo_group();
     }
 
     fn is_repeatable(&self) -> bool {
         self.0.iter().any(Execute::is_repeatable)
     }
+
+    fn is_edit(&self) -> bool {
+        self.0.iter().any(Execute::is_edit)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        // A chain of motions is a motion itself, ending where the last one does.
+        let mut kind = None;
+        for action in &self.0 {
+            kind = Some(action.motion_kind()?);
+        }
+        kind
+    }
 }
 
 #[cfg(test)]
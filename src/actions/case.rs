@@ -0,0 +1,147 @@
+//! Actions that change the letter case of the text.
+//!
+//! The case of the text covered by a motion is changed with the operators
+//! [`Operator::Lowercase`], [`Operator::Uppercase`] and
+//! [`Operator::ToggleCase`] (`gu`, `gU`, `g~`).
+//!
+//! [`Operator::Lowercase`]: super::Operator::Lowercase
+//! [`Operator::Uppercase`]: super::Operator::Uppercase
+//! [`Operator::ToggleCase`]: super::Operator::ToggleCase
+use super::{delete::map_selection, Execute, SwitchMode};
+use crate::{helper::map_chars, EditorMode, EditorState};
+
+/// A change of the letter case.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    /// Makes letters lowercase.
+    Lower,
+    /// Makes letters uppercase.
+    Upper,
+    /// Makes lowercase letters uppercase and the other way around.
+    Toggle,
+}
+
+impl Case {
+    /// Returns the character in this case. A character whose case mapping
+    /// is more than one character, like `ß`, is left unchanged.
+    #[must_use]
+    pub fn apply(self, ch: char) -> char {
+        let lower = || single_char(ch.to_lowercase()).unwrap_or(ch);
+        let upper = || single_char(ch.to_uppercase()).unwrap_or(ch);
+        match self {
+            Self::Lower => lower(),
+            Self::Upper => upper(),
+            Self::Toggle if ch.is_uppercase() => lower(),
+            Self::Toggle => upper(),
+        }
+    }
+}
+
+/// Returns the only character of a case mapping, or `None` if it maps to
+/// several characters.
+fn single_char(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    let ch = chars.next()?;
+    chars.next().is_none().then_some(ch)
+}
+
+/// Toggles the case of the character under the cursor and moves the cursor
+/// to the right (Vim `~`). With a count, toggles that many characters, up to
+/// the end of the line.
+#[derive(Clone, Debug, Copy)]
+pub struct ToggleCase(pub usize);
+
+impl Execute for ToggleCase {
+    fn execute(&mut self, state: &mut EditorState) {
+        let index = state.cursor;
+        let len_col = state.lines.len_col(index.row).unwrap_or_default();
+        if index.col >= len_col {
+            return;
+        }
+        state.capture();
+        let end = index.col.saturating_add(self.0).min(len_col);
+        let cols = index.col..end;
+        map_chars(
+            &mut state.lines,
+            &mut state.changes,
+            index.row,
+            cols,
+            |ch| Case::Toggle.apply(ch),
+        );
+        state.cursor.col = end;
+        state.clamp_column();
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Changes the case of the selected text and leaves visual mode (Vim `u`,
+/// `U` and `~` in visual mode).
+#[derive(Clone, Debug, Copy)]
+pub struct ChangeSelectionCase(pub Case);
+
+impl Execute for ChangeSelectionCase {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.capture();
+        if let Some(selection) = state.selection.take() {
+            let case = self.0;
+            map_selection(state, &selection, |ch| case.apply(ch));
+        }
+        SwitchMode(EditorMode::Normal).execute(state);
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::selection::Selection;
+    use crate::{Index2, Lines};
+
+    #[test]
+    fn test_toggle_case() {
+        let mut state = EditorState::new(Lines::from("Hello World"));
+        state.execute(ToggleCase(1));
+        assert_eq!(state.lines, Lines::from("hello World"));
+        assert_eq!(state.cursor, Index2::new(0, 1));
+
+        state.cursor = Index2::new(0, 6);
+        state.execute(ToggleCase(10));
+        assert_eq!(state.lines, Lines::from("hello wORLD"));
+        assert_eq!(state.cursor, Index2::new(0, 10));
+    }
+
+    #[test]
+    fn test_case_keeps_multi_char_mappings() {
+        assert_eq!(Case::Upper.apply('ß'), 'ß');
+        assert_eq!(Case::Lower.apply('İ'), 'İ');
+        assert_eq!(Case::Toggle.apply('İ'), 'İ');
+        assert_eq!(Case::Toggle.apply('Ä'), 'ä');
+
+        let mut state = EditorState::new(Lines::from("straße"));
+        state.mode = EditorMode::Visual;
+        state.selection = Some(Selection::new(Index2::new(0, 0), Index2::new(0, 5)));
+        state.execute(ChangeSelectionCase(Case::Upper));
+        assert_eq!(state.lines, Lines::from("STRAßE"));
+    }
+
+    #[test]
+    fn test_change_selection_case() {
+        let mut state = EditorState::new(Lines::from("abc\ndef\nghi"));
+        state.mode = EditorMode::VisualLine;
+        state.selection = Some(Selection::new(Index2::new(0, 1), Index2::new(1, 1)).line_mode());
+
+        state.execute(ChangeSelectionCase(Case::Upper));
+        assert_eq!(state.lines, Lines::from("ABC\nDEF\nghi"));
+        assert_eq!(state.mode, EditorMode::Normal);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+}
@@ -5,12 +5,13 @@
 //! insert mode, mirroring Vim's `c` operator.
 
 use super::delete::{
-    delete_selection, DeleteBigWordEnd, DeleteFindForward, DeleteTillForward, DeleteWordEnd,
+    delete_selection, DeleteBigWordEnd, DeleteFindBackward, DeleteFindForward, DeleteTillBackward,
+    DeleteTillForward, DeleteWordEnd,
 };
-use super::motion::find_char_forward;
+use super::insert::BlockInsert;
+use super::motion::{find_char_backward, find_char_forward};
 use super::select::{DeleteInnerBetween, DeleteInnerBigWord, DeleteInnerWord};
 use super::Execute;
-use crate::clipboard::ClipboardTrait;
 use crate::{EditorMode, EditorState};
 
 /// Changes from the cursor to the end of the current word: deletes it and
@@ -27,6 +28,10 @@ impl Execute for ChangeWord {
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 /// Changes from the cursor to the end of the current WORD: deletes it and
@@ -43,6 +48,10 @@ impl Execute for ChangeBigWord {
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 /// Changes the inner word under the cursor: deletes it and enters insert mode.
@@ -121,6 +130,66 @@ impl Execute for ChangeTillForward {
     }
 }
 
+/// Changes from the previous occurrence of a character on the line up to (but
+/// not including) the cursor: deletes it and enters insert mode (Vim
+/// `cF<char>`). Does nothing if the character is not found.
+///
+/// The target is `None` until the key handler supplies the next keystroke via
+/// [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct ChangeFindBackward(pub Option<char>);
+
+impl Execute for ChangeFindBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(target) = self.0 else {
+            return;
+        };
+        if find_char_backward(state, target).is_none() {
+            return;
+        }
+        DeleteFindBackward(self.0).execute(state);
+        state.mode = EditorMode::Insert;
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
+/// Changes from just after the previous occurrence of a character on the line
+/// up to (but not including) the cursor: deletes it and enters insert mode
+/// (Vim `cT<char>`). Does nothing if the character is not found.
+///
+/// The target is `None` until the key handler supplies the next keystroke via
+/// [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct ChangeTillBackward(pub Option<char>);
+
+impl Execute for ChangeTillBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(target) = self.0 else {
+            return;
+        };
+        if find_char_backward(state, target).is_none() {
+            return;
+        }
+        DeleteTillBackward(self.0).execute(state);
+        state.mode = EditorMode::Insert;
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
 /// Changes the inner WORD under the cursor: deletes it and enters insert mode.
 /// This is the `ciW` primitive.
 #[derive(Clone, Debug, Copy)]
@@ -169,10 +238,15 @@ pub struct ChangeSelection;
 
 impl Execute for ChangeSelection {
     fn execute(&mut self, state: &mut EditorState) {
+        state.capture();
         if let Some(selection) = state.selection.take() {
-            state.capture();
             let deleted = delete_selection(state, &selection);
-            state.clip.set_text(deleted.into());
+            state.clip.set_deleted(deleted, selection.kind());
+            // A changed block receives the typed text on every row.
+            if selection.block_mode {
+                let col = selection.block_corners().0.col;
+                state.block_insert = Some(BlockInsert::new(&selection, col, false));
+            }
         }
     }
 
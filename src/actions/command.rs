@@ -0,0 +1,80 @@
+use crate::{EditorMode, EditorState};
+
+use super::Execute;
+
+/// Command to clear the command line before switching into command mode.
+#[derive(Clone, Debug)]
+pub struct StartCommand;
+
+impl Execute for StartCommand {
+    /// Executes the command, starting a new command line.
+    fn execute(&mut self, state: &mut EditorState) {
+        state.command.start();
+    }
+}
+
+/// Command to append a single character to the command line.
+#[derive(Clone, Debug, Copy)]
+pub struct AppendCharToCommand(pub char);
+
+impl Execute for AppendCharToCommand {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.command.push_char(self.0);
+    }
+}
+
+/// Command to remove the last character from the command line. Leaves
+/// command mode if the command line is already empty.
+#[derive(Clone, Debug, Copy)]
+pub struct RemoveCharFromCommand;
+
+impl Execute for RemoveCharFromCommand {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.command.input.is_empty() {
+            state.mode = EditorMode::Normal;
+            return;
+        }
+        state.command.remove_char();
+    }
+}
+
+/// Command to complete the command line. Repeating it cycles through the
+/// completions.
+#[derive(Clone, Debug, Copy)]
+pub struct CompleteCommand;
+
+impl Execute for CompleteCommand {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.command.completion_index.is_none() {
+            state.command.completions = state.commands.complete(state, &state.command.input);
+        }
+        state.command.next_completion();
+    }
+}
+
+/// Command to run the command line and switch back to normal mode. If the
+/// command fails, its error is shown in the status line.
+#[derive(Clone, Debug)]
+pub struct ExecuteCommand;
+
+impl Execute for ExecuteCommand {
+    fn execute(&mut self, state: &mut EditorState) {
+        let input = std::mem::take(&mut state.command.input);
+        state.command.clear_completions();
+        state.mode = EditorMode::Normal;
+        if let Err(err) = state.execute_command(&input) {
+            state.command.error = Some(err);
+        }
+        state.clamp_column();
+    }
+}
+
+/// Command to clear the command line without running it.
+#[derive(Clone, Debug)]
+pub struct StopCommand;
+
+impl Execute for StopCommand {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.command.start();
+    }
+}
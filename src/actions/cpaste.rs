@@ -3,8 +3,9 @@ use std::cmp::min;
 use jagged::{index::RowIndex, Index2};
 
 use crate::{
-    clipboard::ClipboardTrait,
-    helper::{append_str, insert_str, max_row},
+    clipboard::{ClipboardTrait, RegisterKind},
+    helper::{append_str, insert_str},
+    state::changes::end_of,
     EditorState,
 };
 
@@ -16,14 +17,16 @@ use super::{delete::delete_selection, Execute};
 /// Pastes the clipboard contents relative to the cursor.
 ///
 /// With `before` set, characterwise text is inserted in front of the cursor
-/// and linewise text (clipboard starting with `\n`) opens a new line *above*
-/// the current one (mirroring Vim's `P`). Otherwise the text is pasted after
-/// the cursor / on the line below, matching Vim's `p`.
-fn paste(state: &mut EditorState, before: bool) {
-    let s = state.clip.get_text();
+/// and linewise text opens a new line *above* the current one (mirroring
+/// Vim's `P`). Otherwise the text is pasted after the cursor / on the line
+/// below, matching Vim's `p`. Blockwise text is pasted column by column.
+/// The text is pasted `count` times.
+fn paste(state: &mut EditorState, before: bool, count: usize) {
+    let (s, kind) = state.clip.get_text_with_kind();
     if s.is_empty() {
         return;
     }
+    let s = repeat_text(&s, kind, count.max(1));
 
     state.capture();
     state.clamp_column();
@@ -31,53 +34,113 @@ fn paste(state: &mut EditorState, before: bool) {
     if state.view.single_line {
         let s = s.replace('\n', " ").replace('\r', "");
         if before {
-            insert_str(&mut state.lines, &mut state.cursor, &s);
+            insert_str(&mut state.lines, &mut state.changes, &mut state.cursor, &s);
         } else {
-            append_str(&mut state.lines, &mut state.cursor, &s);
+            append_str(&mut state.lines, &mut state.changes, &mut state.cursor, &s);
         }
         return;
     }
 
-    if let Some(stripped) = s.strip_prefix('\n') {
-        let row = if before {
-            state.cursor.row
-        } else {
-            min(max_row(state), state.cursor.row + 1)
-        };
-        state.cursor = Index2::new(row, 0);
-        state.lines.insert(RowIndex::new(row), vec![]);
-        append_str(&mut state.lines, &mut state.cursor, stripped);
-    } else if before {
-        insert_str(&mut state.lines, &mut state.cursor, &s);
-    } else {
-        append_str(&mut state.lines, &mut state.cursor, &s);
+    match kind {
+        RegisterKind::Linewise => {
+            let row = min(state.cursor.row + usize::from(!before), state.lines.len());
+            let pos = Index2::new(row, 0);
+            let text = if row < state.lines.len() {
+                format!("{s}\n")
+            } else {
+                s.clone()
+            };
+            if state.changes.insert(&mut state.lines, pos, &text) {
+                state.cursor = end_of(pos, &s);
+                state.cursor.col = state.cursor.col.saturating_sub(1);
+            }
+        }
+        RegisterKind::Blockwise => {
+            let len_col = state.lines.len_col(state.cursor.row).unwrap_or_default();
+            let col = if before || len_col == 0 {
+                state.cursor.col
+            } else {
+                state.cursor.col + 1
+            };
+            paste_block(state, &s, col);
+        }
+        RegisterKind::Charwise if before => {
+            insert_str(&mut state.lines, &mut state.changes, &mut state.cursor, &s);
+        }
+        RegisterKind::Charwise => {
+            append_str(&mut state.lines, &mut state.changes, &mut state.cursor, &s);
+        }
     }
 }
 
+/// Returns `text` repeated `count` times. Lines are repeated one below the
+/// other and blocks side by side.
+fn repeat_text(text: &str, kind: RegisterKind, count: usize) -> String {
+    match kind {
+        RegisterKind::Charwise => text.repeat(count),
+        RegisterKind::Linewise => vec![text; count].join("\n"),
+        RegisterKind::Blockwise => {
+            let width = text.split('\n').map(|line| line.chars().count()).max();
+            let width = width.unwrap_or_default();
+            let lines: Vec<String> = text
+                .split('\n')
+                .map(|line| format!("{line:width$}").repeat(count))
+                .collect();
+            lines.join("\n")
+        }
+    }
+}
+
+/// Inserts each line of `text` at column `col` of consecutive rows, adding
+/// rows and padding short lines with spaces as needed.
+fn paste_block(state: &mut EditorState, text: &str, col: usize) {
+    let start = state.cursor.row;
+    for (i, line) in text.split('\n').enumerate() {
+        let row = start + i;
+        let len_col = state.lines.len_col(row).unwrap_or_default();
+        let pad = " ".repeat(col.saturating_sub(len_col));
+        let pos = Index2::new(row, col.min(len_col));
+        let _ = state.changes.insert(&mut state.lines, pos, &(pad + line));
+    }
+    state.cursor = Index2::new(start, col);
+}
+
+/// Pastes the clipboard contents after the cursor, or on the line below,
+/// `count` times (Vim `p`).
 #[derive(Clone, Debug)]
-pub struct Paste;
+pub struct Paste(pub usize);
 
 impl Execute for Paste {
     fn execute(&mut self, state: &mut EditorState) {
-        paste(state, false);
+        paste(state, false, self.0);
     }
 
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
+/// Pastes the clipboard contents before the cursor, or on the line above,
+/// `count` times (Vim `P`).
 #[derive(Clone, Debug)]
-pub struct PasteBefore;
+pub struct PasteBefore(pub usize);
 
 impl Execute for PasteBefore {
     fn execute(&mut self, state: &mut EditorState) {
-        paste(state, true);
+        paste(state, true, self.0);
     }
 
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -85,19 +148,32 @@ pub struct PasteOverSelection;
 
 impl Execute for PasteOverSelection {
     fn execute(&mut self, state: &mut EditorState) {
+        state.capture();
         if let Some(selection) = state.selection.take() {
-            state.capture();
             state.clamp_column();
             let _ = delete_selection(state, &selection);
 
             // In single-line mode, replace newlines with spaces
-            let text = state.clip.get_text();
+            let (text, kind) = state.clip.get_text_with_kind();
+            if selection.block_mode && kind == RegisterKind::Blockwise {
+                let col = state.cursor.col;
+                paste_block(state, &text, col);
+                return;
+            }
             let text = if state.view.single_line {
                 text.replace('\n', " ").replace('\r', "")
+            } else if kind == RegisterKind::Linewise && !selection.line_mode {
+                // Lines replacing part of a line go on lines of their own.
+                format!("\n{text}\n")
             } else {
                 text
             };
-            insert_str(&mut state.lines, &mut state.cursor, &text);
+            insert_str(
+                &mut state.lines,
+                &mut state.changes,
+                &mut state.cursor,
+                &text,
+            );
         }
     }
 
@@ -111,9 +187,16 @@ pub struct CopySelection;
 
 impl Execute for CopySelection {
     fn execute(&mut self, state: &mut EditorState) {
-        if let Some(s) = &state.selection {
-            state.clip.set_text(s.copy_from(&state.lines).into());
-            state.selection = None;
+        if let Some(s) = state.selection.take() {
+            state
+                .clip
+                .set_yanked(s.copy_from(&state.lines).into(), s.kind());
+            // Like Vim, the cursor goes to the start of the yanked text.
+            state.cursor = if s.block_mode {
+                s.block_corners().0
+            } else {
+                s.start()
+            };
         }
     }
 }
@@ -124,12 +207,31 @@ pub struct CopyLine;
 impl Execute for CopyLine {
     fn execute(&mut self, state: &mut EditorState) {
         if let Some(line) = state.lines.get(RowIndex::new(state.cursor.row)) {
-            let text = String::from('\n') + &line.iter().collect::<String>();
-            state.clip.set_text(text);
+            let text = line.iter().collect::<String>();
+            state.clip.set_yanked(text, RegisterKind::Linewise);
         }
     }
 }
 
+/// Selects the register used by the next yank, delete or paste (Vim `"x`).
+///
+/// The register name is `None` until the key handler supplies the next
+/// keystroke via [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct SelectRegister(pub Option<char>);
+
+impl Execute for SelectRegister {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(name) = self.0 {
+            state.clip.select(name);
+        }
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::actions::Undo;
@@ -152,19 +254,39 @@ mod tests {
         state.selection = Some(selection);
 
         CopySelection.execute(&mut state);
-        Paste.execute(&mut state);
+        Paste(1).execute(&mut state);
 
         assert_eq!(state.cursor, Index2::new(0, 3));
         assert_eq!(state.lines, Lines::from("HHelello World!\n\n123."));
     }
 
+    #[test]
+    fn test_copy_selection_moves_cursor_to_start() {
+        let mut state = EditorState::new(Lines::from("abc\ndef\nghi"));
+        state.set_clipboard(InternalClipboard::default());
+        state.selection = Some(Selection::new(Index2::new(0, 0), Index2::new(1, 0)).line_mode());
+        state.cursor = Index2::new(1, 0);
+
+        CopySelection.execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        let selection = Selection::new(Index2::new(0, 0), Index2::new(1, 1)).block_mode();
+        state.selection = Some(selection);
+        state.cursor = Index2::new(1, 1);
+
+        CopySelection.execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+        PasteBefore(1).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("ababc\ndedef\nghi"));
+    }
+
     #[test]
     fn test_paste_before_characterwise() {
         let mut state = test_state();
         state.selection = Some(Selection::new(Index2::new(0, 0), Index2::new(0, 2)));
 
         CopySelection.execute(&mut state);
-        PasteBefore.execute(&mut state);
+        PasteBefore(1).execute(&mut state);
 
         // `P` inserts in front of the cursor, landing on the last pasted char.
         assert_eq!(state.cursor, Index2::new(0, 2));
@@ -175,27 +297,81 @@ mod tests {
     fn test_paste_before_linewise() {
         let mut state = test_state();
         state.cursor = Index2::new(2, 1);
-        state.clip.set_text(String::from("\nnew line"));
+        state
+            .clip
+            .set_yanked(String::from("new line"), RegisterKind::Linewise);
 
-        PasteBefore.execute(&mut state);
+        PasteBefore(1).execute(&mut state);
 
         // Linewise `P` opens a new line above the current row.
         assert_eq!(state.cursor, Index2::new(2, 7));
         assert_eq!(state.lines, Lines::from("Hello World!\n\nnew line\n123."));
     }
 
+    #[test]
+    fn test_paste_linewise_below_last_line() {
+        let mut state = test_state();
+        state.cursor = Index2::new(2, 1);
+        state
+            .clip
+            .set_yanked(String::from("new line"), RegisterKind::Linewise);
+
+        Paste(1).execute(&mut state);
+
+        assert_eq!(state.cursor, Index2::new(3, 7));
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123.\nnew line"));
+    }
+
     #[test]
     fn test_paste_with_newline_into_empty_buffer() {
         let mut state = EditorState::default();
         state.set_clipboard(InternalClipboard::default());
-        state.clip.set_text("\ntext".to_string());
+        state
+            .clip
+            .set_yanked("text".to_string(), RegisterKind::Linewise);
 
-        Paste.execute(&mut state);
+        Paste(1).execute(&mut state);
 
         assert_eq!(state.cursor, Index2::new(0, 3));
         assert_eq!(state.lines, Lines::from("text"));
     }
 
+    #[test]
+    fn test_paste_charwise_text_starting_with_newline() {
+        let mut state = test_state();
+        state.cursor = Index2::new(2, 3);
+        state.clip.set_text(String::from("\nab"));
+
+        Paste(1).execute(&mut state);
+
+        // Only the kind decides whether text is pasted as lines.
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123.\nab"));
+    }
+
+    #[test]
+    fn test_copy_line_exports_clean_text() {
+        let mut state = test_state();
+
+        CopyLine.execute(&mut state);
+
+        assert_eq!(state.clip.get_text(), "Hello World!");
+        assert_eq!(state.register_kind('+'), Some(RegisterKind::Linewise));
+    }
+
+    #[test]
+    fn test_paste_blockwise() {
+        let mut state = EditorState::new(Lines::from("abc\nd"));
+        state.set_clipboard(InternalClipboard::default());
+        state
+            .clip
+            .set_yanked(String::from("12\n34\n56"), RegisterKind::Blockwise);
+
+        Paste(1).execute(&mut state);
+
+        assert_eq!(state.lines, Lines::from("a12bc\nd34\n 56"));
+        assert_eq!(state.cursor, Index2::new(0, 1));
+    }
+
     #[test]
     fn test_paste_over_selection() {
         let mut state = test_state();
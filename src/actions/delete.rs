@@ -1,14 +1,18 @@
+use std::ops::RangeInclusive;
+
 use jagged::index::RowIndex;
 
-use super::Execute;
+use super::operator::{Operate, Operator};
+use super::{Execute, SwitchMode};
 use crate::{
-    actions::motion::{find_char_forward, CharacterClass},
-    clipboard::ClipboardTrait,
+    actions::motion::{find_char_forward, CharacterClass, FindBackward, TillBackward},
+    clipboard::{RegisterKind, Registers},
     helper::{
-        is_out_of_bounds, max_col_insert, max_col_normal, skip_whitespace, skip_whitespace_rev,
+        is_out_of_bounds, map_chars, max_col_insert, max_col_normal, remove_range, skip_whitespace,
+        skip_whitespace_rev, text_in_range,
     },
-    state::selection::Selection,
-    EditorState, Index2, Lines,
+    state::{changes::ChangeLog, selection::Selection},
+    EditorMode, EditorState, Index2, Lines,
 };
 
 /// Deletes a character at the current cursor position. Does not
@@ -29,7 +33,8 @@ impl Execute for RemoveChar {
                 return;
             }
 
-            let _ = lines.remove(*index);
+            let end = Index2::new(index.row, index.col + 1);
+            let _ = state.changes.replace(lines, *index, end, "");
             index.col = index.col.min(
                 lines
                     .len_col(index.row)
@@ -42,6 +47,10 @@ impl Execute for RemoveChar {
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 /// Replaces the character under the cursor with a given character.
@@ -56,14 +65,110 @@ impl Execute for ReplaceChar {
             return;
         }
         state.capture();
-        if let Some(ch) = state.lines.get_mut(index) {
-            *ch = self.0;
+        let cols = index.col..index.col + 1;
+        let ch = self.0;
+        map_chars(
+            &mut state.lines,
+            &mut state.changes,
+            index.row,
+            cols,
+            |_| ch,
+        );
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+}
+
+/// Replaces `count` characters from the cursor on with a given character
+/// (Vim `r`), or every selected character in visual mode. Does nothing if
+/// fewer than `count` characters are left in the line.
+///
+/// The character is `None` until the key handler supplies the next keystroke
+/// via [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct ReplaceChars {
+    pub ch: Option<char>,
+    pub count: usize,
+}
+
+impl ReplaceChars {
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self { ch: None, count }
+    }
+}
+
+impl Execute for ReplaceChars {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(ch) = self.ch else {
+            return;
         };
+        if state.mode.is_visual() {
+            state.capture();
+            if let Some(selection) = state.selection.take() {
+                map_selection(state, &selection, |_| ch);
+            }
+            SwitchMode(EditorMode::Normal).execute(state);
+            return;
+        }
+
+        let index = state.cursor;
+        let len_col = state.lines.len_col(index.row).unwrap_or_default();
+        if self.count == 0 || index.col + self.count > len_col {
+            return;
+        }
+        state.capture();
+        let cols = index.col..index.col + self.count;
+        map_chars(
+            &mut state.lines,
+            &mut state.changes,
+            index.row,
+            cols,
+            |_| ch,
+        );
+        state.cursor.col = index.col + self.count - 1;
     }
 
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.ch)
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+}
+
+/// Replaces every character of a selection by `f` of it, and moves the
+/// cursor to its start.
+pub(crate) fn map_selection(
+    state: &mut EditorState,
+    selection: &Selection,
+    f: impl Fn(char) -> char,
+) {
+    let (top, bottom) = selection.block_corners();
+    for row in top.row..=bottom.row {
+        let len_col = state.lines.len_col(row).unwrap_or_default();
+        let contains = |col: &usize| selection.contains(&Index2::new(row, *col));
+        let Some(from) = (0..len_col).find(contains) else {
+            continue;
+        };
+        let to = (0..len_col).rfind(contains).unwrap_or(from) + 1;
+        map_chars(&mut state.lines, &mut state.changes, row, from..to, &f);
+    }
+    state.cursor = if selection.block_mode {
+        top
+    } else {
+        selection.start()
+    };
+    if selection.line_mode {
+        state.cursor.col = 0;
+    }
 }
 
 /// Deletes a character to the left of the current cursor. Deletes
@@ -76,16 +181,20 @@ impl Execute for DeleteChar {
     fn execute(&mut self, state: &mut EditorState) {
         state.capture();
         for _ in 0..self.0 {
-            delete_char(&mut state.lines, &mut state.cursor);
+            delete_char(&mut state.lines, &mut state.changes, &mut state.cursor);
         }
     }
 
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
-fn delete_char(lines: &mut Lines, index: &mut Index2) {
+fn delete_char(lines: &mut Lines, changes: &mut ChangeLog, index: &mut Index2) {
     fn move_left(lines: &Lines, index: &mut Index2) {
         if index.col > 0 {
             index.col -= 1;
@@ -104,16 +213,12 @@ fn delete_char(lines: &mut Lines, index: &mut Index2) {
         index.col = len_col;
     }
 
-    if index.col == 0 {
-        let mut rest = lines.split_off(*index);
-        move_left(lines, index);
-        lines.merge(&mut rest);
-    } else {
-        let max_col = max_col_insert(lines, index);
-        index.col = index.col.min(max_col);
-        move_left(lines, index);
-        let _ = lines.remove(*index);
+    if index.col > 0 {
+        index.col = index.col.min(max_col_insert(lines, index));
     }
+    let end = *index;
+    move_left(lines, index);
+    let _ = changes.replace(lines, *index, end, "");
 }
 
 /// Deletes the character at the current cursor position.
@@ -127,16 +232,20 @@ impl Execute for DeleteCharForward {
         state.capture();
         state.clamp_column();
         for _ in 0..self.0 {
-            delete_char_forward(&mut state.lines, &mut state.cursor);
+            delete_char_forward(&mut state.lines, &mut state.changes, &mut state.cursor);
         }
     }
 
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
-fn delete_char_forward(lines: &mut Lines, index: &mut Index2) {
+fn delete_char_forward(lines: &mut Lines, changes: &mut ChangeLog, index: &mut Index2) {
     let Some(row) = lines.get(RowIndex::new(index.row)) else {
         return;
     };
@@ -149,11 +258,13 @@ fn delete_char_forward(lines: &mut Lines, index: &mut Index2) {
             return;
         }
 
-        lines.join_lines(index.row);
+        let start = Index2::new(index.row, row_len);
+        let _ = changes.replace(lines, start, Index2::new(index.row + 1, 0), "");
         return;
     }
 
-    let _ = lines.remove(*index);
+    let end = Index2::new(index.row, index.col + 1);
+    let _ = changes.replace(lines, *index, end, "");
 }
 
 /// Deletes from cursor to the end of the current word (Emacs Alt+d).
@@ -174,6 +285,10 @@ impl Execute for DeleteWordForward {
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 fn delete_motion_forward<F>(
@@ -189,7 +304,8 @@ fn delete_motion_forward<F>(
     // On an empty line there is nothing to delete
     if len_col == 0 {
         if start.row + 1 < state.lines.len() {
-            state.lines.join_lines(start.row);
+            let end = Index2::new(start.row + 1, 0);
+            let _ = state.changes.replace(&mut state.lines, start, end, "");
         }
         return;
     }
@@ -214,7 +330,13 @@ fn delete_motion_forward<F>(
     if skip_trailing_whitespace {
         skip_whitespace(&state.lines, &mut end);
     }
-    delete_range(&mut state.lines, start, end, &mut state.clip);
+    delete_range(
+        &mut state.lines,
+        &mut state.changes,
+        start,
+        end,
+        &mut state.clip,
+    );
 
     state.cursor.col = state
         .cursor
@@ -250,6 +372,10 @@ impl Execute for DeleteBigWordForward {
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 fn delete_big_word_forward(state: &mut EditorState) {
@@ -280,6 +406,14 @@ impl Execute for DeleteWordEnd {
             delete_word_end(state);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
 }
 
 fn delete_word_end(state: &mut EditorState) {
@@ -306,6 +440,14 @@ impl Execute for DeleteBigWordEnd {
             delete_big_word_end(state);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
 }
 
 fn delete_big_word_end(state: &mut EditorState) {
@@ -338,6 +480,10 @@ impl Execute for DeleteWordBackward {
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 fn delete_word_backward(state: &mut EditorState) {
@@ -350,7 +496,10 @@ fn delete_word_backward(state: &mut EditorState) {
     if end.col == 0 {
         state.cursor.row -= 1;
         state.cursor.col = state.lines.len_col(state.cursor.row).unwrap_or(0);
-        state.lines.join_lines(state.cursor.row);
+        let end = Index2::new(state.cursor.row + 1, 0);
+        let _ = state
+            .changes
+            .replace(&mut state.lines, state.cursor, end, "");
         return;
     }
 
@@ -369,29 +518,34 @@ fn delete_word_backward(state: &mut EditorState) {
         start = idx;
     }
 
-    delete_range(&mut state.lines, start, end, &mut state.clip);
+    delete_range(
+        &mut state.lines,
+        &mut state.changes,
+        start,
+        end,
+        &mut state.clip,
+    );
     state.cursor = start;
 }
 
 fn delete_range(
     lines: &mut Lines,
+    changes: &mut ChangeLog,
     start: Index2,
     end: Index2,
-    clip: &mut crate::clipboard::Clipboard,
+    clip: &mut Registers,
 ) {
     if start.row != end.row || start.col >= end.col {
         return;
     }
 
-    let Some(row) = lines.get_mut(RowIndex::new(start.row)) else {
+    let Some(len_col) = lines.len_col(start.row) else {
         return;
     };
-
-    let end_col = end.col.min(row.len());
-    let start_col = start.col.min(end_col);
-
-    let deleted: String = row.drain(start_col..end_col).collect();
-    clip.set_text(deleted);
+    let end = Index2::new(end.row, end.col.min(len_col));
+    if let Some(deleted) = changes.replace(lines, start, end, "") {
+        clip.set_deleted(deleted, RegisterKind::Charwise);
+    }
 }
 
 /// Deletes the current line.
@@ -405,9 +559,12 @@ impl Execute for DeleteLine {
             if state.cursor.row >= state.lines.len() {
                 break;
             }
-            let row_index = RowIndex::new(state.cursor.row);
-            let deleted_line = state.lines.remove(row_index).iter().collect::<String>();
-            state.clip.set_text(String::from('\n') + &deleted_line);
+            let row = state.cursor.row;
+            let Some(deleted_line) = delete_rows(&mut state.lines, &mut state.changes, row..=row)
+            else {
+                break;
+            };
+            state.clip.set_deleted(deleted_line, RegisterKind::Linewise);
             state.cursor.col = 0;
             state.cursor.row = state.cursor.row.min(state.lines.len().saturating_sub(1));
         }
@@ -416,6 +573,37 @@ impl Execute for DeleteLine {
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Deletes rows with their line breaks and returns their text. Deleting
+/// all rows leaves no row.
+pub(crate) fn delete_rows(
+    lines: &mut Lines,
+    changes: &mut ChangeLog,
+    rows: RangeInclusive<usize>,
+) -> Option<String> {
+    let (first, last) = (*rows.start(), *rows.end());
+    let len_col = lines.len_col(last)?;
+    let (start, end) = if last + 1 < lines.len() {
+        (Index2::new(first, 0), Index2::new(last + 1, 0))
+    } else if let Some(above) = first.checked_sub(1) {
+        let above_len = lines.len_col(above).unwrap_or_default();
+        (Index2::new(above, above_len), Index2::new(last, len_col))
+    } else {
+        (Index2::new(first, 0), Index2::new(last, len_col))
+    };
+    let all_rows = first == 0 && last + 1 == lines.len();
+    let text = text_in_range(lines, Index2::new(first, 0), Index2::new(last, len_col));
+    changes.replace(lines, start, end, "")?;
+    if all_rows {
+        // Like before any text was added, the buffer holds no row.
+        let _ = lines.remove(RowIndex::new(0));
+    }
+    Some(text)
 }
 
 /// Deletes from the current cursor position to the first non-whitespace character of the line
@@ -426,23 +614,26 @@ impl Execute for DeleteToFirstCharOfLine {
     fn execute(&mut self, state: &mut EditorState) {
         state.capture();
 
-        let row_index = RowIndex::new(state.cursor.row);
-        let Some(row) = state.lines.get_mut(row_index) else {
+        let Index2 { row, col } = state.cursor;
+        let Some(line) = state.lines.get(RowIndex::new(row)) else {
             return;
         };
 
-        let col = state.cursor.col;
-
-        let first_char = row
+        let first_char = line
             .iter()
             .position(|c| !c.is_whitespace())
-            .unwrap_or(row.len());
+            .unwrap_or(line.len());
 
         let anchor = if col <= first_char { 0 } else { first_char };
 
-        if anchor < col && col <= row.len() {
-            let deleted = row.drain(anchor..col).collect();
-            state.clip.set_text(deleted);
+        if anchor < col && col <= line.len() {
+            let start = Index2::new(row, anchor);
+            let deleted = state
+                .changes
+                .replace(&mut state.lines, start, state.cursor, "");
+            if let Some(deleted) = deleted {
+                state.clip.set_deleted(deleted, RegisterKind::Charwise);
+            }
         }
 
         state.cursor.col = anchor;
@@ -463,12 +654,16 @@ impl Execute for DeleteToEndOfLine {
             return;
         }
         state.capture();
-        let Some(row) = state.lines.get_mut(RowIndex::new(state.cursor.row)) else {
+        let row = state.cursor.row;
+        let end = Index2::new(row, state.lines.len_col(row).unwrap_or_default());
+        let Some(deleted) = state
+            .changes
+            .replace(&mut state.lines, state.cursor, end, "")
+        else {
             return;
         };
-        let deleted_chars = row.drain(state.cursor.col..);
         state.cursor.col = state.cursor.col.saturating_sub(1);
-        state.clip.set_text(deleted_chars.collect());
+        state.clip.set_deleted(deleted, RegisterKind::Charwise);
     }
 
     fn is_repeatable(&self) -> bool {
@@ -494,11 +689,14 @@ impl Execute for DeleteFindForward {
             return;
         };
         state.capture();
-        let Some(row) = state.lines.get_mut(RowIndex::new(state.cursor.row)) else {
+        let end = Index2::new(state.cursor.row, target + 1);
+        let Some(deleted) = state
+            .changes
+            .replace(&mut state.lines, state.cursor, end, "")
+        else {
             return;
         };
-        let deleted = row.drain(state.cursor.col..=target).collect();
-        state.clip.set_text(deleted);
+        state.clip.set_deleted(deleted, RegisterKind::Charwise);
         state.clamp_column();
     }
 
@@ -529,11 +727,14 @@ impl Execute for DeleteTillForward {
             return;
         };
         state.capture();
-        let Some(row) = state.lines.get_mut(RowIndex::new(state.cursor.row)) else {
+        let end = Index2::new(state.cursor.row, target);
+        let Some(deleted) = state
+            .changes
+            .replace(&mut state.lines, state.cursor, end, "")
+        else {
             return;
         };
-        let deleted = row.drain(state.cursor.col..target).collect();
-        state.clip.set_text(deleted);
+        state.clip.set_deleted(deleted, RegisterKind::Charwise);
         state.clamp_column();
     }
 
@@ -546,16 +747,76 @@ impl Execute for DeleteTillForward {
     }
 }
 
+/// Deletes from the previous occurrence of a character on the current line up
+/// to (but not including) the cursor (Vim `dF<char>`). Does nothing if the
+/// character is not found.
+///
+/// The target is `None` until the key handler supplies the next keystroke via
+/// [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct DeleteFindBackward(pub Option<char>);
+
+impl Execute for DeleteFindBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        if self.0.is_some() {
+            Operate::new(Operator::Delete)
+                .with_motion(FindBackward {
+                    target: self.0,
+                    count: 1,
+                })
+                .execute(state);
+        }
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
+/// Deletes from just after the previous occurrence of a character on the
+/// current line up to (but not including) the cursor (Vim `dT<char>`). Does
+/// nothing if the character is not found.
+///
+/// The target is `None` until the key handler supplies the next keystroke via
+/// [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct DeleteTillBackward(pub Option<char>);
+
+impl Execute for DeleteTillBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        if self.0.is_some() {
+            Operate::new(Operator::Delete)
+                .with_motion(TillBackward {
+                    target: self.0,
+                    count: 1,
+                })
+                .execute(state);
+        }
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
 /// Deletes the current selection.
 #[derive(Clone, Debug)]
 pub struct DeleteSelection;
 
 impl Execute for DeleteSelection {
     fn execute(&mut self, state: &mut EditorState) {
+        state.capture();
         if let Some(selection) = state.selection.take() {
-            state.capture();
             let drained = delete_selection(state, &selection);
-            state.clip.set_text(drained.into());
+            state.clip.set_deleted(drained, selection.kind());
         }
         state.selection = None;
     }
@@ -565,10 +826,36 @@ impl Execute for DeleteSelection {
     }
 }
 
-pub(crate) fn delete_selection(state: &mut EditorState, selection: &Selection) -> Lines {
-    state.cursor = selection.start();
+/// Deletes a selection and returns its text.
+pub(crate) fn delete_selection(state: &mut EditorState, selection: &Selection) -> String {
+    state.cursor = if selection.block_mode {
+        selection.block_corners().0
+    } else {
+        selection.start()
+    };
     state.clamp_column();
-    selection.extract_from(&mut state.lines)
+    let (start, end) = (selection.start(), selection.end());
+    if selection.line_mode {
+        let rows = start.row..=end.row;
+        return delete_rows(&mut state.lines, &mut state.changes, rows).unwrap_or_default();
+    }
+    if selection.block_mode {
+        let mut block = Vec::new();
+        for (row, cols) in selection.block_ranges(&state.lines) {
+            let start = Index2::new(row, cols.start);
+            let end = Index2::new(row, cols.end);
+            let text = state.changes.replace(&mut state.lines, start, end, "");
+            block.push(text.unwrap_or_default());
+        }
+        return block.join("\n");
+    }
+    // A selection past the end of a row includes its line break.
+    let end = if end.col < state.lines.len_col(end.row).unwrap_or_default() {
+        Index2::new(end.row, end.col + 1)
+    } else {
+        Index2::new(end.row + 1, 0)
+    };
+    remove_range(&mut state.lines, &mut state.changes, start, end)
 }
 
 /// Joins line below to the current line.
@@ -581,7 +868,11 @@ impl Execute for JoinLineWithLineBelow {
             return;
         }
         state.capture();
-        state.lines.join_lines(state.cursor.row);
+        let row = state.cursor.row;
+        let len_col = state.lines.len_col(row).unwrap_or_default();
+        let start = Index2::new(row, len_col);
+        let end = Index2::new(row + 1, 0);
+        let _ = state.changes.replace(&mut state.lines, start, end, "");
     }
 
     fn is_repeatable(&self) -> bool {
@@ -638,6 +929,22 @@ mod tests {
         assert_eq!(state.lines, Lines::from("Hellx World!\n\n123."));
     }
 
+    #[test]
+    fn test_replace_chars_in_block() {
+        let mut state = EditorState::new(Lines::from("abcd\nab\nabcd"));
+        state.mode = EditorMode::VisualBlock;
+        state.selection = Some(Selection::new(Index2::new(0, 1), Index2::new(2, 2)).block_mode());
+
+        ReplaceChars {
+            ch: Some('x'),
+            count: 1,
+        }
+        .execute(&mut state);
+        assert_eq!(state.lines, Lines::from("axxd\nax\naxxd"));
+        assert_eq!(state.cursor, Index2::new(0, 1));
+        assert_eq!(state.mode, EditorMode::Normal);
+    }
+
     #[test]
     fn test_delete_char() {
         let mut state = test_state();
@@ -1,9 +1,11 @@
 use jagged::index::RowIndex;
 
-use super::Execute;
+use super::{Execute, SwitchMode};
 use crate::{
-    helper::{insert_char, line_break},
-    EditorState,
+    helper::{indentation, insert_char, line_break},
+    state::indent::{closer, opener},
+    state::selection::Selection,
+    EditorMode, EditorState, Index2,
 };
 
 /// Inserts a single character at the current cursor position.
@@ -18,12 +20,21 @@ impl Execute for InsertChar {
         if state.view.single_line && matches!(self.0, '\n' | '\r') {
             return;
         }
-        insert_char(&mut state.lines, &mut state.cursor, self.0, false);
+        insert_char(
+            &mut state.lines,
+            &mut state.changes,
+            &mut state.cursor,
+            self.0,
+            false,
+        );
 
         // Capture insert session for the dot-repeat command
         if let Some(buffer) = &mut state.insert_recording {
             buffer.push(self.0);
         }
+        if let Some(block) = &mut state.block_insert {
+            block.text.push(self.0);
+        }
     }
 
     fn is_repeatable(&self) -> bool {
@@ -31,6 +42,81 @@ impl Execute for InsertChar {
     }
 }
 
+/// Overwrites the character at the current cursor position, or appends it
+/// at the end of the line. Intended to be called in replace mode, where
+/// [`RestoreChar`] brings the overwritten characters back.
+#[derive(Clone, Debug, Copy)]
+pub struct OverwriteChar(pub char);
+
+impl Execute for OverwriteChar {
+    fn execute(&mut self, state: &mut EditorState) {
+        if matches!(self.0, '\n' | '\r') {
+            return;
+        }
+        let index = state.cursor;
+        let rejections = state.changes.rejections;
+        let original = state.lines.get(index).copied();
+        if original.is_some() {
+            let end = Index2::new(index.row, index.col + 1);
+            let text = self.0.to_string();
+            let _ = state.changes.replace(&mut state.lines, index, end, &text);
+            state.cursor.col += 1;
+        } else {
+            insert_char(
+                &mut state.lines,
+                &mut state.changes,
+                &mut state.cursor,
+                self.0,
+                false,
+            );
+        }
+        if state.changes.rejections == rejections {
+            state.replaced.push((index, original));
+        }
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+}
+
+/// Moves the cursor one character to the left and restores the character
+/// that [`OverwriteChar`] replaced there, or removes the one it appended.
+/// Characters from before the replace session are only moved over.
+/// Intended to be called in replace mode.
+#[derive(Clone, Debug, Copy)]
+pub struct RestoreChar(pub usize);
+
+impl Execute for RestoreChar {
+    fn execute(&mut self, state: &mut EditorState) {
+        for _ in 0..self.0 {
+            if state.cursor.col == 0 {
+                return;
+            }
+            state.cursor.col -= 1;
+            let index = state.cursor;
+            let Some(&(pos, original)) = state.replaced.last() else {
+                continue;
+            };
+            if pos != index {
+                continue;
+            }
+            state.replaced.pop();
+            let end = Index2::new(index.row, index.col + 1);
+            let text = original.map(String::from).unwrap_or_default();
+            let _ = state.changes.replace(&mut state.lines, index, end, &text);
+        }
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
 /// Inserts a newline at the current cursor position.
 ///
 /// In single-line mode, this action is ignored.
@@ -46,13 +132,18 @@ impl Execute for LineBreak {
             state.lines.push(Vec::new());
         }
         for _ in 0..self.0 {
-            line_break(&mut state.lines, &mut state.cursor);
+            line_break(&mut state.lines, &mut state.changes, &mut state.cursor);
+            indent_line_break(state);
         }
     }
 
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 /// Appends a newline below the current cursor position.
@@ -72,10 +163,21 @@ impl Execute for AppendNewline {
             if !state.lines.is_empty() {
                 state.cursor.row += 1;
             }
-            if state.cursor.row < state.lines.len() {
-                state.lines.insert(RowIndex::new(state.cursor.row), vec![]);
+            let row = state.cursor.row;
+            if row < state.lines.len() {
+                let start = Index2::new(row, 0);
+                let _ = state.changes.insert(&mut state.lines, start, "\n");
+            } else if !state.lines.is_empty() {
+                // Below the last row, the line break is the new row itself.
+                let _ = state
+                    .changes
+                    .insert(&mut state.lines, Index2::new(row, 0), "");
             } else {
-                state.lines.push(vec![]);
+                state.lines.push(Vec::new());
+            }
+            if let Some(above) = row.checked_sub(1) {
+                let indent = state.indent_below(above);
+                indent_row(state, row, indent);
             }
         }
     }
@@ -83,6 +185,10 @@ impl Execute for AppendNewline {
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
 }
 
 /// Appends a newline at the current cursor position.
@@ -97,14 +203,63 @@ impl Execute for InsertNewline {
             return;
         }
         state.cursor.col = 0;
+        let row = state.cursor.row;
         for _ in 0..self.0 {
-            state.lines.insert(RowIndex::new(state.cursor.row), vec![]);
+            let _ = state
+                .changes
+                .insert(&mut state.lines, Index2::new(row, 0), "\n");
+            let indent = state.indent_above(row + 1);
+            indent_row(state, row, indent);
         }
     }
 
     fn is_repeatable(&self) -> bool {
         true
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Auto-indents the row started by a line break. With smart indent, a closing
+/// bracket right after the cursor gets a row of its own, so that the cursor
+/// ends up in an indented empty row between the brackets.
+fn indent_line_break(state: &mut EditorState) {
+    let Some(above) = state.cursor.row.checked_sub(1) else {
+        return;
+    };
+    let indent = state.indent_below(above);
+    if !indent_row(state, state.cursor.row, indent) || !state.indent.smart_indent {
+        return;
+    }
+
+    let Index2 { row, col } = state.cursor;
+    let closer = state
+        .lines
+        .get(RowIndex::new(above))
+        .and_then(|line| opener(line))
+        .and_then(closer);
+    if closer.is_some() && state.lines.get(state.cursor).copied() == closer {
+        let width = state.indent_width(above);
+        line_break(&mut state.lines, &mut state.changes, &mut state.cursor);
+        state.set_indent(row + 1, width);
+        state.cursor = Index2::new(row, col);
+    }
+}
+
+/// Replaces the indentation of a new row by the auto-indent, if any, and
+/// moves the cursor behind it. Returns whether the row was auto-indented.
+fn indent_row(state: &mut EditorState, row: usize, indent: Option<Vec<char>>) -> bool {
+    let Some(indent) = indent else {
+        return false;
+    };
+    state.cursor.col = indent.len();
+    let blanks = indentation(&state.lines, row);
+    let indent: String = indent.into_iter().collect();
+    let (start, end) = (Index2::new(row, 0), Index2::new(row, blanks));
+    let _ = state.changes.replace(&mut state.lines, start, end, &indent);
+    true
 }
 
 /// Pushes a line to the back of the buffer.
@@ -114,8 +269,118 @@ pub struct PushLine<'a>(pub &'a str);
 
 impl Execute for PushLine<'_> {
     fn execute(&mut self, state: &mut EditorState) {
-        let chars: Vec<char> = self.0.chars().collect();
-        state.lines.push(chars);
+        let pos = Index2::new(state.lines.len(), 0);
+        let _ = state.changes.insert(&mut state.lines, pos, self.0);
+    }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
+}
+
+/// Inserts text in front of a visual block on every row of the block
+/// (Vim `I` in visual block mode). The text is typed on the first row and
+/// copied to the other rows when leaving insert mode.
+#[derive(Clone, Debug, Copy)]
+pub struct InsertBlock;
+
+impl Execute for InsertBlock {
+    fn execute(&mut self, state: &mut EditorState) {
+        start_block_insert(state, false);
+    }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
+}
+
+/// Appends text after a visual block on every row of the block (Vim `A` in
+/// visual block mode). Rows that end before the block are padded with spaces.
+/// A block extended with `$` appends to the end of every row instead.
+#[derive(Clone, Debug, Copy)]
+pub struct AppendBlock;
+
+impl Execute for AppendBlock {
+    fn execute(&mut self, state: &mut EditorState) {
+        start_block_insert(state, true);
+    }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
+}
+
+fn start_block_insert(state: &mut EditorState, append: bool) {
+    let Some(selection) = state.selection.take().filter(|s| s.block_mode) else {
+        return;
+    };
+    let (top_left, bottom_right) = selection.block_corners();
+    let col = if append && selection.to_line_end {
+        state.lines.len_col(top_left.row).unwrap_or_default()
+    } else if append {
+        bottom_right.col + 1
+    } else {
+        top_left.col
+    };
+    SwitchMode(EditorMode::Insert).execute(state);
+    let mut len_col = state.lines.len_col(top_left.row).unwrap_or_default();
+    if append && len_col < col {
+        let pos = Index2::new(top_left.row, len_col);
+        let padding = " ".repeat(col - len_col);
+        if state.changes.insert(&mut state.lines, pos, &padding) {
+            len_col = col;
+        }
+    }
+    state.cursor = Index2::new(top_left.row, col.min(len_col));
+    state.block_insert = Some(BlockInsert::new(&selection, col, append));
+}
+
+/// A pending insert into every row of a visual block.
+#[derive(Clone, Debug)]
+pub(crate) struct BlockInsert {
+    top: usize,
+    bottom: usize,
+    col: usize,
+    /// Appends to the end of every row instead of at `col`.
+    to_line_end: bool,
+    /// Pads rows that end before `col` instead of skipping them.
+    pad: bool,
+    /// The text typed on the first row.
+    pub(crate) text: String,
+}
+
+impl BlockInsert {
+    pub(crate) fn new(selection: &Selection, col: usize, pad: bool) -> Self {
+        let (top_left, bottom_right) = selection.block_corners();
+        Self {
+            top: top_left.row,
+            bottom: bottom_right.row,
+            col,
+            to_line_end: pad && selection.to_line_end,
+            pad,
+            text: String::new(),
+        }
+    }
+
+    /// Copies the text typed on the first row to the other rows.
+    pub(crate) fn apply(self, state: &mut EditorState) {
+        if self.text.is_empty() || state.cursor.row != self.top {
+            return;
+        }
+        for row in self.top + 1..=self.bottom {
+            let Some(len_col) = state.lines.len_col(row) else {
+                break;
+            };
+            let col = if self.to_line_end { len_col } else { self.col };
+            let text = match len_col.checked_sub(col) {
+                Some(_) => self.text.clone(),
+                None if self.pad => " ".repeat(col - len_col) + &self.text,
+                None => continue,
+            };
+            let pos = Index2::new(row, col.min(len_col));
+            let _ = state.changes.insert(&mut state.lines, pos, &text);
+        }
+        state.cursor = Index2::new(self.top, self.col);
     }
 }
 
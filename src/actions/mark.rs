@@ -0,0 +1,175 @@
+use jagged::Index2;
+
+use super::{operator::MotionKind, Execute};
+use crate::{helper::indentation, state::selection::set_selection_with_lines, EditorState};
+
+/// Sets a mark at the cursor (Vim `m{a-z}`).
+///
+/// The mark name is `None` until the key handler supplies the next keystroke
+/// via [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct SetMark(pub Option<char>);
+
+impl Execute for SetMark {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(name) = self.0.filter(|name| is_mark_name(*name)) {
+            state.set_mark(name, state.cursor);
+        }
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
+/// Moves the cursor to the position of a mark (Vim `` `a ``). The mark `` ` ``
+/// is the position before the latest jump.
+#[derive(Clone, Debug, Copy)]
+pub struct JumpToMark(pub Option<char>);
+
+impl Execute for JumpToMark {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(pos) = mark_position(state, self.0) {
+            jump(state, pos);
+        }
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
+}
+
+/// Moves the cursor to the first non-blank character in the row of a mark
+/// (Vim `'a`). The mark `'` is the position before the latest jump.
+#[derive(Clone, Debug, Copy)]
+pub struct JumpToMarkLine(pub Option<char>);
+
+impl Execute for JumpToMarkLine {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(pos) = mark_position(state, self.0) {
+            let col = indentation(&state.lines, pos.row);
+            jump(state, Index2::new(pos.row, col));
+        }
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
+}
+
+/// Moves the cursor to an older position in the jump list (Vim `Ctrl+o`).
+#[derive(Clone, Debug, Copy)]
+pub struct JumpBackward(pub usize);
+
+impl Execute for JumpBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(pos) = state.changes.marks.back(state.cursor, self.0) {
+            move_to(state, pos);
+        }
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Moves the cursor to a newer position in the jump list (Vim `Ctrl+i`).
+#[derive(Clone, Debug, Copy)]
+pub struct JumpForward(pub usize);
+
+impl Execute for JumpForward {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(pos) = state.changes.marks.forward(self.0) {
+            move_to(state, pos);
+        }
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Returns whether a mark can be set with `m`.
+fn is_mark_name(name: char) -> bool {
+    name.is_ascii_alphabetic() || name == '\'' || name == '`'
+}
+
+/// Returns the position of a mark, within the text.
+fn mark_position(state: &EditorState, name: Option<char>) -> Option<Index2> {
+    let pos = state.mark(name?)?;
+    let row = pos.row.min(state.lines.last_row_index());
+    Some(Index2::new(row, pos.col))
+}
+
+/// Adds the cursor to the jump list and moves it to `pos`.
+fn jump(state: &mut EditorState, pos: Index2) {
+    state.push_jump();
+    move_to(state, pos);
+}
+
+/// Moves the cursor to `pos`, extending the selection in visual mode.
+fn move_to(state: &mut EditorState, pos: Index2) {
+    state.cursor = pos;
+    state.clamp_column();
+    if state.mode.is_visual() {
+        set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{MoveToLastRow, Operate, Operator};
+    use crate::Lines;
+
+    #[test]
+    fn test_jump_to_mark() {
+        let mut state = EditorState::new(Lines::from("one\n  two\nthree"));
+        state.cursor = Index2::new(1, 4);
+        state.execute(SetMark(Some('a')));
+        state.cursor = Index2::new(2, 1);
+
+        state.execute(JumpToMark(Some('a')));
+        assert_eq!(state.cursor, Index2::new(1, 4));
+
+        state.execute(JumpToMarkLine(Some('`')));
+        assert_eq!(state.cursor, Index2::new(2, 0));
+
+        state.execute(JumpToMarkLine(Some('a')));
+        assert_eq!(state.cursor, Index2::new(1, 2));
+
+        // Unknown marks do not move the cursor.
+        state.execute(JumpToMark(Some('b')));
+        assert_eq!(state.cursor, Index2::new(1, 2));
+    }
+
+    #[test]
+    fn test_jump_list() {
+        let mut state = EditorState::new(Lines::from("one\ntwo\nthree"));
+        state.cursor = Index2::new(0, 2);
+        state.execute(MoveToLastRow());
+
+        state.execute(JumpBackward(1));
+        assert_eq!(state.cursor, Index2::new(0, 2));
+        state.execute(JumpForward(1));
+        assert_eq!(state.cursor, Index2::new(2, 2));
+    }
+
+    #[test]
+    fn test_delete_to_mark() {
+        let mut state = EditorState::new(Lines::from("one\ntwo\nthree"));
+        state.set_mark('a', Index2::new(1, 0));
+        state.cursor = Index2::new(2, 2);
+
+        state.execute(Operate::new(Operator::Delete).with_motion(JumpToMarkLine(Some('a'))));
+        assert_eq!(state.lines, Lines::from("one"));
+    }
+}
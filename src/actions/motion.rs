@@ -4,12 +4,12 @@ use crate::{
     helper::{find_matching_bracket, skip_empty_lines},
     state::selection::set_selection_with_lines,
 };
-use jagged::Index2;
+use jagged::{index::RowIndex, Index2};
 
-use super::Execute;
+use super::{operator::MotionKind, Execute};
 use crate::{
     helper::{max_col, max_col_normal, skip_whitespace, skip_whitespace_rev},
-    EditorMode, EditorState,
+    EditorState,
 };
 
 #[derive(Clone, Debug, Copy)]
@@ -23,10 +23,18 @@ impl Execute for MoveForward {
             }
             state.cursor.col += 1;
         }
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -44,10 +52,18 @@ impl Execute for MoveBackward {
             }
             state.cursor.col = state.cursor.col.saturating_sub(1);
         }
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -61,10 +77,18 @@ impl Execute for MoveUp {
             }
             state.cursor.row = state.cursor.row.saturating_sub(1);
         }
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -78,10 +102,18 @@ impl Execute for MoveDown {
             }
             state.cursor.row += 1;
         }
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
 }
 
 /// Move one word forward. Breaks on the first character that is not of
@@ -99,23 +131,36 @@ impl Execute for MoveWordForward {
         state.clamp_column();
 
         for _ in 0..self.0 {
-            move_word_forward(state);
+            move_word_forward(state, CharacterClass::word);
         }
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
 }
 
-fn move_word_forward(state: &mut EditorState) {
-    let start_char_class = CharacterClass::from(state.lines.get(state.cursor));
+fn move_word_forward(state: &mut EditorState, class: fn(Option<&char>) -> CharacterClass) {
+    let start_char_class = class(state.lines.get(state.cursor));
 
     let start_index = match (
         state.lines.is_last_col(state.cursor),
         state.lines.is_last_row(state.cursor),
     ) {
-        (true, true) => return,
+        // Past the last word, visual mode moves behind the last character,
+        // so that an operator covers the rest of the buffer (`2dw`).
+        (true, true) => {
+            state.cursor.col = max_col(&state.lines, &state.cursor, state.mode);
+            return;
+        }
         (true, false) => {
             state.cursor = Index2::new(state.cursor.row.saturating_add(1), 0);
             return;
@@ -124,7 +169,7 @@ fn move_word_forward(state: &mut EditorState) {
     };
 
     for (next_char, index) in state.lines.iter().from(start_index) {
-        if CharacterClass::from(next_char) != start_char_class {
+        if class(next_char) != start_char_class {
             state.cursor = index;
             skip_whitespace(&state.lines, &mut state.cursor);
             return;
@@ -147,16 +192,27 @@ impl Execute for MoveWordForwardToEndOfWord {
         state.clamp_column();
 
         for _ in 0..self.0 {
-            move_word_forward_to_end_of_word(state);
+            move_word_forward_to_end_of_word(state, CharacterClass::word);
         }
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
 }
 
-fn move_word_forward_to_end_of_word(state: &mut EditorState) {
+fn move_word_forward_to_end_of_word(
+    state: &mut EditorState,
+    class: fn(Option<&char>) -> CharacterClass,
+) {
     let mut start_index = match (
         state.lines.is_last_col(state.cursor),
         state.lines.is_last_row(state.cursor),
@@ -167,11 +223,11 @@ fn move_word_forward_to_end_of_word(state: &mut EditorState) {
     };
     skip_empty_lines(&state.lines, &mut start_index.row);
     skip_whitespace(&state.lines, &mut start_index);
-    let start_char_class = CharacterClass::from(state.lines.get(start_index));
+    let start_char_class = class(state.lines.get(start_index));
 
     for (next_char, index) in state.lines.iter().from(start_index) {
         // Break loop if characters don't belong to the same class
-        if CharacterClass::from(next_char) != start_char_class {
+        if class(next_char) != start_char_class {
             break;
         }
         state.cursor = index;
@@ -201,16 +257,24 @@ impl Execute for MoveWordBackward {
         }
 
         for _ in 0..self.0 {
-            move_word_backward(state);
+            move_word_backward(state, CharacterClass::word);
         }
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
 }
 
-fn move_word_backward(state: &mut EditorState) {
+fn move_word_backward(state: &mut EditorState, class: fn(Option<&char>) -> CharacterClass) {
     let mut start_index = state.cursor;
     if start_index.row == 0 && start_index.col == 0 {
         return;
@@ -224,7 +288,7 @@ fn move_word_backward(state: &mut EditorState) {
 
     start_index.col = start_index.col.saturating_sub(1);
     skip_whitespace_rev(&state.lines, &mut start_index);
-    let start_char_class = CharacterClass::from(state.lines.get(start_index));
+    let start_char_class = class(state.lines.get(start_index));
 
     for (next_char, i) in state.lines.iter().from(start_index).rev() {
         // Break loop if it reaches the start of the line
@@ -233,7 +297,7 @@ fn move_word_backward(state: &mut EditorState) {
             break;
         }
         // Break loop if characters don't belong to the same class
-        if CharacterClass::from(next_char) != start_char_class {
+        if class(next_char) != start_char_class {
             break;
         }
         start_index = i;
@@ -242,6 +306,100 @@ fn move_word_backward(state: &mut EditorState) {
     state.cursor = start_index;
 }
 
+/// Move one WORD forward (Vim `W`). A WORD is any sequence of
+/// non-whitespace characters.
+#[derive(Clone, Debug, Copy)]
+pub struct MoveBigWordForward(pub usize);
+
+impl Execute for MoveBigWordForward {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.lines.is_empty() {
+            return;
+        }
+
+        state.clamp_column();
+
+        for _ in 0..self.0 {
+            move_word_forward(state, CharacterClass::big);
+        }
+
+        if state.mode.is_visual() {
+            set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
+        }
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
+}
+
+/// Move one WORD forward to the end of the WORD (Vim `E`).
+#[derive(Clone, Debug, Copy)]
+pub struct MoveBigWordForwardToEndOfWord(pub usize);
+
+impl Execute for MoveBigWordForwardToEndOfWord {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.lines.is_empty() {
+            return;
+        }
+
+        state.clamp_column();
+
+        for _ in 0..self.0 {
+            move_word_forward_to_end_of_word(state, CharacterClass::big);
+        }
+
+        if state.mode.is_visual() {
+            set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
+        }
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
+}
+
+/// Move one WORD backward (Vim `B`).
+#[derive(Clone, Debug, Copy)]
+pub struct MoveBigWordBackward(pub usize);
+
+impl Execute for MoveBigWordBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.lines.is_empty() {
+            return;
+        }
+
+        let max_col = max_col(&state.lines, &state.cursor, state.mode);
+        if state.cursor.col > max_col {
+            state.cursor.col = max_col;
+        }
+
+        for _ in 0..self.0 {
+            move_word_backward(state, CharacterClass::big);
+        }
+
+        if state.mode.is_visual() {
+            set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
+        }
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
+}
+
 // Move the cursor to the start of the line.
 #[derive(Clone, Debug, Copy)]
 pub struct MoveToStartOfLine();
@@ -250,10 +408,14 @@ impl Execute for MoveToStartOfLine {
     fn execute(&mut self, state: &mut EditorState) {
         state.cursor.col = 0;
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
 }
 // move to the first non-whitespace character in the line.
 #[derive(Clone, Debug, Copy)]
@@ -264,10 +426,14 @@ impl Execute for MoveToFirst {
         state.cursor.col = 0;
         skip_whitespace(&state.lines, &mut state.cursor);
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
 }
 
 // Move the cursor to the end of the line.
@@ -278,10 +444,17 @@ impl Execute for MoveToEndOfLine {
     fn execute(&mut self, state: &mut EditorState) {
         state.cursor.col = max_col(&state.lines, &state.cursor, state.mode);
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
+            if let Some(selection) = state.selection.as_mut().filter(|s| s.block_mode) {
+                selection.to_line_end = true;
+            }
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
 }
 
 // Move the cursor to the start of the buffer.
@@ -290,12 +463,17 @@ pub struct MoveToFirstRow();
 
 impl Execute for MoveToFirstRow {
     fn execute(&mut self, state: &mut EditorState) {
+        state.push_jump();
         state.cursor.row = 0;
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
 }
 
 // Move the cursor to the end of the buffer.
@@ -304,12 +482,41 @@ pub struct MoveToLastRow();
 
 impl Execute for MoveToLastRow {
     fn execute(&mut self, state: &mut EditorState) {
+        state.push_jump();
         state.cursor.row = state.lines.len().saturating_sub(1);
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
+            set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
+        }
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
+}
+
+/// Move the cursor to the given line number, starting at 1 (Vim `10G`).
+#[derive(Clone, Debug, Copy)]
+pub struct MoveToLine(pub usize);
+
+impl Execute for MoveToLine {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.push_jump();
+        state.cursor.row = self.0.saturating_sub(1).min(state.lines.last_row_index());
+        state.clamp_column();
+
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
 }
 
 // Move the cursor to the closing bracket.
@@ -321,12 +528,17 @@ impl Execute for MoveToMatchinBracket {
         let max_col = max_col_normal(&state.lines, &state.cursor);
         let index = Index2::new(state.cursor.row, state.cursor.col.min(max_col));
         if let Some(index) = find_matching_bracket(&state.lines, index) {
+            state.push_jump();
             state.cursor = index;
-            if state.mode == EditorMode::Visual {
+            if state.mode.is_visual() {
                 set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
             }
         };
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -337,10 +549,14 @@ impl Execute for MoveHalfPageDown {
         let jump_rows = state.view.num_rows / 2;
         state.cursor.row = min(state.cursor.row + jump_rows, state.lines.last_row_index());
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -351,10 +567,14 @@ impl Execute for MoveHalfPageUp {
         let jump_rows = state.view.num_rows / 2;
         state.cursor.row = state.cursor.row.saturating_sub(jump_rows);
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -374,10 +594,14 @@ impl Execute for MovePageDown {
             state.cursor.row = state.view.viewport.y;
         }
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -397,10 +621,14 @@ impl Execute for MovePageUp {
             state.cursor.row = min(last_visible_row, state.lines.last_row_index());
         }
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
 }
 
 /// Move to the next paragraph boundary (Vim `}`).
@@ -411,6 +639,7 @@ pub struct MoveParagraphForward();
 
 impl Execute for MoveParagraphForward {
     fn execute(&mut self, state: &mut EditorState) {
+        state.push_jump();
         let last = state.lines.last_row_index();
         let mut row = state.cursor.row;
 
@@ -431,10 +660,14 @@ impl Execute for MoveParagraphForward {
             state.cursor.col = 0;
         };
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
 }
 
 /// Move to the previous paragraph boundary (Vim `{`).
@@ -443,6 +676,7 @@ pub struct MoveParagraphBackward();
 
 impl Execute for MoveParagraphBackward {
     fn execute(&mut self, state: &mut EditorState) {
+        state.push_jump();
         let mut row = state.cursor.row;
 
         if !state.lines.is_first_row(state.cursor) {
@@ -458,70 +692,347 @@ impl Execute for MoveParagraphBackward {
 
         state.cursor.col = 0;
 
-        if state.mode == EditorMode::Visual {
+        if state.mode.is_visual() {
             set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
         }
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
+}
+
+/// A character search on the current line (Vim `f`, `F`, `t` and `T`).
+///
+/// The last search is stored in the [`EditorState`], so that [`RepeatFind`]
+/// can repeat it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CharSearch {
+    pub(crate) target: char,
+    /// Searches to the left of the cursor (Vim `F` and `T`).
+    pub(crate) backward: bool,
+    /// Stops just before the target (Vim `t` and `T`).
+    pub(crate) till: bool,
+}
+
+impl CharSearch {
+    /// Returns the same search in the opposite direction.
+    fn reversed(self) -> Self {
+        Self {
+            backward: !self.backward,
+            ..self
+        }
+    }
+
+    /// Returns the column the search moves the cursor to, if the target
+    /// occurs `count` times. A repeated till search skips a target right next
+    /// to the cursor, so that `;` after `t` does not get stuck.
+    fn column(self, state: &EditorState, repeat: bool, count: usize) -> Option<usize> {
+        let skip = usize::from(self.till && repeat);
+        if self.backward {
+            let mut col = state.cursor.col.checked_sub(skip)?;
+            for _ in 0..count.max(1) {
+                col = find_char_backward_from(state, col, self.target)?;
+            }
+            Some(if self.till { col + 1 } else { col })
+        } else {
+            let mut col = state.cursor.col + skip;
+            for _ in 0..count.max(1) {
+                col = find_char_forward_from(state, col, self.target)?;
+            }
+            Some(if self.till { col - 1 } else { col })
+        }
+    }
+
+    /// Moves the cursor to the `count`th target, if it is found.
+    fn execute(self, state: &mut EditorState, repeat: bool, count: usize) {
+        if let Some(col) = self.column(state, repeat, count) {
+            state.cursor.col = col;
+            if state.mode.is_visual() {
+                set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
+            }
+        }
+    }
+
+    fn motion_kind(self) -> MotionKind {
+        if self.backward {
+            MotionKind::Exclusive
+        } else {
+            MotionKind::Inclusive
+        }
+    }
+}
+
+/// Runs a new character search and remembers it for [`RepeatFind`].
+fn find_char(
+    state: &mut EditorState,
+    target: Option<char>,
+    backward: bool,
+    till: bool,
+    count: usize,
+) {
+    let Some(target) = target else {
+        return;
+    };
+    let search = CharSearch {
+        target,
+        backward,
+        till,
+    };
+    state.last_find = Some(search);
+    search.execute(state, false, count);
 }
 
-/// Moves the cursor to the next occurrence of a character to the right on the
-/// current line (Vim `f`). Does nothing if the character is not found.
+/// Moves the cursor to the `count`th occurrence of a character to the right
+/// on the current line (Vim `f`). Does nothing if the character is not found
+/// that often.
 ///
 /// The target is `None` until the key handler supplies the next keystroke via
 /// [`Execute::char_arg`].
 #[derive(Clone, Debug, Copy)]
-pub struct FindForward(pub Option<char>);
+pub struct FindForward {
+    pub target: Option<char>,
+    pub count: usize,
+}
+
+impl FindForward {
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self {
+            target: None,
+            count,
+        }
+    }
+}
 
 impl Execute for FindForward {
     fn execute(&mut self, state: &mut EditorState) {
-        let Some(target) = self.0 else {
-            return;
-        };
-        if let Some(col) = find_char_forward(state, target) {
-            state.cursor.col = col;
-            if state.mode == EditorMode::Visual {
-                set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
-            }
-        }
+        find_char(state, self.target, false, false, self.count);
     }
 
     fn char_arg(&mut self) -> Option<&mut Option<char>> {
-        Some(&mut self.0)
+        Some(&mut self.target)
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
     }
 }
 
-/// Moves the cursor to just before the next occurrence of a character to the
-/// right on the current line (Vim `t`). Does nothing if the character is not
-/// found.
+/// Moves the cursor to just before the `count`th occurrence of a character
+/// to the right on the current line (Vim `t`). Does nothing if the character
+/// is not found that often.
 ///
 /// The target is `None` until the key handler supplies the next keystroke via
 /// [`Execute::char_arg`].
 #[derive(Clone, Debug, Copy)]
-pub struct TillForward(pub Option<char>);
+pub struct TillForward {
+    pub target: Option<char>,
+    pub count: usize,
+}
+
+impl TillForward {
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self {
+            target: None,
+            count,
+        }
+    }
+}
 
 impl Execute for TillForward {
     fn execute(&mut self, state: &mut EditorState) {
-        let Some(target) = self.0 else {
-            return;
-        };
-        if let Some(col) = find_char_forward(state, target) {
-            state.cursor.col = col.saturating_sub(1);
-            if state.mode == EditorMode::Visual {
-                set_selection_with_lines(&mut state.selection, state.cursor, &state.lines);
-            }
+        find_char(state, self.target, false, true, self.count);
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.target)
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
+}
+
+/// Moves the cursor to the `count`th occurrence of a character to the left
+/// on the current line (Vim `F`). Does nothing if the character is not found
+/// that often.
+///
+/// The target is `None` until the key handler supplies the next keystroke via
+/// [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct FindBackward {
+    pub target: Option<char>,
+    pub count: usize,
+}
+
+impl FindBackward {
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self {
+            target: None,
+            count,
         }
     }
+}
+
+impl Execute for FindBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        find_char(state, self.target, true, false, self.count);
+    }
 
     fn char_arg(&mut self) -> Option<&mut Option<char>> {
-        Some(&mut self.0)
+        Some(&mut self.target)
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
+}
+
+/// Moves the cursor to just after the `count`th occurrence of a character to
+/// the left on the current line (Vim `T`). Does nothing if the character is
+/// not found that often.
+///
+/// The target is `None` until the key handler supplies the next keystroke via
+/// [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct TillBackward {
+    pub target: Option<char>,
+    pub count: usize,
+}
+
+impl TillBackward {
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self {
+            target: None,
+            count,
+        }
+    }
+}
+
+impl Execute for TillBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        find_char(state, self.target, true, true, self.count);
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.target)
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Exclusive)
+    }
+}
+
+/// Repeats the last `f`, `F`, `t` or `T` search `count` times (Vim `;`), or
+/// repeats it in the opposite direction (Vim `,`). Does nothing if there was
+/// no search yet.
+#[derive(Clone, Debug, Copy)]
+pub struct RepeatFind {
+    reverse: bool,
+    count: usize,
+    /// The search to repeat, looked up before an operator runs the motion so
+    /// that [`Execute::motion_kind`] follows its direction.
+    search: Option<CharSearch>,
+}
+
+impl RepeatFind {
+    /// Repeats the last search in the same direction (Vim `;`).
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            reverse: false,
+            count: 1,
+            search: None,
+        }
+    }
+
+    /// Repeats the last search in the opposite direction (Vim `,`).
+    #[must_use]
+    pub fn reverse() -> Self {
+        Self {
+            reverse: true,
+            count: 1,
+            search: None,
+        }
+    }
+
+    /// Looks up the search to repeat.
+    pub(crate) fn resolve(&mut self, state: &EditorState) {
+        self.search = state.last_find.map(|search| {
+            if self.reverse {
+                search.reversed()
+            } else {
+                search
+            }
+        });
+    }
+}
+
+impl Default for RepeatFind {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Execute for RepeatFind {
+    fn execute(&mut self, state: &mut EditorState) {
+        if self.search.is_none() {
+            self.resolve(state);
+        }
+        if let Some(search) = self.search {
+            search.execute(state, true, self.count);
+        }
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(
+            self.search
+                .map_or(MotionKind::Inclusive, CharSearch::motion_kind),
+        )
     }
 }
 
 /// Returns the column of the next occurrence of `target` to the right of the
 /// cursor on the current line, if any.
 pub(crate) fn find_char_forward(state: &EditorState, target: char) -> Option<usize> {
+    find_char_forward_from(state, state.cursor.col, target)
+}
+
+/// Returns the column of the previous occurrence of `target` to the left of
+/// the cursor on the current line, if any.
+pub(crate) fn find_char_backward(state: &EditorState, target: char) -> Option<usize> {
+    find_char_backward_from(state, state.cursor.col, target)
+}
+
+/// Returns the column of the next occurrence of `target` to the right of `col`
+/// on the cursor's line, if any.
+fn find_char_forward_from(state: &EditorState, col: usize, target: char) -> Option<usize> {
     let row = state.cursor.row;
-    let start = Index2::new(row, state.cursor.col + 1);
+    let start = Index2::new(row, col + 1);
     for (ch, index) in state.lines.iter().from(start) {
         if index.row != row {
             break;
@@ -533,6 +1044,15 @@ pub(crate) fn find_char_forward(state: &EditorState, target: char) -> Option<usi
     None
 }
 
+/// Returns the column of the previous occurrence of `target` to the left of
+/// `col` on the cursor's line, if any.
+fn find_char_backward_from(state: &EditorState, col: usize, target: char) -> Option<usize> {
+    let line = state.lines.get(RowIndex::new(state.cursor.row))?;
+    line.iter()
+        .take(col.min(line.len()))
+        .rposition(|&ch| ch == target)
+}
+
 #[derive(Debug, Clone, Eq)]
 pub(crate) enum CharacterClass {
     Unknown,
@@ -563,6 +1083,23 @@ impl From<Option<&char>> for CharacterClass {
     }
 }
 
+impl CharacterClass {
+    /// Classifies a character for word motions.
+    pub(crate) fn word(value: Option<&char>) -> Self {
+        Self::from(value)
+    }
+
+    /// Classifies a character for WORD motions, where every non-whitespace
+    /// character belongs to the same class.
+    pub(crate) fn big(value: Option<&char>) -> Self {
+        match Self::from(value) {
+            Self::Whitespace => Self::Whitespace,
+            _ if value.is_some() => Self::Alphanumeric,
+            _ => Self::Unknown,
+        }
+    }
+}
+
 impl PartialEq for CharacterClass {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -574,7 +1111,7 @@ impl PartialEq for CharacterClass {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Index2, Lines};
+    use crate::{EditorMode, Index2, Lines};
 
     use super::*;
     fn test_state() -> EditorState {
@@ -906,6 +1443,21 @@ mod tests {
         assert_eq!(state.cursor, Index2::new(0, 1));
     }
 
+    #[test]
+    fn test_move_to_line() {
+        let mut state = test_state();
+        state.cursor = Index2::new(0, 11);
+
+        MoveToLine(3).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(2, 3));
+
+        MoveToLine(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 3));
+
+        MoveToLine(100).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(2, 3));
+    }
+
     fn paragraph_state() -> EditorState {
         // Lines:
         //   0: "first paragraph"
@@ -1037,4 +1589,74 @@ mod tests {
         MoveParagraphBackward().execute(&mut state);
         assert_eq!(state.cursor.row, 0);
     }
+
+    #[test]
+    fn test_find_backward() {
+        let mut state = EditorState::new(Lines::from("a.b.c.d"));
+        state.cursor = Index2::new(0, 6);
+
+        FindBackward {
+            target: Some('.'),
+            count: 1,
+        }
+        .execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 5));
+
+        TillBackward {
+            target: Some('.'),
+            count: 1,
+        }
+        .execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 4));
+
+        FindBackward {
+            target: Some('x'),
+            count: 1,
+        }
+        .execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 4));
+    }
+
+    #[test]
+    fn test_repeat_find() {
+        let mut state = EditorState::new(Lines::from("a.b.c.d"));
+
+        RepeatFind::new().execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        FindForward {
+            target: Some('.'),
+            count: 1,
+        }
+        .execute(&mut state);
+        RepeatFind::new().execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 3));
+
+        RepeatFind::reverse().execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 1));
+    }
+
+    #[test]
+    fn test_repeat_till_skips_adjacent_target() {
+        let mut state = EditorState::new(Lines::from("a.b.c.d"));
+
+        TillForward {
+            target: Some('.'),
+            count: 1,
+        }
+        .execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+        RepeatFind::new().execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 2));
+
+        state.cursor = Index2::new(0, 6);
+        TillBackward {
+            target: Some('.'),
+            count: 1,
+        }
+        .execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 6));
+        RepeatFind::new().execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 4));
+    }
 }
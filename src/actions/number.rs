@@ -0,0 +1,330 @@
+//! Actions that add to the number at or after the cursor (Vim `Ctrl+a` and
+//! `Ctrl+x`).
+//!
+//! Decimal numbers, which may be negative, hexadecimal numbers with a `0x`
+//! prefix and binary numbers with a `0b` prefix are recognized. Leading zeros
+//! are kept, hexadecimal and binary numbers wrap around at 64 bits. In visual
+//! mode, the first number in every selected row is changed.
+use jagged::index::RowIndex;
+
+use super::{Execute, SwitchMode};
+use crate::{EditorMode, EditorState, Index2};
+
+/// Adds the count to the number at or after the cursor on the current line
+/// (Vim `Ctrl+a`), or to the first number in every selected row.
+///
+/// # Example
+///
+/// ```
+/// use edtui::{EditorState, Lines};
+/// use edtui::actions::Increment;
+///
+/// let mut state = EditorState::new(Lines::from("port = 8080"));
+/// state.execute(Increment::new(10));
+/// assert_eq!(state.lines, Lines::from("port = 8090"));
+/// ```
+#[derive(Clone, Debug, Copy)]
+pub struct Increment {
+    pub count: usize,
+    /// Adds the count times the position of the number in the selection,
+    /// turning the selected numbers into a sequence (Vim `g Ctrl+a`).
+    pub sequential: bool,
+}
+
+impl Increment {
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            sequential: false,
+        }
+    }
+
+    /// Increments the selected numbers sequentially.
+    #[must_use]
+    pub fn sequential(mut self) -> Self {
+        self.sequential = true;
+        self
+    }
+}
+
+impl Execute for Increment {
+    fn execute(&mut self, state: &mut EditorState) {
+        add(state, delta(self.count), self.sequential);
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+}
+
+/// Subtracts the count from the number at or after the cursor on the current
+/// line (Vim `Ctrl+x`), or from the first number in every selected row.
+#[derive(Clone, Debug, Copy)]
+pub struct Decrement {
+    pub count: usize,
+    /// Subtracts the count times the position of the number in the
+    /// selection (Vim `g Ctrl+x`).
+    pub sequential: bool,
+}
+
+impl Decrement {
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            sequential: false,
+        }
+    }
+
+    /// Decrements the selected numbers sequentially.
+    #[must_use]
+    pub fn sequential(mut self) -> Self {
+        self.sequential = true;
+        self
+    }
+}
+
+impl Execute for Decrement {
+    fn execute(&mut self, state: &mut EditorState) {
+        add(state, -delta(self.count), self.sequential);
+    }
+
+    fn is_repeatable(&self) -> bool {
+        true
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+}
+
+fn delta(count: usize) -> i128 {
+    i128::try_from(count).unwrap_or(i128::MAX)
+}
+
+/// Adds `delta` to the number at or after the cursor, or to the selected
+/// numbers in visual mode.
+fn add(state: &mut EditorState, delta: i128, sequential: bool) {
+    if state.mode.is_visual() {
+        state.capture();
+        if let Some(selection) = state.selection.take() {
+            let (top, bottom) = selection.block_corners();
+            let mut factor = 0;
+            for row in top.row..=bottom.row {
+                let Some(line) = state.lines.get(RowIndex::new(row)) else {
+                    break;
+                };
+                let number = numbers(line)
+                    .into_iter()
+                    .find(|number| selection.contains(&Index2::new(row, number.start)));
+                if let Some(number) = number {
+                    factor += 1;
+                    let delta = if sequential {
+                        delta.saturating_mul(factor)
+                    } else {
+                        delta
+                    };
+                    let _ = add_to_number(state, row, number, delta);
+                }
+            }
+            state.cursor = if selection.block_mode {
+                top
+            } else {
+                selection.start()
+            };
+            if selection.line_mode {
+                state.cursor.col = 0;
+            }
+        }
+        SwitchMode(EditorMode::Normal).execute(state);
+        return;
+    }
+
+    let Index2 { row, col } = state.cursor;
+    let Some(line) = state.lines.get(RowIndex::new(row)) else {
+        return;
+    };
+    let Some(number) = numbers(line).into_iter().find(|number| number.end > col) else {
+        return;
+    };
+    state.capture();
+    if let Some(end) = add_to_number(state, row, number, delta) {
+        state.cursor = Index2::new(row, end.saturating_sub(1));
+    }
+}
+
+/// Replaces a number in a row by the number plus `delta`. Returns the
+/// column after the new number.
+fn add_to_number(
+    state: &mut EditorState,
+    row: usize,
+    number: Number,
+    delta: i128,
+) -> Option<usize> {
+    let line = state.lines.get(RowIndex::new(row))?;
+    let text = number.add(line, delta)?;
+    let start = Index2::new(row, number.start);
+    let end = Index2::new(row, number.end);
+    state.changes.replace(&mut state.lines, start, end, &text)?;
+    Some(number.start + text.chars().count())
+}
+
+/// The position of a number in a line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Number {
+    /// The column of the first character, the sign or the prefix.
+    start: usize,
+    /// The column after the last digit.
+    end: usize,
+    radix: u32,
+}
+
+impl Number {
+    /// Returns the text of the number plus `delta`, or `None` if the number
+    /// is too large.
+    fn add(&self, line: &[char], delta: i128) -> Option<String> {
+        let text: String = line[self.start..self.end].iter().collect();
+        if self.radix == 10 {
+            let value = text.parse::<i128>().ok()?.saturating_add(delta);
+            let digits = text.trim_start_matches('-');
+            let width = if digits.starts_with('0') {
+                digits.len()
+            } else {
+                0
+            };
+            let sign = if value < 0 { "-" } else { "" };
+            return Some(format!("{sign}{:0width$}", value.unsigned_abs()));
+        }
+
+        let (prefix, digits) = text.split_at(2);
+        let value = u64::from_str_radix(digits, self.radix).ok()?;
+        // Truncating the delta to 64 bits wraps around like the addition.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let value = value.wrapping_add(delta as u64);
+        let width = digits.len();
+        let digits = match self.radix {
+            16 if digits.chars().any(|ch| ch.is_ascii_uppercase()) => {
+                format!("{value:0width$X}")
+            }
+            16 => format!("{value:0width$x}"),
+            _ => format!("{value:0width$b}"),
+        };
+        Some(format!("{prefix}{digits}"))
+    }
+}
+
+/// Returns the numbers in a line, from left to right.
+fn numbers(line: &[char]) -> Vec<Number> {
+    let mut numbers = Vec::new();
+    let mut col = 0;
+    while col < line.len() {
+        if !line[col].is_ascii_digit() {
+            col += 1;
+            continue;
+        }
+        let radix = match (line[col], line.get(col + 1), line.get(col + 2)) {
+            ('0', Some('x' | 'X'), Some(ch)) if ch.is_ascii_hexdigit() => 16,
+            ('0', Some('b' | 'B'), Some('0' | '1')) => 2,
+            _ => 10,
+        };
+        let digits = if radix == 10 { col } else { col + 2 };
+        let end = digits
+            + line[digits..]
+                .iter()
+                .take_while(|ch| ch.is_digit(radix))
+                .count();
+        let start = if radix == 10 && col > 0 && line[col - 1] == '-' {
+            col - 1
+        } else {
+            col
+        };
+        numbers.push(Number { start, end, radix });
+        col = end;
+    }
+    numbers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::selection::Selection;
+    use crate::Lines;
+
+    fn increment(text: &str, col: usize, count: usize) -> String {
+        let mut state = EditorState::new(Lines::from(text));
+        state.cursor = Index2::new(0, col);
+        state.execute(Increment::new(count));
+        state.lines.to_string()
+    }
+
+    #[test]
+    fn test_numbers() {
+        let line: Vec<char> = "v1.-20 0x1F 0b10 x".chars().collect();
+        assert_eq!(
+            numbers(&line),
+            vec![
+                Number {
+                    start: 1,
+                    end: 2,
+                    radix: 10
+                },
+                Number {
+                    start: 3,
+                    end: 6,
+                    radix: 10
+                },
+                Number {
+                    start: 7,
+                    end: 11,
+                    radix: 16
+                },
+                Number {
+                    start: 12,
+                    end: 16,
+                    radix: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_increment() {
+        assert_eq!(increment("port 8080", 0, 1), "port 8081");
+        assert_eq!(increment("v1.9", 2, 1), "v1.10");
+        assert_eq!(increment("x = -1", 0, 3), "x = 2");
+        assert_eq!(increment("007", 0, 1), "008");
+        assert_eq!(increment("0x0F", 1, 1), "0x10");
+        assert_eq!(increment("0xff", 3, 1), "0x100");
+        assert_eq!(increment("0b011", 0, 1), "0b100");
+        assert_eq!(increment("no number", 0, 1), "no number");
+    }
+
+    #[test]
+    fn test_decrement() {
+        let mut state = EditorState::new(Lines::from("a 1 b 0x00"));
+        state.execute(Decrement::new(3));
+        assert_eq!(state.lines, Lines::from("a -2 b 0x00"));
+        assert_eq!(state.cursor, Index2::new(0, 3));
+
+        state.cursor.col = 5;
+        state.execute(Decrement::new(1));
+        assert_eq!(state.lines, Lines::from("a -2 b 0xffffffffffffffff"));
+    }
+
+    #[test]
+    fn test_increment_selection() {
+        let mut state = EditorState::new(Lines::from("0.\n0.\nx\n0."));
+        state.mode = EditorMode::VisualLine;
+        state.selection = Some(Selection::new(Index2::new(0, 0), Index2::new(3, 0)).line_mode());
+
+        state.execute(Increment::new(2).sequential());
+        assert_eq!(state.lines, Lines::from("2.\n4.\nx\n6."));
+        assert_eq!(state.mode, EditorMode::Normal);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+}
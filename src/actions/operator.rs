@@ -0,0 +1,629 @@
+//! Operators such as `d`, `c` or `y`.
+//!
+//! An operator acts on the text covered by a motion or text object. The key
+//! handler resolves the operator first, waits for a motion and hands it over
+//! through [`Execute::motion_arg`]. The operator then runs the motion to find
+//! the range it covers, so any motion can follow any operator (`d}`, `c$`,
+//! `y%`, `dG`, ...) without a dedicated action for every pair.
+use jagged::index::RowIndex;
+
+use super::case::Case;
+use super::delete::delete_rows;
+use super::motion::{CharacterClass, MoveBigWordForwardToEndOfWord, MoveWordForwardToEndOfWord};
+use super::select::SelectLines;
+use super::Execute;
+use super::{Action, SwitchMode};
+use crate::clipboard::RegisterKind;
+use crate::helper::{indentation, map_chars, remove_range, text_in_range};
+use crate::state::selection::Selection;
+use crate::{EditorMode, EditorState, Index2};
+
+/// Describes how an operator treats the text between the cursor and the
+/// target of a motion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MotionKind {
+    /// The character at the target is not included (Vim `w`, `b`, `}`).
+    Exclusive,
+    /// The character at the target is included (Vim `e`, `$`, `f`).
+    Inclusive,
+    /// All lines from the cursor to the target are included (Vim `j`, `G`).
+    Linewise,
+}
+
+/// The operation applied to the text covered by a motion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    /// Deletes the text (Vim `d`).
+    Delete,
+    /// Deletes the text and enters insert mode (Vim `c`).
+    Change,
+    /// Copies the text to the clipboard (Vim `y`).
+    Yank,
+    /// Indents the covered lines (Vim `>`).
+    IndentRight,
+    /// Removes one level of indentation from the covered lines (Vim `<`).
+    IndentLeft,
+    /// Makes the text lowercase (Vim `gu`).
+    Lowercase,
+    /// Makes the text uppercase (Vim `gU`).
+    Uppercase,
+    /// Toggles the case of the text (Vim `g~`).
+    ToggleCase,
+    /// Indents the covered lines like the lines around them (Vim `=`).
+    Reindent,
+}
+
+/// Applies an operator to the text covered by a motion or text object
+/// (Vim `d{motion}`).
+///
+/// The motion is `None` until the key handler supplies it via
+/// [`Execute::motion_arg`].
+///
+/// # Example
+///
+/// ```
+/// use edtui::{EditorState, Lines};
+/// use edtui::actions::MoveParagraphForward;
+/// use edtui::actions::operator::{Operate, Operator};
+///
+/// let mut state = EditorState::new(Lines::from("Hello\nWorld\n\n!"));
+/// state.execute(Operate::new(Operator::Delete).with_motion(MoveParagraphForward()));
+/// assert_eq!(state.lines, Lines::from("\n!"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Operate {
+    pub operator: Operator,
+    pub motion: Option<Box<Action>>,
+}
+
+impl Operate {
+    #[must_use]
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            motion: None,
+        }
+    }
+
+    /// Sets the motion that determines the range of the operator.
+    #[must_use]
+    pub fn with_motion<A: Into<Action>>(mut self, motion: A) -> Self {
+        self.motion = Some(Box::new(motion.into()));
+        self
+    }
+}
+
+impl Execute for Operate {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(motion) = &self.motion else {
+            return;
+        };
+        if state.lines.is_empty() {
+            return;
+        }
+        state.clamp_column();
+
+        let mut motion = motion.as_ref().clone();
+        if self.operator == Operator::Change {
+            change_word_motion(state, &mut motion);
+        }
+        if let Action::RepeatFind(repeat) = &mut motion {
+            repeat.resolve(state);
+        }
+        if let Some(range) = OperatorRange::from_motion(state, &mut motion) {
+            if self.operator != Operator::Yank {
+                state.capture();
+            }
+            range.apply(self.operator, state);
+        }
+    }
+
+    fn is_repeatable(&self) -> bool {
+        self.operator != Operator::Yank
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        self.motion.as_mut()?.count()
+    }
+
+    fn motion_arg(&mut self) -> Option<&mut Option<Box<Action>>> {
+        Some(&mut self.motion)
+    }
+}
+
+/// Applies an operator to the selected text and leaves visual mode (Vim `>`,
+/// `<` or `=` in visual mode). A block selection covers its whole rows.
+///
+/// The selection is gone when the change is repeated, so `.` applies the
+/// operator to the same number of lines from the cursor instead.
+#[derive(Clone, Debug, Copy)]
+pub struct OperateSelection(pub Operator);
+
+impl Execute for OperateSelection {
+    fn execute(&mut self, state: &mut EditorState) {
+        if self.0 != Operator::Yank {
+            state.capture();
+        }
+        let Some(selection) = state.selection.take() else {
+            return;
+        };
+        let range = if selection.line_mode || selection.block_mode {
+            let (top, bottom) = selection.block_corners();
+            OperatorRange::linewise(top.row, bottom.row)
+        } else {
+            let (start, end) = (selection.start(), selection.end());
+            let len = state.lines.len_col(end.row).unwrap_or_default();
+            OperatorRange {
+                start,
+                end: Index2::new(end.row, (end.col + 1).min(len)),
+                linewise: false,
+            }
+        };
+        SwitchMode(EditorMode::Normal).execute(state);
+        range.apply(self.0, state);
+
+        let by_rows = matches!(
+            self.0,
+            Operator::IndentRight | Operator::IndentLeft | Operator::Reindent
+        );
+        if by_rows {
+            let rows = range.end.row - range.start.row + 1;
+            let repeat = Operate::new(self.0).with_motion(SelectLines(rows));
+            state.last_change = Some(repeat.into());
+            state.last_insert = None;
+        }
+    }
+
+    fn is_edit(&self) -> bool {
+        self.0 != Operator::Yank
+    }
+}
+
+/// `cw` and `cW` leave the whitespace after a word alone and behave like
+/// `ce` and `cE`, unless the cursor is on whitespace.
+fn change_word_motion(state: &EditorState, motion: &mut Action) {
+    let Some(&ch) = state.lines.get(state.cursor) else {
+        return;
+    };
+    if ch.is_whitespace() {
+        return;
+    }
+
+    // On the last character of a word, `e` would jump to the next word.
+    let next = state
+        .lines
+        .get(Index2::new(state.cursor.row, state.cursor.col + 1));
+    let at_word_end = |class: fn(Option<&char>) -> CharacterClass| class(next) != class(Some(&ch));
+
+    match motion {
+        Action::MoveWordForward(m) => {
+            let count =
+                m.0.saturating_sub(usize::from(at_word_end(CharacterClass::word)));
+            *motion = MoveWordForwardToEndOfWord(count).into();
+        }
+        Action::MoveBigWordForward(m) => {
+            let count =
+                m.0.saturating_sub(usize::from(at_word_end(CharacterClass::big)));
+            *motion = MoveBigWordForwardToEndOfWord(count).into();
+        }
+        _ => {}
+    }
+}
+
+/// The text covered by a motion, from `start` up to (excluding) `end`.
+/// Linewise ranges cover all rows from `start.row` to `end.row`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct OperatorRange {
+    pub(crate) start: Index2,
+    pub(crate) end: Index2,
+    pub(crate) linewise: bool,
+}
+
+impl OperatorRange {
+    fn linewise(start_row: usize, end_row: usize) -> Self {
+        Self {
+            start: Index2::new(start_row, 0),
+            end: Index2::new(end_row, 0),
+            linewise: true,
+        }
+    }
+
+    /// Runs a motion from the cursor and returns the range it covers. Returns
+    /// `None` if the action is not a motion or if the motion fails.
+    pub(crate) fn from_motion(state: &mut EditorState, motion: &mut Action) -> Option<Self> {
+        let kind = motion.motion_kind()?;
+        let is_word_motion = matches!(
+            motion,
+            Action::MoveWordForward(_) | Action::MoveBigWordForward(_)
+        );
+
+        // Motions report their target through the selection, just like they
+        // extend it in visual mode. If the end of the selection was never
+        // touched, the motion did not find its target (e.g. `f` without match).
+        let unset = Index2::new(usize::MAX, usize::MAX);
+        let (cursor, mode) = (state.cursor, state.mode);
+        let selection = state.selection.take();
+        state.mode = EditorMode::Visual;
+        state.selection = Some(Selection::new(cursor, unset));
+        motion.execute(state);
+        let target = state.selection.take();
+        state.cursor = cursor;
+        state.mode = mode;
+        state.selection = selection;
+
+        let target = target.filter(|target| target.end != unset)?;
+        let (start, mut end) = (target.start(), target.end());
+        if target.line_mode || kind == MotionKind::Linewise {
+            return Some(Self::linewise(start.row, end.row));
+        }
+
+        let lines = &state.lines;
+        // `}` without a blank line below stops on the last character of the
+        // buffer, which is then included.
+        let at_buffer_end = matches!(motion, Action::MoveParagraphForward(_))
+            && end.row == lines.last_row_index()
+            && lines.len_col(end.row).unwrap_or_default() > 0;
+        if kind == MotionKind::Inclusive || at_buffer_end {
+            end.col += 1;
+        } else if end.row > start.row && end.col <= indentation(lines, end.row) {
+            // An exclusive motion that ends at the start of a line stops at the
+            // end of the previous line instead (`dw` on the last word of a line).
+            // If it also started before the first non-blank, it becomes linewise.
+            let row = end.row - 1;
+            end = Index2::new(row, lines.len_col(row).unwrap_or_default());
+            let starts_line = start.col <= indentation(lines, start.row);
+            let is_empty = lines.len_col(start.row) == Some(0);
+            if starts_line && (!is_word_motion || is_empty) {
+                return Some(Self::linewise(start.row, end.row));
+            }
+        }
+        end.col = end.col.min(lines.len_col(end.row).unwrap_or_default());
+
+        (start < end).then_some(Self {
+            start,
+            end,
+            linewise: false,
+        })
+    }
+
+    /// Returns the covered text.
+    pub(crate) fn text(&self, state: &EditorState) -> String {
+        if self.linewise {
+            let end = state.lines.len_col(self.end.row).unwrap_or_default();
+            return text_in_range(&state.lines, self.start, Index2::new(self.end.row, end));
+        }
+        text_in_range(&state.lines, self.start, self.end)
+    }
+
+    /// Returns how the covered text is pasted.
+    pub(crate) fn kind(&self) -> RegisterKind {
+        if self.linewise {
+            RegisterKind::Linewise
+        } else {
+            RegisterKind::Charwise
+        }
+    }
+
+    /// Removes the covered text and returns it.
+    fn remove(&self, state: &mut EditorState) -> String {
+        let text = self.text(state);
+        if self.linewise {
+            let last_row = self.end.row.min(state.lines.last_row_index());
+            let _ = delete_rows(
+                &mut state.lines,
+                &mut state.changes,
+                self.start.row..=last_row,
+            );
+        } else {
+            let _ = remove_range(&mut state.lines, &mut state.changes, self.start, self.end);
+        }
+        text
+    }
+
+    /// Applies an operator to the covered text. Operators that edit expect
+    /// the caller to have started an undo step.
+    pub(crate) fn apply(&self, operator: Operator, state: &mut EditorState) {
+        match operator {
+            Operator::Yank => {
+                let text = self.text(state);
+                state.clip.set_yanked(text, self.kind());
+                state.cursor = if self.linewise {
+                    Index2::new(self.start.row, state.cursor.col)
+                } else {
+                    self.start
+                };
+                state.clamp_column();
+            }
+            Operator::Delete => {
+                let text = self.remove(state);
+                state.clip.set_deleted(text, self.kind());
+                if self.linewise {
+                    let row = self.start.row.min(state.lines.len().saturating_sub(1));
+                    state.cursor = Index2::new(row, indentation(&state.lines, row));
+                } else {
+                    state.cursor = self.start;
+                }
+                state.clamp_column();
+            }
+            Operator::Change => {
+                let text = self.remove(state);
+                state.clip.set_deleted(text, self.kind());
+                if self.linewise {
+                    let row = self.start.row;
+                    let pos = Index2::new(row, 0);
+                    let text = if row < state.lines.len() { "\n" } else { "" };
+                    state.changes.insert(&mut state.lines, pos, text);
+                }
+                state.cursor = self.start;
+                state.mode = EditorMode::Insert;
+            }
+            Operator::IndentRight | Operator::IndentLeft | Operator::Reindent => {
+                for row in self.start.row..=self.end.row {
+                    match operator {
+                        Operator::IndentRight => indent_row(state, row),
+                        Operator::IndentLeft => dedent_row(state, row),
+                        _ => reindent_row(state, row),
+                    }
+                }
+                let row = self.start.row;
+                state.cursor = Index2::new(row, indentation(&state.lines, row));
+                state.clamp_column();
+            }
+            Operator::Lowercase | Operator::Uppercase | Operator::ToggleCase => {
+                let case = match operator {
+                    Operator::Lowercase => Case::Lower,
+                    Operator::Uppercase => Case::Upper,
+                    _ => Case::Toggle,
+                };
+                self.map_chars(state, |ch| case.apply(ch));
+                state.cursor = if self.linewise {
+                    Index2::new(self.start.row, state.cursor.col)
+                } else {
+                    self.start
+                };
+                state.clamp_column();
+            }
+        }
+    }
+
+    /// Replaces every covered character.
+    fn map_chars(&self, state: &mut EditorState, f: impl Fn(char) -> char) {
+        for row in self.start.row..=self.end.row {
+            let Some(len_col) = state.lines.len_col(row) else {
+                break;
+            };
+            let from = if self.linewise || row > self.start.row {
+                0
+            } else {
+                self.start.col
+            };
+            let to = if self.linewise || row < self.end.row {
+                len_col
+            } else {
+                self.end.col.min(len_col)
+            };
+            map_chars(&mut state.lines, &mut state.changes, row, from..to, &f);
+        }
+    }
+}
+
+/// Indents a non-empty row by one shift width.
+fn indent_row(state: &mut EditorState, row: usize) {
+    if state.lines.len_col(row).unwrap_or_default() > 0 {
+        let width = state.indent_width(row) + state.shift_width();
+        state.set_indent(row, width);
+    }
+}
+
+/// Removes up to one shift width of indentation from a row.
+fn dedent_row(state: &mut EditorState, row: usize) {
+    let width = state.indent_width(row).saturating_sub(state.shift_width());
+    state.set_indent(row, width);
+}
+
+/// Indents a row like the rows above it, and empties a blank row.
+fn reindent_row(state: &mut EditorState, row: usize) {
+    let width = match state.lines.get(RowIndex::new(row)) {
+        Some(line) if line.iter().all(|ch| ch.is_whitespace()) => 0,
+        Some(_) => state.reindent_width(row),
+        None => return,
+    };
+    state.set_indent(row, width);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::motion::{
+        FindForward, MoveBackward, MoveDown, MoveToEndOfLine, MoveToLastRow, MoveToStartOfLine,
+        MoveWordBackward, MoveWordForward,
+    };
+    use crate::actions::{MoveParagraphForward, MoveToMatchinBracket, SelectInnerBetween};
+    use crate::clipboard::{ClipboardTrait, InternalClipboard};
+    use crate::Lines;
+
+    fn test_state(text: &str) -> EditorState {
+        let mut state = EditorState::new(Lines::from(text));
+        state.set_clipboard(InternalClipboard::default());
+        state
+    }
+
+    fn operate<A: Into<Action>>(state: &mut EditorState, operator: Operator, motion: A) {
+        Operate::new(operator).with_motion(motion).execute(state);
+    }
+
+    #[test]
+    fn test_delete_exclusive_motion() {
+        let mut state = test_state("one two three");
+        state.cursor = Index2::new(0, 4);
+
+        operate(&mut state, Operator::Delete, MoveWordForward(1));
+        assert_eq!(state.lines, Lines::from("one three"));
+        assert_eq!(state.clip.get_text(), "two ");
+        assert_eq!(state.cursor, Index2::new(0, 4));
+
+        operate(&mut state, Operator::Delete, MoveWordBackward(1));
+        assert_eq!(state.lines, Lines::from("three"));
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
+    #[test]
+    fn test_delete_inclusive_motion() {
+        let mut state = test_state("one (two) three");
+        state.cursor = Index2::new(0, 4);
+
+        operate(&mut state, Operator::Delete, MoveToMatchinBracket());
+        assert_eq!(state.lines, Lines::from("one  three"));
+
+        operate(&mut state, Operator::Delete, MoveToEndOfLine());
+        assert_eq!(state.lines, Lines::from("one "));
+        assert_eq!(state.cursor, Index2::new(0, 3));
+    }
+
+    #[test]
+    fn test_delete_linewise_motion() {
+        let mut state = test_state("a\nb\nc\nd");
+        state.cursor = Index2::new(1, 0);
+
+        operate(&mut state, Operator::Delete, MoveToLastRow());
+        assert_eq!(state.lines, Lines::from("a"));
+        assert_eq!(state.clip.get_text(), "b\nc\nd");
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
+    #[test]
+    fn test_delete_word_at_end_of_line() {
+        let mut state = test_state("one two\nthree");
+        state.cursor = Index2::new(0, 4);
+
+        operate(&mut state, Operator::Delete, MoveWordForward(1));
+        assert_eq!(state.lines, Lines::from("one \nthree"));
+    }
+
+    #[test]
+    fn test_delete_paragraph() {
+        let mut state = test_state("one\ntwo\n\nthree");
+
+        operate(&mut state, Operator::Delete, MoveParagraphForward());
+        assert_eq!(state.lines, Lines::from("\nthree"));
+    }
+
+    #[test]
+    fn test_delete_paragraph_to_end_of_buffer() {
+        let mut state = test_state("one two three four\nfive six\nseven");
+        state.cursor = Index2::new(0, 4);
+
+        operate(&mut state, Operator::Delete, MoveParagraphForward());
+        assert_eq!(state.lines, Lines::from("one "));
+    }
+
+    #[test]
+    fn test_operate_past_end_of_short_line() {
+        // The cursor keeps its column when moving onto a shorter line (`$j`).
+        let mut state = test_state("abcdef\nxy");
+        state.cursor = Index2::new(1, 5);
+        operate(&mut state, Operator::Delete, MoveBackward(1));
+        assert_eq!(state.lines, Lines::from("abcdef\ny"));
+
+        let mut state = test_state("abcdef\nxy");
+        state.cursor = Index2::new(1, 5);
+        operate(&mut state, Operator::Delete, MoveWordBackward(1));
+        assert_eq!(state.lines, Lines::from("abcdef\ny"));
+
+        let mut state = test_state("abcdef\nxy");
+        state.cursor = Index2::new(1, 5);
+        operate(&mut state, Operator::Uppercase, MoveToStartOfLine());
+        assert_eq!(state.lines, Lines::from("abcdef\nXy"));
+        assert_eq!(state.cursor, Index2::new(1, 0));
+    }
+
+    #[test]
+    fn test_failed_motion_is_noop() {
+        let mut state = test_state("hello");
+
+        let find = FindForward {
+            target: Some('z'),
+            count: 1,
+        };
+        operate(&mut state, Operator::Delete, find);
+        assert_eq!(state.lines, Lines::from("hello"));
+        assert!(!state.can_undo());
+    }
+
+    #[test]
+    fn test_change_text_object() {
+        let mut state = test_state("call(a, b)");
+        state.cursor = Index2::new(0, 6);
+
+        operate(
+            &mut state,
+            Operator::Change,
+            SelectInnerBetween::new('(', ')'),
+        );
+        assert_eq!(state.lines, Lines::from("call()"));
+        assert_eq!(state.mode, EditorMode::Insert);
+        assert_eq!(state.cursor, Index2::new(0, 5));
+    }
+
+    #[test]
+    fn test_change_word_keeps_whitespace() {
+        let mut state = test_state("one two");
+
+        operate(&mut state, Operator::Change, MoveWordForward(1));
+        assert_eq!(state.lines, Lines::from(" two"));
+
+        // On the last character of a word only that character changes.
+        let mut state = test_state("one two");
+        state.cursor = Index2::new(0, 2);
+        operate(&mut state, Operator::Change, MoveWordForward(1));
+        assert_eq!(state.lines, Lines::from("on two"));
+    }
+
+    #[test]
+    fn test_change_lines() {
+        let mut state = test_state("a\nb\nc");
+
+        operate(&mut state, Operator::Change, MoveDown(1));
+        assert_eq!(state.lines, Lines::from("\nc"));
+        assert_eq!(state.cursor, Index2::new(0, 0));
+        assert_eq!(state.mode, EditorMode::Insert);
+    }
+
+    #[test]
+    fn test_yank_does_not_modify() {
+        let mut state = test_state("one two");
+        state.cursor = Index2::new(0, 4);
+
+        operate(&mut state, Operator::Yank, MoveWordBackward(1));
+        assert_eq!(state.lines, Lines::from("one two"));
+        assert_eq!(state.clip.get_text(), "one ");
+        assert_eq!(state.cursor, Index2::new(0, 0));
+        assert!(!state.can_undo());
+    }
+
+    #[test]
+    fn test_indent() {
+        let mut state = test_state("a\n\nb");
+
+        operate(&mut state, Operator::IndentRight, MoveToLastRow());
+        assert_eq!(state.lines, Lines::from("\ta\n\n\tb"));
+        assert_eq!(state.cursor, Index2::new(0, 1));
+
+        operate(&mut state, Operator::IndentLeft, MoveDown(0));
+        assert_eq!(state.lines, Lines::from("a\n\n\tb"));
+    }
+
+    #[test]
+    fn test_case() {
+        let mut state = test_state("Hello World");
+
+        operate(&mut state, Operator::Uppercase, MoveWordForward(1));
+        assert_eq!(state.lines, Lines::from("HELLO World"));
+
+        operate(&mut state, Operator::Lowercase, MoveToEndOfLine());
+        assert_eq!(state.lines, Lines::from("hello world"));
+
+        operate(&mut state, Operator::ToggleCase, MoveDown(0));
+        assert_eq!(state.lines, Lines::from("HELLO WORLD"));
+    }
+}
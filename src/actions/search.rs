@@ -1,4 +1,6 @@
-use crate::EditorState;
+use jagged::{index::RowIndex, Index2};
+
+use crate::{EditorState, ReplaceChoice, SearchOption};
 
 use super::Execute;
 
@@ -11,10 +13,7 @@ impl Execute for AppendCharToSearch {
     /// and triggering a search based on the updated buffer.
     fn execute(&mut self, state: &mut EditorState) {
         state.search.push_char(self.0);
-        state.search.trigger_search(&state.lines);
-        if let Some(index) = state.search.first() {
-            state.cursor = *index;
-        }
+        update_search(state);
     }
 }
 
@@ -27,7 +26,30 @@ impl Execute for RemoveCharFromSearch {
     /// and triggering a search based on the updated buffer.
     fn execute(&mut self, state: &mut EditorState) {
         state.search.remove_char();
-        state.search.trigger_search(&state.lines);
+        update_search(state);
+    }
+}
+
+/// Updates the matches of the search pattern and moves the cursor to the
+/// nearest match from where the search started, or back to the start if
+/// nothing matches.
+fn update_search(state: &mut EditorState) {
+    state.search.trigger_search(&state.lines);
+    state.cursor = match state.search.first() {
+        Some(index) => *index,
+        None => state.search.start_cursor,
+    };
+}
+
+/// Command to toggle a search option and trigger a search with it.
+#[derive(Clone, Debug, Copy)]
+pub struct ToggleSearchOption(pub SearchOption);
+
+impl Execute for ToggleSearchOption {
+    /// Executes the command, toggling the option and updating the matches.
+    fn execute(&mut self, state: &mut EditorState) {
+        state.search.options.toggle(self.0);
+        update_search(state);
     }
 }
 
@@ -40,13 +62,16 @@ impl Execute for FindFirst {
     /// the last cursor position and setting the cursor to the found match.
     /// Switches to normal mode.
     fn execute(&mut self, state: &mut EditorState) {
+        state.search.push_history();
         if let Some(index) = state.search.first() {
             state.cursor = *index;
+            state.changes.marks.push_jump(state.search.start_cursor);
         }
     }
 }
 
-/// Command to find the next search match and update the cursor position.
+/// Command to find the next search match in the direction of the search and
+/// update the cursor position.
 #[derive(Clone, Debug)]
 pub struct FindNext;
 
@@ -54,13 +79,15 @@ impl Execute for FindNext {
     /// Executes the command, finding the next search match and updating the cursor position.
     /// Switches to normal mode.
     fn execute(&mut self, state: &mut EditorState) {
-        if let Some(index) = state.search.next() {
-            state.cursor = *index;
+        if let Some(&index) = state.search.forward() {
+            state.push_jump();
+            state.cursor = index;
         }
     }
 }
 
-/// Command to find the previous search match and update the cursor position.
+/// Command to find the next search match against the direction of the search
+/// and update the cursor position.
 #[derive(Clone, Debug)]
 pub struct FindPrevious;
 
@@ -68,8 +95,9 @@ impl Execute for FindPrevious {
     /// Executes the command, finding the previous search match and updating the cursor position.
     /// Switches to normal mode.
     fn execute(&mut self, state: &mut EditorState) {
-        if let Some(index) = state.search.previous() {
-            state.cursor = *index;
+        if let Some(&index) = state.search.reverse() {
+            state.push_jump();
+            state.cursor = index;
         }
     }
 }
@@ -82,8 +110,10 @@ impl Execute for SelectCurrentSearch {
     /// Executes the command by moving the cursor to the currently selected
     /// search match, if one exists.
     fn execute(&mut self, state: &mut EditorState) {
+        state.search.push_history();
         if let Some(index) = state.search.current() {
             state.cursor = *index;
+            state.changes.marks.push_jump(state.search.start_cursor);
         }
     }
 }
@@ -98,6 +128,106 @@ impl Execute for StartSearch {
         state.search.start(state.cursor);
     }
 }
+
+/// Command to clear the start of a backward search and switch into search mode.
+#[derive(Clone, Debug)]
+pub struct StartBackwardSearch;
+
+impl Execute for StartBackwardSearch {
+    /// Executes the command, starting the search state in backward direction.
+    fn execute(&mut self, state: &mut EditorState) {
+        state.search.start(state.cursor);
+        state.search.backward = true;
+    }
+}
+
+/// Command to replace the search pattern with the previous entry of the
+/// search history.
+#[derive(Clone, Debug)]
+pub struct PreviousSearchHistory;
+
+impl Execute for PreviousSearchHistory {
+    /// Executes the command, recalling an older pattern and triggering a search.
+    fn execute(&mut self, state: &mut EditorState) {
+        state.search.history_previous();
+        update_search(state);
+    }
+}
+
+/// Command to replace the search pattern with the next entry of the search
+/// history, or the typed pattern after the newest entry.
+#[derive(Clone, Debug)]
+pub struct NextSearchHistory;
+
+impl Execute for NextSearchHistory {
+    /// Executes the command, recalling a newer pattern and triggering a search.
+    fn execute(&mut self, state: &mut EditorState) {
+        state.search.history_next();
+        update_search(state);
+    }
+}
+
+/// Command to search forward for the word under the cursor (`*`).
+#[derive(Clone, Debug)]
+pub struct SearchWordForward;
+
+impl Execute for SearchWordForward {
+    /// Executes the command, moving the cursor to the next occurrence of the
+    /// whole word under the cursor.
+    fn execute(&mut self, state: &mut EditorState) {
+        search_word(state, false);
+    }
+}
+
+/// Command to search backward for the word under the cursor (`#`).
+#[derive(Clone, Debug)]
+pub struct SearchWordBackward;
+
+impl Execute for SearchWordBackward {
+    /// Executes the command, moving the cursor to the previous occurrence of
+    /// the whole word under the cursor.
+    fn execute(&mut self, state: &mut EditorState) {
+        search_word(state, true);
+    }
+}
+
+/// Searches for the word under the cursor, or the next word on the line.
+fn search_word(state: &mut EditorState, backward: bool) {
+    let Some(line) = state.lines.get(RowIndex::new(state.cursor.row)) else {
+        return;
+    };
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let Some(start) = (state.cursor.col..line.len()).find(|&col| is_word(&line[col])) else {
+        return;
+    };
+    let start = line[..start]
+        .iter()
+        .rposition(|c| !is_word(c))
+        .map_or(0, |col| col + 1);
+    let end = line[start..]
+        .iter()
+        .position(|c| !is_word(c))
+        .map_or(line.len(), |len| start + len);
+    let word: String = line[start..end].iter().collect();
+
+    // Skip the word under the cursor itself.
+    let from = if backward {
+        Index2::new(state.cursor.row, start)
+    } else {
+        Index2::new(state.cursor.row, end)
+    };
+    state.search.start(from);
+    state.search.pattern = word;
+    state.search.backward = backward;
+    state.search.whole_word = true;
+    state.search.push_history();
+    state.search.trigger_search(&state.lines);
+    if let Some(&index) = state.search.first() {
+        state.push_jump();
+        state.cursor = index;
+    }
+}
+
 /// Command to clear the search state.
 #[derive(Clone, Debug)]
 pub struct StopSearch;
@@ -109,3 +239,14 @@ impl Execute for StopSearch {
         state.cursor = state.search.start_cursor;
     }
 }
+
+/// Command to answer a replace that waits for confirmation of each match.
+#[derive(Clone, Debug, Copy)]
+pub struct ConfirmReplace(pub ReplaceChoice);
+
+impl Execute for ConfirmReplace {
+    /// Executes the command, replacing or skipping the current match.
+    fn execute(&mut self, state: &mut EditorState) {
+        state.confirm_replace(self.0);
+    }
+}
@@ -1,8 +1,8 @@
 use jagged::index::RowIndex;
 
-use super::{delete::delete_selection, motion::CharacterClass, Execute};
+use super::{delete::delete_selection, motion::CharacterClass, operator::MotionKind, Execute};
 use crate::{
-    clipboard::ClipboardTrait, state::selection::Selection, EditorMode, EditorState, Index2, Lines,
+    clipboard::RegisterKind, state::selection::Selection, EditorMode, EditorState, Index2, Lines,
 };
 
 /// Selects text between specified delimiter characters.
@@ -10,21 +10,48 @@ use crate::{
 /// It searches for the first occurrence of a delimiter character in the text to
 /// define the start of the selection, and the next occurrence of any of the delimiter
 /// characters to define the end of the selection.
+///
+/// With a count, brackets select the text inside the count-th enclosing pair
+/// (`2i(`).
 #[derive(Clone, Debug, Copy)]
 pub struct SelectInnerBetween {
     opening: char,
     closing: char,
+    count: usize,
 }
 
 impl SelectInnerBetween {
     #[must_use]
     pub fn new(opening: char, closing: char) -> Self {
-        Self { opening, closing }
+        Self {
+            opening,
+            closing,
+            count: 1,
+        }
     }
 }
 
 impl Execute for SelectInnerBetween {
     fn execute(&mut self, state: &mut EditorState) {
+        if self.count > 1 && self.opening != self.closing {
+            let Some(line) = state.lines.get(RowIndex::new(state.cursor.row)) else {
+                return;
+            };
+            let delimiters = find_delimiters(
+                line,
+                state.cursor.col,
+                self.opening,
+                self.closing,
+                self.count,
+            );
+            if let Some((opening, closing)) = delimiters.filter(|(o, c)| o + 1 < *c) {
+                let row = state.cursor.row;
+                let selection =
+                    Selection::new(Index2::new(row, opening + 1), Index2::new(row, closing - 1));
+                select_object(state, selection);
+            }
+            return;
+        }
         if let Some(selection) = select_between(
             &state.lines,
             state.cursor,
@@ -33,9 +60,17 @@ impl Execute for SelectInnerBetween {
             |(_, _)| false,
             |(_, _)| false,
         ) {
-            state.selection = Some(selection);
+            select_object(state, selection);
         }
     }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
 }
 
 fn select_inner_motion<F>(state: &mut EditorState, is_boundary: F)
@@ -64,7 +99,7 @@ where
         |(_, col)| col == 0,
         |(_, col)| col == max_col_index,
     ) {
-        state.selection = Some(selection);
+        select_object(state, selection);
     }
 }
 
@@ -81,6 +116,10 @@ impl Execute for SelectInnerWord {
 
         select_inner_motion(state, move |ch| CharacterClass::from(ch) != start_class);
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
 }
 
 fn select_between(
@@ -155,10 +194,14 @@ impl Execute for DeleteInnerWord {
         if let Some(selection) = state.selection.take() {
             state.capture();
             let deleted = delete_selection(state, &selection);
-            state.clip.set_text(deleted.into());
+            state.clip.set_deleted(deleted, RegisterKind::Charwise);
         }
         state.mode = EditorMode::Normal;
     }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -177,6 +220,10 @@ impl Execute for SelectInnerBigWord {
             (CharacterClass::from(ch) == CharacterClass::Whitespace) != start_is_whitespace
         });
     }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
 }
 
 /// Deletes the inner WORD under the cursor, leaving the editor in normal mode.
@@ -190,10 +237,14 @@ impl Execute for DeleteInnerBigWord {
         if let Some(selection) = state.selection.take() {
             state.capture();
             let deleted = delete_selection(state, &selection);
-            state.clip.set_text(deleted.into());
+            state.clip.set_deleted(deleted, RegisterKind::Charwise);
         }
         state.mode = EditorMode::Normal;
     }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
 }
 
 /// Deletes the text between the given delimiters, leaving the editor in
@@ -217,10 +268,594 @@ impl Execute for DeleteInnerBetween {
         if let Some(selection) = state.selection.take() {
             state.capture();
             let deleted = delete_selection(state, &selection);
-            state.clip.set_text(deleted.into());
+            state.clip.set_deleted(deleted, RegisterKind::Charwise);
         }
         state.mode = EditorMode::Normal;
     }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
+}
+
+/// Sets the selection of a text object. In visual mode, the cursor moves to
+/// the end of the object and the mode follows its kind, like `vip` in Vim
+/// switches to visual line mode.
+fn select_object(state: &mut EditorState, selection: Selection) {
+    if state.mode.is_visual() {
+        state.cursor = selection.end;
+        state.mode = if selection.line_mode {
+            EditorMode::VisualLine
+        } else {
+            EditorMode::Visual
+        };
+    }
+    state.selection = Some(selection);
+}
+
+/// Selects a word and the whitespace after it, or the whitespace before it if
+/// there is none. On whitespace, selects it and the following word.
+fn select_around_word(state: &mut EditorState, big: bool) {
+    if big {
+        SelectInnerBigWord.execute(state);
+    } else {
+        SelectInnerWord.execute(state);
+    }
+    let Some(mut selection) = state.selection.take() else {
+        return;
+    };
+    let Some(line) = state.lines.get(RowIndex::new(state.cursor.row)) else {
+        return;
+    };
+    let is_space = |col: usize| line.get(col).is_some_and(|ch| ch.is_ascii_whitespace());
+    let same_word = |a: &char, b: &char| {
+        if big {
+            !a.is_ascii_whitespace() && !b.is_ascii_whitespace()
+        } else {
+            CharacterClass::from(a) == CharacterClass::from(b)
+        }
+    };
+
+    let (mut start, mut end) = (selection.start.col, selection.end.col);
+    if is_space(start) {
+        if let Some(first) = line.get(end + 1) {
+            while line.get(end + 1).is_some_and(|ch| same_word(ch, first)) {
+                end += 1;
+            }
+        }
+    } else if is_space(end + 1) {
+        while is_space(end + 1) {
+            end += 1;
+        }
+    } else {
+        while start > 0 && is_space(start - 1) {
+            start -= 1;
+        }
+    }
+    selection.start.col = start;
+    selection.end.col = end;
+    select_object(state, selection);
+}
+
+/// Selects a word and its surrounding whitespace. This is the `aw` text
+/// object.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectAroundWord;
+
+impl Execute for SelectAroundWord {
+    fn execute(&mut self, state: &mut EditorState) {
+        select_around_word(state, false);
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
+}
+
+/// Selects a WORD and its surrounding whitespace. This is the `aW` text
+/// object.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectAroundBigWord;
+
+impl Execute for SelectAroundBigWord {
+    fn execute(&mut self, state: &mut EditorState) {
+        select_around_word(state, true);
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
+}
+
+/// Selects text between specified delimiter characters, including the
+/// delimiters. This is the `a(`, `a"`, ... text object.
+///
+/// Brackets may be nested, and a count selects the count-th enclosing pair
+/// (`2a(`). For quotes, where both delimiters are the same, quotes are paired
+/// up from the start of the line, and the whitespace after the closing quote
+/// is included, or else the whitespace before the opening quote.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectAroundBetween {
+    opening: char,
+    closing: char,
+    count: usize,
+}
+
+impl SelectAroundBetween {
+    #[must_use]
+    pub fn new(opening: char, closing: char) -> Self {
+        Self {
+            opening,
+            closing,
+            count: 1,
+        }
+    }
+}
+
+impl Execute for SelectAroundBetween {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(line) = state.lines.get(RowIndex::new(state.cursor.row)) else {
+            return;
+        };
+        let delimiters = find_delimiters(
+            line,
+            state.cursor.col,
+            self.opening,
+            self.closing,
+            self.count,
+        );
+        let Some((mut opening, mut closing)) = delimiters else {
+            return;
+        };
+        if self.opening == self.closing {
+            let is_space = |col: usize| line.get(col).is_some_and(|ch| ch.is_ascii_whitespace());
+            if is_space(closing + 1) {
+                while is_space(closing + 1) {
+                    closing += 1;
+                }
+            } else {
+                while opening > 0 && is_space(opening - 1) {
+                    opening -= 1;
+                }
+            }
+        }
+        let row = state.cursor.row;
+        let selection = Selection::new(Index2::new(row, opening), Index2::new(row, closing));
+        select_object(state, selection);
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.count)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
+}
+
+/// Returns the columns of the delimiters around `col`. For brackets, returns
+/// the `count`-th enclosing pair.
+fn find_delimiters(
+    line: &[char],
+    col: usize,
+    opening: char,
+    closing: char,
+    count: usize,
+) -> Option<(usize, usize)> {
+    if opening == closing {
+        let quotes: Vec<usize> = (0..line.len()).filter(|&i| line[i] == opening).collect();
+        return quotes
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .find(|&(_, end)| col <= end);
+    }
+
+    let mut depth = 0;
+    let mut start = None;
+    let mut enclosing = 0;
+    for i in (0..=col.min(line.len().checked_sub(1)?)).rev() {
+        if line[i] == closing && i != col {
+            depth += 1;
+        } else if line[i] == opening {
+            if depth > 0 {
+                depth -= 1;
+                continue;
+            }
+            enclosing += 1;
+            if enclosing >= count {
+                start = Some(i);
+                break;
+            }
+        }
+    }
+    let start = start?;
+
+    let mut depth = 0;
+    for (i, &ch) in line.iter().enumerate().skip(start + 1) {
+        if ch == opening {
+            depth += 1;
+        } else if ch == closing {
+            if depth == 0 {
+                return Some((start, i));
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// Returns whether a line is empty or contains only whitespace.
+fn is_blank(lines: &Lines, row: usize) -> bool {
+    lines
+        .get(RowIndex::new(row))
+        .is_some_and(|line| line.iter().all(char::is_ascii_whitespace))
+}
+
+/// Returns the rows of the paragraph, or block of blank lines, at `row`.
+fn paragraph_rows(lines: &Lines, row: usize) -> (usize, usize) {
+    let blank = is_blank(lines, row);
+    let mut start = row;
+    while start > 0 && is_blank(lines, start - 1) == blank {
+        start -= 1;
+    }
+    let mut end = row;
+    while end < lines.last_row_index() && is_blank(lines, end + 1) == blank {
+        end += 1;
+    }
+    (start, end)
+}
+
+/// Selects whole lines from `start` to `end`.
+fn select_rows(state: &mut EditorState, start: usize, end: usize) {
+    let len_col = state.lines.len_col(end).unwrap_or_default();
+    let end = Index2::new(end, len_col.saturating_sub(1));
+    select_object(
+        state,
+        Selection::new(Index2::new(start, 0), end).line_mode(),
+    );
+}
+
+/// Selects the paragraph under the cursor, or the blank lines if the cursor is
+/// on one. This is the linewise `ip` text object. With a count, the
+/// paragraphs and blank lines after it are included, each counting as one.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectInnerParagraph(pub usize);
+
+impl Execute for SelectInnerParagraph {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.lines.is_empty() {
+            return;
+        }
+        let (start, mut end) = paragraph_rows(&state.lines, state.cursor.row);
+        for _ in 1..self.0 {
+            if end == state.lines.last_row_index() {
+                break;
+            }
+            end = paragraph_rows(&state.lines, end + 1).1;
+        }
+        select_rows(state, start, end);
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
+}
+
+/// Selects the paragraph under the cursor and the blank lines after it, or
+/// before it if there are none. This is the linewise `ap` text object. A
+/// count selects that many paragraphs.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectAroundParagraph(pub usize);
+
+impl Execute for SelectAroundParagraph {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.lines.is_empty() {
+            return;
+        }
+        let last = state.lines.last_row_index();
+        let (mut start, mut end) = paragraph_rows(&state.lines, state.cursor.row);
+        for i in 0..self.0.max(1) {
+            if i > 0 {
+                if end == last {
+                    break;
+                }
+                end = paragraph_rows(&state.lines, end + 1).1;
+            }
+            if end < last {
+                end = paragraph_rows(&state.lines, end + 1).1;
+            } else if i == 0 && start > 0 && !is_blank(&state.lines, start) {
+                start = paragraph_rows(&state.lines, start - 1).0;
+            }
+        }
+        select_rows(state, start, end);
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
+}
+
+/// Returns the characters of the given rows with their positions. Rows are
+/// joined by a `'\n'` positioned just past the end of the row.
+fn flatten(lines: &Lines, start: usize, end: usize) -> Vec<(char, Index2)> {
+    let mut chars = Vec::new();
+    for row in start..=end {
+        let Some(line) = lines.get(RowIndex::new(row)) else {
+            break;
+        };
+        if row > start {
+            let prev = lines.len_col(row - 1).unwrap_or_default();
+            chars.push(('\n', Index2::new(row - 1, prev)));
+        }
+        for (col, ch) in line.iter().enumerate() {
+            chars.push((*ch, Index2::new(row, col)));
+        }
+    }
+    chars
+}
+
+/// Returns the selection from `start` to `end` of flattened characters, not
+/// starting or ending on a line break.
+fn flat_selection(chars: &[(char, Index2)], start: usize, end: usize) -> Option<Selection> {
+    let start = (start..=end).find(|&i| chars[i].0 != '\n')?;
+    let end = (start..=end).rev().find(|&i| chars[i].0 != '\n')?;
+    Some(Selection::new(chars[start].1, chars[end].1))
+}
+
+/// Returns the sentences of flattened characters as inclusive index ranges.
+/// A sentence ends at a `.`, `!` or `?`, optionally followed by closing
+/// brackets or quotes, and then whitespace or the end of the text.
+fn sentences(chars: &[(char, Index2)]) -> Vec<(usize, usize)> {
+    let is_space = |i: usize| chars.get(i).is_none_or(|(ch, _)| ch.is_whitespace());
+    let ends_sentence = |i: usize| {
+        if !matches!(chars[i].0, '.' | '!' | '?') {
+            return None;
+        }
+        let mut end = i;
+        while chars
+            .get(end + 1)
+            .is_some_and(|(ch, _)| matches!(ch, ')' | ']' | '"' | '\''))
+        {
+            end += 1;
+        }
+        is_space(end + 1).then_some(end)
+    };
+
+    let mut sentences = Vec::new();
+    let mut i = 0;
+    loop {
+        while i < chars.len() && is_space(i) {
+            i += 1;
+        }
+        if i >= chars.len() {
+            return sentences;
+        }
+        let start = i;
+        let mut end = None;
+        while i < chars.len() {
+            if let Some(last) = ends_sentence(i) {
+                end = Some(last);
+                break;
+            }
+            i += 1;
+        }
+        let end = end.unwrap_or_else(|| {
+            (start..chars.len())
+                .rev()
+                .find(|&j| !is_space(j))
+                .unwrap_or(start)
+        });
+        sentences.push((start, end));
+        i = end + 1;
+    }
+}
+
+/// Selects the sentence under the cursor, optionally with the whitespace
+/// after it (or before it, if there is none). On whitespace between
+/// sentences, selects the whitespace and, with `around`, the next sentence.
+fn select_sentence(state: &mut EditorState, around: bool) {
+    if is_blank(&state.lines, state.cursor.row) {
+        return;
+    }
+    let (start_row, end_row) = paragraph_rows(&state.lines, state.cursor.row);
+    let chars = flatten(&state.lines, start_row, end_row);
+    let Some(cursor) = chars.iter().position(|(_, pos)| *pos == state.cursor) else {
+        return;
+    };
+
+    let sentences = sentences(&chars);
+    let last = chars.len() - 1;
+    let (start, end) = match sentences.iter().position(|&(_, end)| cursor <= end) {
+        Some(i) if sentences[i].0 <= cursor => {
+            let (start, end) = sentences[i];
+            let next = sentences.get(i + 1).map_or(last, |s| s.0 - 1);
+            if !around {
+                (start, end)
+            } else if next > end {
+                (start, next)
+            } else {
+                let prev = if i == 0 { 0 } else { sentences[i - 1].1 + 1 };
+                (prev, end)
+            }
+        }
+        Some(i) => {
+            let gap = if i == 0 { 0 } else { sentences[i - 1].1 + 1 };
+            let (next_start, next_end) = sentences[i];
+            if around {
+                (gap, next_end)
+            } else {
+                (gap, next_start - 1)
+            }
+        }
+        None => {
+            let gap = sentences.last().map_or(0, |s| s.1 + 1);
+            (gap, last)
+        }
+    };
+
+    if let Some(selection) = flat_selection(&chars, start, end) {
+        select_object(state, selection);
+    }
+}
+
+/// Selects the sentence under the cursor. This is the `is` text object.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectInnerSentence;
+
+impl Execute for SelectInnerSentence {
+    fn execute(&mut self, state: &mut EditorState) {
+        select_sentence(state, false);
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
+}
+
+/// Selects the sentence under the cursor and the whitespace after it. This
+/// is the `as` text object.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectAroundSentence;
+
+impl Execute for SelectAroundSentence {
+    fn execute(&mut self, state: &mut EditorState) {
+        select_sentence(state, true);
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
+}
+
+/// A matched pair of XML/HTML tags, as indices of flattened characters.
+struct TagPair {
+    /// The `<` of the opening tag.
+    open_start: usize,
+    /// The `>` of the opening tag.
+    open_end: usize,
+    /// The `<` of the closing tag.
+    close_start: usize,
+    /// The `>` of the closing tag.
+    close_end: usize,
+}
+
+/// Returns the `count`-th innermost pair of tags around `cursor`.
+/// Self-closing tags, comments and unmatched tags are skipped.
+fn find_tag_pair(chars: &[(char, Index2)], cursor: usize, count: usize) -> Option<TagPair> {
+    let mut open: Vec<(String, usize, usize)> = Vec::new();
+    let mut enclosing: Vec<TagPair> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].0 != '<' {
+            i += 1;
+            continue;
+        }
+        let Some(len) = chars[i..].iter().position(|(ch, _)| *ch == '>') else {
+            break;
+        };
+        let end = i + len;
+        let tag: String = chars[i + 1..end].iter().map(|(ch, _)| ch).collect();
+        let name = |tag: &str| {
+            tag.split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        if let Some(closing) = tag.strip_prefix('/') {
+            let name = name(closing);
+            if let Some(index) = open.iter().rposition(|(open, _, _)| *open == name) {
+                let (_, open_start, open_end) = open[index];
+                open.truncate(index);
+                if open_start <= cursor && cursor <= end {
+                    enclosing.push(TagPair {
+                        open_start,
+                        open_end,
+                        close_start: i,
+                        close_end: end,
+                    });
+                }
+            }
+        } else if !tag.starts_with(['!', '?']) && !tag.ends_with('/') {
+            open.push((name(&tag), i, end));
+        }
+        i = end + 1;
+    }
+    // Enclosing pairs are nested, so the inner ones open later.
+    enclosing.sort_by_key(|pair| std::cmp::Reverse(pair.open_start));
+    enclosing.into_iter().nth(count.checked_sub(1)?)
+}
+
+/// Selects the contents of the `count`-th tag block around the cursor, or the
+/// whole block including the tags with `around`.
+fn select_tag(state: &mut EditorState, around: bool, count: usize) {
+    if state.lines.is_empty() {
+        return;
+    }
+    let chars = flatten(&state.lines, 0, state.lines.last_row_index());
+    let Some(cursor) = chars.iter().position(|(_, pos)| *pos == state.cursor) else {
+        return;
+    };
+    let Some(pair) = find_tag_pair(&chars, cursor, count) else {
+        return;
+    };
+    let (start, end) = if around {
+        (pair.open_start, pair.close_end)
+    } else if pair.open_end + 1 < pair.close_start {
+        (pair.open_end + 1, pair.close_start - 1)
+    } else {
+        return;
+    };
+    if let Some(selection) = flat_selection(&chars, start, end) {
+        select_object(state, selection);
+    }
+}
+
+/// Selects the contents of the XML/HTML tag block around the cursor. This is
+/// the `it` text object. With a count, selects the contents of the count-th
+/// enclosing block (`2it`).
+#[derive(Clone, Debug, Copy)]
+pub struct SelectInnerTag(pub usize);
+
+impl Execute for SelectInnerTag {
+    fn execute(&mut self, state: &mut EditorState) {
+        select_tag(state, false, self.0);
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
+}
+
+/// Selects the XML/HTML tag block around the cursor, including the tags. This
+/// is the `at` text object. A count selects the count-th enclosing block.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectAroundTag(pub usize);
+
+impl Execute for SelectAroundTag {
+    fn execute(&mut self, state: &mut EditorState) {
+        select_tag(state, true, self.0);
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Inclusive)
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -233,11 +868,35 @@ impl Execute for SelectLine {
             let start = Index2::new(row, 0);
             let end = Index2::new(row, len_col.saturating_sub(1));
             state.selection = Some(Selection::new(start, end).line_mode());
-            state.mode = EditorMode::Visual;
+            state.mode = EditorMode::VisualLine;
         }
     }
 }
 
+/// Selects `count` lines starting at the cursor without entering visual
+/// mode. This is the text object behind doubled operators like `dd` or `yy`.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectLines(pub usize);
+
+impl Execute for SelectLines {
+    fn execute(&mut self, state: &mut EditorState) {
+        let start = state.cursor.row;
+        let end = (start + self.0.saturating_sub(1)).min(state.lines.last_row_index());
+        if let Some(len_col) = state.lines.len_col(end) {
+            let end = Index2::new(end, len_col.saturating_sub(1));
+            state.selection = Some(Selection::new(Index2::new(start, 0), end).line_mode());
+        }
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+
+    fn motion_kind(&self) -> Option<MotionKind> {
+        Some(MotionKind::Linewise)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::state::selection::Selection;
@@ -260,7 +919,7 @@ mod tests {
         let selection = state.selection.as_ref().unwrap();
         assert!(selection.line_mode);
         assert!(selection.contains_row(0));
-        assert_eq!(state.mode, EditorMode::Visual);
+        assert_eq!(state.mode, EditorMode::VisualLine);
         assert_eq!(state.cursor, Index2::new(0, 4));
     }
 
@@ -563,4 +1222,117 @@ mod tests {
         let want = Selection::new(Index2::new(0, 0), Index2::new(0, 6));
         assert_eq!(state.selection.unwrap(), want);
     }
+
+    #[test]
+    fn test_select_around_word() {
+        let mut state = EditorState::new(Lines::from("foo bar  baz"));
+        state.cursor = Index2::new(0, 5);
+
+        SelectAroundWord.execute(&mut state);
+        let want = Selection::new(Index2::new(0, 4), Index2::new(0, 8));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        // Without trailing whitespace, the leading whitespace is selected.
+        state.cursor = Index2::new(0, 10);
+        SelectAroundWord.execute(&mut state);
+        let want = Selection::new(Index2::new(0, 7), Index2::new(0, 11));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        // On whitespace, the following word is selected as well.
+        state.cursor = Index2::new(0, 3);
+        SelectAroundBigWord.execute(&mut state);
+        let want = Selection::new(Index2::new(0, 3), Index2::new(0, 6));
+        assert_eq!(state.selection.unwrap(), want);
+    }
+
+    #[test]
+    fn test_select_around_between() {
+        let mut state = EditorState::new(Lines::from("f(a, (b), c) \"x\""));
+        state.cursor = Index2::new(0, 10);
+
+        SelectAroundBetween::new('(', ')').execute(&mut state);
+        let want = Selection::new(Index2::new(0, 1), Index2::new(0, 11));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        state.cursor = Index2::new(0, 5);
+        SelectAroundBetween::new('(', ')').execute(&mut state);
+        let want = Selection::new(Index2::new(0, 5), Index2::new(0, 7));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        // Without whitespace after the quotes, the whitespace before is taken.
+        state.cursor = Index2::new(0, 15);
+        SelectAroundBetween::new('"', '"').execute(&mut state);
+        let want = Selection::new(Index2::new(0, 12), Index2::new(0, 15));
+        assert_eq!(state.selection.unwrap(), want);
+    }
+
+    #[test]
+    fn test_select_paragraph() {
+        let mut state = EditorState::new(Lines::from("a\nb\n\n\nc"));
+        state.mode = EditorMode::Visual;
+
+        SelectInnerParagraph(1).execute(&mut state);
+        let want = Selection::new(Index2::new(0, 0), Index2::new(1, 0)).line_mode();
+        assert_eq!(state.selection.take().unwrap(), want);
+        assert_eq!(state.mode, EditorMode::VisualLine);
+        assert_eq!(state.cursor, Index2::new(1, 0));
+
+        SelectAroundParagraph(1).execute(&mut state);
+        let want = Selection::new(Index2::new(0, 0), Index2::new(3, 0)).line_mode();
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        // The last paragraph takes the blank lines before it.
+        state.cursor = Index2::new(4, 0);
+        SelectAroundParagraph(1).execute(&mut state);
+        let want = Selection::new(Index2::new(2, 0), Index2::new(4, 0)).line_mode();
+        assert_eq!(state.selection.unwrap(), want);
+    }
+
+    #[test]
+    fn test_select_sentence() {
+        let mut state = EditorState::new(Lines::from("One. Two is\nlong!  Three"));
+        state.cursor = Index2::new(0, 6);
+
+        SelectInnerSentence.execute(&mut state);
+        let want = Selection::new(Index2::new(0, 5), Index2::new(1, 4));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        SelectAroundSentence.execute(&mut state);
+        let want = Selection::new(Index2::new(0, 5), Index2::new(1, 6));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        // The last sentence takes the whitespace before it.
+        state.cursor = Index2::new(1, 8);
+        SelectAroundSentence.execute(&mut state);
+        let want = Selection::new(Index2::new(1, 5), Index2::new(1, 11));
+        assert_eq!(state.selection.unwrap(), want);
+    }
+
+    #[test]
+    fn test_select_tag() {
+        let mut state = EditorState::new(Lines::from("<div a=\"1\">\n  <b>x</b><br/>\n</div>"));
+        state.cursor = Index2::new(1, 3);
+
+        SelectInnerTag(1).execute(&mut state);
+        let want = Selection::new(Index2::new(1, 5), Index2::new(1, 5));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        SelectAroundTag(1).execute(&mut state);
+        let want = Selection::new(Index2::new(1, 2), Index2::new(1, 9));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        // A count selects an enclosing block.
+        SelectAroundTag(2).execute(&mut state);
+        let want = Selection::new(Index2::new(0, 0), Index2::new(2, 5));
+        assert_eq!(state.selection.take().unwrap(), want);
+
+        SelectInnerTag(3).execute(&mut state);
+        assert_eq!(state.selection, None);
+
+        // Line breaks at the edges of the contents are not selected.
+        state.cursor = Index2::new(1, 12);
+        SelectInnerTag(1).execute(&mut state);
+        let want = Selection::new(Index2::new(1, 0), Index2::new(1, 14));
+        assert_eq!(state.selection.unwrap(), want);
+    }
 }
@@ -23,6 +23,10 @@ impl Execute for OpenSystemEditor {
     fn execute(&mut self, state: &mut EditorState) {
         state.system_edit_requested = true;
     }
+
+    fn is_edit(&self) -> bool {
+        true
+    }
 }
 
 /// Opens the editor content in an external system editor if a request is pending.
@@ -60,7 +64,10 @@ pub fn open<B: Backend>(state: &mut EditorState, terminal: &mut Terminal<B>) ->
 
     let edited = result.map_err(std::io::Error::other)?;
 
-    state.lines = Lines::from(edited.trim_end_matches('\n'));
+    let rows = 0..=state.lines.len();
+    state.changes.edit(&mut state.lines, rows, |lines| {
+        *lines = Lines::from(edited.trim_end_matches('\n'));
+    });
     state.cursor = Index2::new(0, 0);
     state.selection = None;
 
@@ -0,0 +1,314 @@
+//! Yank actions.
+//!
+//! A "yank" copies the text a delete would remove, without touching the
+//! buffer. Each yank command runs the [`Operator::Yank`] operator over the
+//! same motion or text object as its delete counterpart. Like Vim, the cursor
+//! moves to the start of the yanked text, so `yw` stays put and `yb` or `yiw`
+//! move back.
+
+use super::motion::{
+    FindForward, MoveBigWordForward, MoveBigWordForwardToEndOfWord, MoveToEndOfLine, MoveToFirst,
+    MoveWordBackward, MoveWordForward, MoveWordForwardToEndOfWord, TillForward,
+};
+use super::operator::{Operate, Operator};
+use super::select::{SelectInnerBetween, SelectInnerBigWord, SelectInnerWord, SelectLines};
+use super::{Action, Execute};
+use crate::EditorState;
+
+/// Copies the text covered by a motion to the clipboard.
+fn yank<A: Into<Action>>(state: &mut EditorState, motion: A) {
+    Operate::new(Operator::Yank)
+        .with_motion(motion)
+        .execute(state);
+}
+
+/// Copies from the cursor to the start of the next word (Vim `yw`).
+#[derive(Clone, Debug, Copy)]
+pub struct YankWordForward(pub usize);
+
+impl Execute for YankWordForward {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, MoveWordForward(self.0));
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Copies from the cursor to the start of the next WORD (Vim `yW`).
+#[derive(Clone, Debug, Copy)]
+pub struct YankBigWordForward(pub usize);
+
+impl Execute for YankBigWordForward {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, MoveBigWordForward(self.0));
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Copies from the cursor to the end of the word (Vim `ye`).
+#[derive(Clone, Debug, Copy)]
+pub struct YankWordEnd(pub usize);
+
+impl Execute for YankWordEnd {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, MoveWordForwardToEndOfWord(self.0));
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Copies from the cursor to the end of the WORD (Vim `yE`).
+#[derive(Clone, Debug, Copy)]
+pub struct YankBigWordEnd(pub usize);
+
+impl Execute for YankBigWordEnd {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, MoveBigWordForwardToEndOfWord(self.0));
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Copies from the start of the previous word up to the cursor (Vim `yb`).
+#[derive(Clone, Debug, Copy)]
+pub struct YankWordBackward(pub usize);
+
+impl Execute for YankWordBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, MoveWordBackward(self.0));
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Copies whole lines starting at the cursor (Vim `yy` and `Y`).
+#[derive(Clone, Debug, Copy)]
+pub struct YankLine(pub usize);
+
+impl Execute for YankLine {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, SelectLines(self.0));
+    }
+
+    fn count(&mut self) -> Option<&mut usize> {
+        Some(&mut self.0)
+    }
+}
+
+/// Copies from the first non-whitespace character of the line up to the
+/// cursor (Vim `y^`).
+#[derive(Clone, Debug, Copy)]
+pub struct YankToFirstCharOfLine;
+
+impl Execute for YankToFirstCharOfLine {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, MoveToFirst());
+    }
+}
+
+/// Copies from the cursor to the end of the line (Vim `y$`).
+#[derive(Clone, Debug, Copy)]
+pub struct YankToEndOfLine;
+
+impl Execute for YankToEndOfLine {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, MoveToEndOfLine());
+    }
+}
+
+/// Copies from the cursor up to and including the next occurrence of a
+/// character on the line (Vim `yf<char>`). Does nothing if the character is
+/// not found.
+///
+/// The target is `None` until the key handler supplies the next keystroke via
+/// [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct YankFindForward(pub Option<char>);
+
+impl Execute for YankFindForward {
+    fn execute(&mut self, state: &mut EditorState) {
+        if self.0.is_some() {
+            yank(
+                state,
+                FindForward {
+                    target: self.0,
+                    count: 1,
+                },
+            );
+        }
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
+/// Copies from the cursor up to (but not including) the next occurrence of a
+/// character on the line (Vim `yt<char>`). Does nothing if the character is
+/// not found.
+///
+/// The target is `None` until the key handler supplies the next keystroke via
+/// [`Execute::char_arg`].
+#[derive(Clone, Debug, Copy)]
+pub struct YankTillForward(pub Option<char>);
+
+impl Execute for YankTillForward {
+    fn execute(&mut self, state: &mut EditorState) {
+        if self.0.is_some() {
+            yank(
+                state,
+                TillForward {
+                    target: self.0,
+                    count: 1,
+                },
+            );
+        }
+    }
+
+    fn char_arg(&mut self) -> Option<&mut Option<char>> {
+        Some(&mut self.0)
+    }
+}
+
+/// Copies the inner word under the cursor. This is the `yiw` primitive.
+#[derive(Clone, Debug, Copy)]
+pub struct YankInnerWord;
+
+impl Execute for YankInnerWord {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, SelectInnerWord);
+    }
+}
+
+/// Copies the inner WORD under the cursor. This is the `yiW` primitive.
+#[derive(Clone, Debug, Copy)]
+pub struct YankInnerBigWord;
+
+impl Execute for YankInnerBigWord {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, SelectInnerBigWord);
+    }
+}
+
+/// Copies the text between the given delimiters. This is the `yi<delim>`
+/// primitive.
+#[derive(Clone, Debug, Copy)]
+pub struct YankInnerBetween {
+    opening: char,
+    closing: char,
+}
+
+impl YankInnerBetween {
+    #[must_use]
+    pub fn new(opening: char, closing: char) -> Self {
+        Self { opening, closing }
+    }
+}
+
+impl Execute for YankInnerBetween {
+    fn execute(&mut self, state: &mut EditorState) {
+        yank(state, SelectInnerBetween::new(self.opening, self.closing));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::{ClipboardTrait, InternalClipboard};
+    use crate::{Index2, Lines};
+
+    fn test_state(text: &str) -> EditorState {
+        let mut state = EditorState::new(Lines::from(text));
+        state.set_clipboard(InternalClipboard::default());
+        state
+    }
+
+    #[test]
+    fn test_yank_word_forward() {
+        let mut state = test_state("foo bar baz");
+        state.cursor = Index2::new(0, 4);
+
+        YankWordForward(1).execute(&mut state);
+        assert_eq!(state.clip.get_text(), "bar ");
+        assert_eq!(state.lines, Lines::from("foo bar baz"));
+        assert_eq!(state.cursor, Index2::new(0, 4));
+        assert!(!state.can_undo());
+    }
+
+    #[test]
+    fn test_yank_word_backward_moves_cursor() {
+        let mut state = test_state("foo bar baz");
+        state.cursor = Index2::new(0, 8);
+
+        YankWordBackward(2).execute(&mut state);
+        assert_eq!(state.clip.get_text(), "foo bar ");
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
+    #[test]
+    fn test_yank_inner_word() {
+        let mut state = test_state("foo bar baz");
+        state.cursor = Index2::new(0, 5);
+
+        YankInnerWord.execute(&mut state);
+        assert_eq!(state.clip.get_text(), "bar");
+        assert_eq!(state.cursor, Index2::new(0, 4));
+        assert!(!state.can_undo());
+    }
+
+    #[test]
+    fn test_yank_to_end_of_line() {
+        let mut state = test_state("foo bar\nbaz");
+        state.cursor = Index2::new(0, 4);
+
+        YankToEndOfLine.execute(&mut state);
+        assert_eq!(state.clip.get_text(), "bar");
+        assert_eq!(state.cursor, Index2::new(0, 4));
+    }
+
+    #[test]
+    fn test_yank_inner_between() {
+        let mut state = test_state("f(a, b) c");
+        state.cursor = Index2::new(0, 3);
+
+        YankInnerBetween::new('(', ')').execute(&mut state);
+        assert_eq!(state.clip.get_text(), "a, b");
+        assert_eq!(state.lines, Lines::from("f(a, b) c"));
+        assert_eq!(state.cursor, Index2::new(0, 2));
+    }
+
+    #[test]
+    fn test_yank_find_forward() {
+        let mut state = test_state("foo.bar");
+
+        YankFindForward(Some('.')).execute(&mut state);
+        assert_eq!(state.clip.get_text(), "foo.");
+
+        state.clip.set_text(String::new());
+        YankTillForward(Some('x')).execute(&mut state);
+        assert_eq!(state.clip.get_text(), "");
+    }
+
+    #[test]
+    fn test_yank_lines() {
+        let mut state = test_state("one\ntwo\nthree\nfour");
+        state.cursor = Index2::new(1, 2);
+
+        YankLine(3).execute(&mut state);
+        assert_eq!(state.clip.get_text(), "two\nthree\nfour");
+        assert_eq!(state.lines, Lines::from("one\ntwo\nthree\nfour"));
+        assert_eq!(state.cursor, Index2::new(1, 2));
+        assert!(!state.can_undo());
+    }
+}
@@ -47,9 +47,25 @@
 //!```
 #[cfg(feature = "arboard")]
 mod arboard;
+mod registers;
+
+pub(crate) use registers::Registers;
 
 use std::{cell::RefCell, rc::Rc};
 
+/// How text in the clipboard or a register is pasted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RegisterKind {
+    /// Text is pasted inside the line, e.g. after `yw`.
+    #[default]
+    Charwise,
+    /// Text is pasted as whole lines, e.g. after `yy`.
+    Linewise,
+    /// Each line of the text is pasted into its own row at the cursor column,
+    /// e.g. after yanking a visual block.
+    Blockwise,
+}
+
 /// Trait defining clipboard operations.
 pub trait ClipboardTrait {
     /// Sets text to the clipboard.
@@ -57,6 +73,18 @@ pub trait ClipboardTrait {
 
     /// Retrieves text from the clipboard.
     fn get_text(&mut self) -> String;
+
+    /// Sets text together with its kind. Clipboards that cannot store the
+    /// kind only keep the text.
+    fn set_text_with_kind(&mut self, text: String, kind: RegisterKind) {
+        let _ = kind;
+        self.set_text(text);
+    }
+
+    /// Retrieves text together with its kind. Defaults to characterwise text.
+    fn get_text_with_kind(&mut self) -> (String, RegisterKind) {
+        (self.get_text(), RegisterKind::Charwise)
+    }
 }
 
 /// A clipboard for the editor.
@@ -102,19 +130,35 @@ impl ClipboardTrait for Clipboard {
     fn get_text(&mut self) -> String {
         self.0.borrow_mut().get_text()
     }
+
+    fn set_text_with_kind(&mut self, text: String, kind: RegisterKind) {
+        self.0.borrow_mut().set_text_with_kind(text, kind);
+    }
+
+    fn get_text_with_kind(&mut self) -> (String, RegisterKind) {
+        self.0.borrow_mut().get_text_with_kind()
+    }
 }
 
 #[derive(Default)]
-pub struct InternalClipboard(String);
+pub struct InternalClipboard(String, RegisterKind);
 
 impl ClipboardTrait for InternalClipboard {
     fn set_text(&mut self, text: String) {
-        self.0 = text;
+        self.set_text_with_kind(text, RegisterKind::Charwise);
     }
 
     fn get_text(&mut self) -> String {
         self.0.clone()
     }
+
+    fn set_text_with_kind(&mut self, text: String, kind: RegisterKind) {
+        (self.0, self.1) = (text, kind);
+    }
+
+    fn get_text_with_kind(&mut self) -> (String, RegisterKind) {
+        (self.0.clone(), self.1)
+    }
 }
 
 #[cfg(not(feature = "arboard"))]
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use super::{Clipboard, ClipboardTrait, RegisterKind};
+
+/// The editors registers.
+///
+/// - `"`, `+` and `*` are backed by the [`Clipboard`], so by default yanks and
+///   deletes go to the system clipboard, as before registers existed.
+/// - `a` to `z` are named registers. Writing to `A` to `Z` appends to them.
+/// - `0` holds the last yank, `1` to `9` the history of multi-line deletes
+///   and `-` the last delete within a line.
+/// - `_` is the black-hole register: writes are discarded, reads are empty.
+///
+/// Each register remembers the [`RegisterKind`] of its text, which decides
+/// how it is pasted. The text itself never carries extra line breaks.
+#[derive(Clone)]
+pub(crate) struct Registers {
+    clipboard: Clipboard,
+    registers: HashMap<char, (String, RegisterKind)>,
+    /// The text last written to the clipboard, for clipboards that cannot
+    /// store its kind.
+    clipboard_text: Option<(String, RegisterKind)>,
+    /// The register picked with a `"x` prefix for the next operation.
+    selected: Option<char>,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new(Clipboard::default())
+    }
+}
+
+impl Registers {
+    pub(crate) fn new(clipboard: Clipboard) -> Self {
+        Self {
+            clipboard,
+            registers: HashMap::new(),
+            clipboard_text: None,
+            selected: None,
+        }
+    }
+
+    /// Replaces the clipboard backing the `"`, `+` and `*` registers.
+    pub(crate) fn set_clipboard(&mut self, clipboard: Clipboard) {
+        self.clipboard = clipboard;
+        self.clipboard_text = None;
+    }
+
+    /// Returns whether `name` is a register that can be selected.
+    pub(crate) fn is_valid(name: char) -> bool {
+        name.is_ascii_alphanumeric() || matches!(name, '"' | '+' | '*' | '-' | '_')
+    }
+
+    /// Selects the register used by the next read or write.
+    pub(crate) fn select(&mut self, name: char) {
+        self.selected = Self::is_valid(name).then_some(name);
+    }
+
+    /// Clears a register selection that was not used.
+    pub(crate) fn deselect(&mut self) {
+        self.selected = None;
+    }
+
+    /// Returns the contents of a register, or `None` if it was never written.
+    pub(crate) fn get(&mut self, name: char) -> Option<(String, RegisterKind)> {
+        match name {
+            '"' | '+' | '*' => Some(self.get_clipboard()),
+            '_' => None,
+            _ => self.registers.get(&name.to_ascii_lowercase()).cloned(),
+        }
+    }
+
+    /// Writes to a register. An uppercase letter appends to the named register.
+    pub(crate) fn set(&mut self, name: char, text: String, kind: RegisterKind) {
+        match name {
+            '"' | '+' | '*' => self.set_clipboard_text(text, kind),
+            '_' => {}
+            'A'..='Z' => {
+                let name = name.to_ascii_lowercase();
+                let appended = match self.registers.remove(&name) {
+                    Some((old, RegisterKind::Charwise)) if kind == RegisterKind::Charwise => {
+                        (old + &text, kind)
+                    }
+                    // Appending to or from lines keeps the text on its own line.
+                    Some((old, old_kind)) => {
+                        let kind = if kind == RegisterKind::Charwise {
+                            old_kind
+                        } else {
+                            kind
+                        };
+                        (old + "\n" + &text, kind)
+                    }
+                    None => (text, kind),
+                };
+                self.registers.insert(name, appended);
+            }
+            _ if Self::is_valid(name) => {
+                self.registers.insert(name, (text, kind));
+            }
+            _ => {}
+        }
+    }
+
+    /// Stores yanked text in the selected register, or in the clipboard and
+    /// the `0` register.
+    pub(crate) fn set_yanked(&mut self, text: String, kind: RegisterKind) {
+        if let Some(name) = self.selected.take() {
+            self.set(name, text, kind);
+            return;
+        }
+        self.registers.insert('0', (text.clone(), kind));
+        self.set_clipboard_text(text, kind);
+    }
+
+    /// Stores deleted text in the selected register, or in the clipboard and
+    /// the delete history.
+    pub(crate) fn set_deleted(&mut self, text: String, kind: RegisterKind) {
+        if let Some(name) = self.selected.take() {
+            self.set(name, text, kind);
+            return;
+        }
+        if kind == RegisterKind::Linewise || text.contains('\n') {
+            let digit = |i| char::from_digit(i, 10).unwrap_or_default();
+            for i in (1..9).rev() {
+                if let Some(older) = self.registers.remove(&digit(i)) {
+                    self.registers.insert(digit(i + 1), older);
+                }
+            }
+            self.registers.insert('1', (text.clone(), kind));
+        } else {
+            self.registers.insert('-', (text.clone(), kind));
+        }
+        self.set_clipboard_text(text, kind);
+    }
+
+    fn set_clipboard_text(&mut self, text: String, kind: RegisterKind) {
+        self.clipboard_text = Some((text.clone(), kind));
+        self.clipboard.set_text_with_kind(text, kind);
+    }
+
+    /// Reads the clipboard. Text that comes back unchanged keeps the kind it
+    /// was written with, anything else is characterwise.
+    fn get_clipboard(&mut self) -> (String, RegisterKind) {
+        let (text, kind) = self.clipboard.get_text_with_kind();
+        match &self.clipboard_text {
+            Some((last, last_kind)) if kind == RegisterKind::Charwise && *last == text => {
+                (text, *last_kind)
+            }
+            _ => (text, kind),
+        }
+    }
+}
+
+impl ClipboardTrait for Registers {
+    /// Stores characterwise text as if it were yanked.
+    fn set_text(&mut self, text: String) {
+        self.set_yanked(text, RegisterKind::Charwise);
+    }
+
+    /// Reads the selected register, or the clipboard.
+    fn get_text(&mut self) -> String {
+        self.get_text_with_kind().0
+    }
+
+    fn set_text_with_kind(&mut self, text: String, kind: RegisterKind) {
+        self.set_yanked(text, kind);
+    }
+
+    fn get_text_with_kind(&mut self) -> (String, RegisterKind) {
+        match self.selected.take() {
+            Some(name) => self.get(name).unwrap_or_default(),
+            None => self.get_clipboard(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::InternalClipboard;
+
+    fn registers() -> Registers {
+        Registers::new(Clipboard::new(InternalClipboard::default()))
+    }
+
+    fn charwise(text: &str) -> Option<(String, RegisterKind)> {
+        Some((String::from(text), RegisterKind::Charwise))
+    }
+
+    fn linewise(text: &str) -> Option<(String, RegisterKind)> {
+        Some((String::from(text), RegisterKind::Linewise))
+    }
+
+    #[test]
+    fn test_named_registers() {
+        let mut registers = registers();
+        registers.set_text(String::from("clip"));
+
+        registers.select('a');
+        registers.set_text(String::from("foo"));
+        registers.select('A');
+        registers.set_deleted(String::from("bar"), RegisterKind::Charwise);
+
+        assert_eq!(registers.get('a'), charwise("foobar"));
+        assert_eq!(registers.get_text(), "clip");
+        registers.select('a');
+        assert_eq!(registers.get_text(), "foobar");
+
+        // Appending lines turns the register linewise.
+        registers.select('A');
+        registers.set_yanked(String::from("baz"), RegisterKind::Linewise);
+        assert_eq!(registers.get('a'), linewise("foobar\nbaz"));
+    }
+
+    #[test]
+    fn test_numbered_registers() {
+        let mut registers = registers();
+        registers.set_yanked(String::from("yank"), RegisterKind::Charwise);
+        registers.set_deleted(String::from("one"), RegisterKind::Linewise);
+        registers.set_deleted(String::from("two"), RegisterKind::Linewise);
+        registers.set_deleted(String::from("word"), RegisterKind::Charwise);
+
+        assert_eq!(registers.get('0'), charwise("yank"));
+        assert_eq!(registers.get('1'), linewise("two"));
+        assert_eq!(registers.get('2'), linewise("one"));
+        assert_eq!(registers.get('-'), charwise("word"));
+        assert_eq!(registers.get('+'), charwise("word"));
+    }
+
+    #[test]
+    fn test_black_hole_register() {
+        let mut registers = registers();
+        registers.set_text(String::from("keep"));
+
+        registers.select('_');
+        registers.set_deleted(String::from("gone"), RegisterKind::Charwise);
+        assert_eq!(registers.get_text(), "keep");
+        assert_eq!(registers.get('_'), None);
+    }
+
+    #[test]
+    fn test_clipboard_without_kind() {
+        struct TextOnly(String);
+        impl ClipboardTrait for TextOnly {
+            fn set_text(&mut self, text: String) {
+                self.0 = text;
+            }
+            fn get_text(&mut self) -> String {
+                self.0.clone()
+            }
+        }
+        let clipboard = Clipboard::new(TextOnly(String::new()));
+        let mut registers = Registers::new(clipboard.clone());
+
+        // Our own text keeps its kind, text copied elsewhere is charwise.
+        registers.set_yanked(String::from("line"), RegisterKind::Linewise);
+        assert_eq!(
+            registers.get_text_with_kind(),
+            (String::from("line"), RegisterKind::Linewise)
+        );
+
+        clipboard.clone().set_text(String::from("\nother"));
+        assert_eq!(registers.get('+'), charwise("\nother"));
+    }
+}
@@ -0,0 +1,877 @@
+//! Ex commands, typed after `:` in command mode.
+//!
+//! Pressing `:` in normal mode switches to [`EditorMode::Command`], where the
+//! command line is shown in the status line. `Enter` runs it, `Tab` completes
+//! it and `Esc` cancels it.
+//!
+//! ## Built-in commands
+//!
+//! | Command                       | Description                                       |
+//! |-------------------------------|---------------------------------------------------|
+//! | `:<n>`                        | Go to line `<n>` (also `:$`, `:.+3`)              |
+//! | `:[range]s/<pat>/<rep>/[gic]` | Replace `<pat>` with `<rep>` (`:%s` for all rows) |
+//! | `:set <option>`               | Set `wrap`, `nu`, `rnu` or `ts=<n>`               |
+//! | `:set ic`, `scs`, `regex`     | Ignore case, smartcase or match regexes in search |
+//! | `:set sw=<n>`                 | Set the width of one level of indentation         |
+//! | `:set et`, `ai`, `si`         | Indent with spaces, auto-indent or smart indent   |
+//! | `:u [n]`, `:red`              | Undo, redo or go to the text after change `<n>`   |
+//! | `:earlier <n>[smhd]`          | Go back `<n>` changes or seconds, minutes, ...    |
+//! | `:later <n>[smhd]`            | Go forward `<n>` changes or in time               |
+//! | `:w [path]`, `:q`, `:wq`      | Ask the host app to save or quit                  |
+//!
+//! The pattern of `:s` is matched with the editor's
+//! [`SearchOptions`](crate::SearchOptions), so it is a regular expression
+//! after `:set regex`. Its flags are `g` to replace every match in a line
+//! instead of the first, `i` to ignore case and `c` to confirm each match
+//! with `y`, `n`, `a` (all) or `q` (quit).
+//!
+//! The editor can't save files or quit on its own. `:w` and `:q` emit a
+//! [`CommandEvent`], which the host app reads with
+//! [`EditorState::take_command_events`] after handling an event.
+//!
+//! ## Example: A custom command
+//!
+//! ```
+//! use edtui::command::{CommandArgs, CommandError};
+//! use edtui::{EditorState, Lines};
+//!
+//! let mut state = EditorState::new(Lines::from("Hello"));
+//! state.register_command("upper", |state: &mut EditorState, _: &CommandArgs| {
+//!     state.lines = Lines::from(state.lines.to_string().to_uppercase().as_str());
+//!     Ok(())
+//! });
+//!
+//! state.execute_command("upper").unwrap();
+//! assert_eq!(state.lines, Lines::from("HELLO"));
+//! assert_eq!(
+//!     state.execute_command("lower"),
+//!     Err(CommandError::NotFound(String::from("lower")))
+//! );
+//! ```
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::helper::indentation;
+use crate::{EditorState, Index2, LineNumbers, ReplaceFlags};
+
+/// A command that can be run from command mode.
+///
+/// Closures with the signature of [`Command::execute`] implement this trait.
+/// Implement it on a type to also offer completions.
+pub trait Command {
+    /// Runs the command.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arguments are invalid or the command fails. The
+    /// error is shown in the status line.
+    fn execute(&self, state: &mut EditorState, args: &CommandArgs) -> Result<(), CommandError>;
+
+    /// Returns candidates for the last, partially typed argument.
+    fn complete(&self, state: &EditorState, partial: &str) -> Vec<String> {
+        let _ = (state, partial);
+        Vec::new()
+    }
+}
+
+impl<F> Command for F
+where
+    F: Fn(&mut EditorState, &CommandArgs) -> Result<(), CommandError>,
+{
+    fn execute(&self, state: &mut EditorState, args: &CommandArgs) -> Result<(), CommandError> {
+        self(state, args)
+    }
+}
+
+/// The parsed command line passed to a [`Command`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandArgs {
+    /// The rows given before the command name (`:2,5`, `:%`), if any.
+    pub range: Option<RangeInclusive<usize>>,
+    /// Whether the command name was followed by `!` (`:q!`).
+    pub bang: bool,
+    /// The text after the command name, without leading whitespace.
+    pub args: String,
+}
+
+impl CommandArgs {
+    /// Returns the whitespace separated arguments.
+    pub fn iter(&self) -> std::str::SplitWhitespace<'_> {
+        self.args.split_whitespace()
+    }
+
+    /// Returns the argument at `index`.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.iter().nth(index)
+    }
+
+    /// Parses the argument at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument is missing or cannot be parsed.
+    pub fn parse<T: FromStr>(&self, index: usize) -> Result<T, CommandError> {
+        let arg = self
+            .get(index)
+            .ok_or_else(|| CommandError::InvalidArgument(String::from("missing argument")))?;
+        arg.parse()
+            .map_err(|_| CommandError::InvalidArgument(arg.to_string()))
+    }
+
+    /// Returns the rows the command applies to: the given range, or the
+    /// cursor row.
+    #[must_use]
+    pub fn rows(&self, state: &EditorState) -> RangeInclusive<usize> {
+        self.range
+            .clone()
+            .unwrap_or(state.cursor.row..=state.cursor.row)
+    }
+}
+
+/// An error from parsing or running a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    /// No command with this name is registered.
+    NotFound(String),
+    /// The range before the command name is invalid.
+    InvalidRange(String),
+    /// An argument is missing or invalid.
+    InvalidArgument(String),
+    /// The command would change the text of a read-only editor.
+    ReadOnly,
+    /// The command ran but failed.
+    Failed(String),
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "Not an editor command: {name}"),
+            Self::InvalidRange(range) => write!(f, "Invalid range: {range}"),
+            Self::InvalidArgument(arg) => write!(f, "Invalid argument: {arg}"),
+            Self::ReadOnly => write!(f, "Cannot make changes, the editor is read-only"),
+            Self::Failed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// A request from a built-in command that the host app has to handle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandEvent {
+    /// Save the buffer (`:w`), optionally to another path (`:w <path>`).
+    Write {
+        path: Option<String>,
+        /// Set by `:w!`.
+        force: bool,
+    },
+    /// Close the editor (`:q`).
+    Quit {
+        /// Set by `:q!`, to quit without saving.
+        force: bool,
+    },
+}
+
+/// The commands available in command mode, by name.
+#[derive(Clone)]
+pub(crate) struct CommandRegistry {
+    commands: HashMap<String, Rc<dyn Command>>,
+}
+
+impl Default for CommandRegistry {
+    /// Creates a registry with the built-in commands.
+    fn default() -> Self {
+        let mut registry = Self {
+            commands: HashMap::new(),
+        };
+        registry.register_builtin(&["s", "substitute"], substitute);
+        registry.register_builtin(&["set", "se"], SetOption);
+        registry.register_builtin(&["u", "undo"], undo);
+        registry.register_builtin(
+            &["red", "redo"],
+            |state: &mut EditorState, _: &CommandArgs| {
+                writable(state)?;
+                state.redo();
+                Ok(())
+            },
+        );
+        registry.register_builtin(
+            &["ea", "earlier"],
+            |state: &mut EditorState, args: &CommandArgs| time_travel(state, args, true),
+        );
+        registry.register_builtin(
+            &["lat", "later"],
+            |state: &mut EditorState, args: &CommandArgs| time_travel(state, args, false),
+        );
+        registry.register_builtin(
+            &["w", "write"],
+            |state: &mut EditorState, args: &CommandArgs| {
+                state.command.events.push(write_event(args));
+                Ok(())
+            },
+        );
+        registry.register_builtin(
+            &["q", "quit"],
+            |state: &mut EditorState, args: &CommandArgs| {
+                let force = args.bang;
+                state.command.events.push(CommandEvent::Quit { force });
+                Ok(())
+            },
+        );
+        registry.register_builtin(
+            &["wq", "x", "xit"],
+            |state: &mut EditorState, args: &CommandArgs| {
+                let force = args.bang;
+                state.command.events.push(write_event(args));
+                state.command.events.push(CommandEvent::Quit { force });
+                Ok(())
+            },
+        );
+        registry
+    }
+}
+
+impl CommandRegistry {
+    /// Registers a command, replacing any command with the same name.
+    pub(crate) fn register(&mut self, name: String, command: Rc<dyn Command>) {
+        self.commands.insert(name, command);
+    }
+
+    fn register_builtin(&mut self, names: &[&str], command: impl Command + 'static) {
+        let command: Rc<dyn Command> = Rc::new(command);
+        for name in names {
+            self.register((*name).to_string(), Rc::clone(&command));
+        }
+    }
+
+    /// Returns the command with the given name.
+    pub(crate) fn get(&self, name: &str) -> Option<Rc<dyn Command>> {
+        self.commands.get(name).cloned()
+    }
+
+    /// Returns the completions of a partially typed command line, as
+    /// complete command lines.
+    pub(crate) fn complete(&self, state: &EditorState, input: &str) -> Vec<String> {
+        let Ok((range_len, _)) = parse_range(input, state) else {
+            return Vec::new();
+        };
+        let (prefix, rest) = input.split_at(range_len);
+        let name_len = command_name_len(rest);
+        let (name, args) = rest.split_at(name_len);
+
+        // Still typing the command name.
+        if args.is_empty() {
+            let mut names: Vec<&String> = self
+                .commands
+                .keys()
+                .filter(|candidate| candidate.starts_with(name))
+                .collect();
+            names.sort();
+            return names
+                .into_iter()
+                .map(|candidate| format!("{prefix}{candidate}"))
+                .collect();
+        }
+
+        let Some(command) = self.get(name) else {
+            return Vec::new();
+        };
+        let partial_start = args.rfind(char::is_whitespace).map_or(0, |index| index + 1);
+        let (head, partial) = args.split_at(partial_start);
+        command
+            .complete(state, partial)
+            .into_iter()
+            .map(|candidate| format!("{prefix}{name}{head}{candidate}"))
+            .collect()
+    }
+}
+
+/// Parses and runs a command line.
+pub(crate) fn execute(state: &mut EditorState, input: &str) -> Result<(), CommandError> {
+    let input = input.trim_start_matches([':', ' ']);
+    let (range_len, range) = parse_range(input, state)?;
+    let rest = &input[range_len..];
+    let name_len = command_name_len(rest);
+    let (name, rest) = rest.split_at(name_len);
+
+    // A range without a command goes to its last line (`:42`).
+    if name.is_empty() {
+        if !rest.trim().is_empty() {
+            return Err(CommandError::NotFound(rest.trim().to_string()));
+        }
+        if let Some(range) = range {
+            state.push_jump();
+            state.cursor = Index2::new(*range.end(), indentation(&state.lines, *range.end()));
+        }
+        return Ok(());
+    }
+
+    let command = state
+        .commands
+        .get(name)
+        .ok_or_else(|| CommandError::NotFound(name.to_string()))?;
+    let bang = rest.starts_with('!');
+    let args = CommandArgs {
+        range,
+        bang,
+        args: rest[usize::from(bang)..].trim_start().to_string(),
+    };
+    command.execute(state, &args)
+}
+
+/// Returns the length of the command name at the start of `input`. Names are
+/// made of letters, digits and underscores.
+fn command_name_len(input: &str) -> usize {
+    input
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(input.len())
+}
+
+/// Parses the range at the start of the command line. Returns its length in
+/// bytes and the rows it covers, if there is one.
+fn parse_range(
+    input: &str,
+    state: &EditorState,
+) -> Result<(usize, Option<RangeInclusive<usize>>), CommandError> {
+    let last_row = state.lines.len().saturating_sub(1);
+    if input.starts_with('%') {
+        return Ok((1, Some(0..=last_row)));
+    }
+
+    let (len, start) = parse_address(input, state)?;
+    let Some(start) = start else {
+        return Ok((0, None));
+    };
+    let Some(rest) = input[len..].strip_prefix(',') else {
+        return Ok((len, Some(start..=start)));
+    };
+    let (end_len, end) = parse_address(rest, state)?;
+    let end = end.ok_or_else(|| CommandError::InvalidRange(input[..=len].to_string()))?;
+    if end < start {
+        return Err(CommandError::InvalidRange(
+            input[..len + 1 + end_len].to_string(),
+        ));
+    }
+    Ok((len + 1 + end_len, Some(start..=end)))
+}
+
+/// Parses a line address: a line number, `.` for the cursor row or `$` for
+/// the last row, each followed by optional `+<n>` or `-<n>` offsets.
+fn parse_address(input: &str, state: &EditorState) -> Result<(usize, Option<usize>), CommandError> {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let last_row = state.lines.len().saturating_sub(1);
+
+    let (mut len, mut row) = match input.chars().next() {
+        Some('.') => (1, Some(state.cursor.row as isize)),
+        Some('$') => (1, Some(last_row as isize)),
+        Some(c) if c.is_ascii_digit() => {
+            let len = digits(input);
+            let line: isize = input[..len]
+                .parse()
+                .map_err(|_| CommandError::InvalidRange(input[..len].to_string()))?;
+            (len, Some(line - 1))
+        }
+        _ => (0, None),
+    };
+
+    while let Some(sign) = input[len..]
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '+' | '-'))
+    {
+        let offset_len = digits(&input[len + 1..]);
+        let offset: isize = if offset_len == 0 {
+            1
+        } else {
+            input[len + 1..len + 1 + offset_len]
+                .parse()
+                .map_err(|_| CommandError::InvalidRange(input[..len].to_string()))?
+        };
+        let base = row.unwrap_or(state.cursor.row as isize);
+        row = Some(if sign == '+' {
+            base + offset
+        } else {
+            base - offset
+        });
+        len += 1 + offset_len;
+    }
+
+    match row {
+        None => Ok((len, None)),
+        Some(row) if row < 0 => Err(CommandError::InvalidRange(input[..len].to_string())),
+        Some(row) => Ok((len, Some((row as usize).min(last_row)))),
+    }
+}
+
+/// Refuses a command that changes the text of a read-only editor.
+fn writable(state: &EditorState) -> Result<(), CommandError> {
+    if state.read_only {
+        return Err(CommandError::ReadOnly);
+    }
+    Ok(())
+}
+
+fn write_event(args: &CommandArgs) -> CommandEvent {
+    CommandEvent::Write {
+        path: (!args.args.is_empty()).then(|| args.args.clone()),
+        force: args.bang,
+    }
+}
+
+/// Undoes the last change, or moves to the text after change `N` (`:undo N`).
+fn undo(state: &mut EditorState, args: &CommandArgs) -> Result<(), CommandError> {
+    writable(state)?;
+    if args.get(0).is_none() {
+        state.undo();
+        return Ok(());
+    }
+    let seq: usize = args.parse(0)?;
+    if state.undo_to(seq) {
+        Ok(())
+    } else {
+        Err(CommandError::Failed(format!("Undo number {seq} not found")))
+    }
+}
+
+/// Moves through the undo history by a number of changes (`:earlier 3`) or
+/// by time (`:earlier 10s`, with `s`, `m`, `h` or `d`).
+fn time_travel(
+    state: &mut EditorState,
+    args: &CommandArgs,
+    earlier: bool,
+) -> Result<(), CommandError> {
+    writable(state)?;
+    let arg = args.get(0).unwrap_or("1");
+    let invalid = || CommandError::InvalidArgument(arg.to_string());
+    let (count, unit) = arg.split_at(arg.find(|c: char| !c.is_ascii_digit()).unwrap_or(arg.len()));
+    let count: u64 = count.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "" => {
+            let count = usize::try_from(count).map_err(|_| invalid())?;
+            if earlier {
+                state.undo_earlier(count);
+            } else {
+                state.undo_later(count);
+            }
+            return Ok(());
+        }
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let duration = Duration::from_secs(count.saturating_mul(seconds));
+    let time = if earlier {
+        state
+            .undo_time()
+            .checked_sub(duration)
+            .unwrap_or(UNIX_EPOCH)
+    } else {
+        state
+            .undo_time()
+            .checked_add(duration)
+            .ok_or_else(invalid)?
+    };
+    state.undo_to_time(time);
+    Ok(())
+}
+
+/// Replaces text in the given rows (`:s/<pattern>/<replacement>/[g]`).
+///
+/// Any character can delimit the pattern, and a delimiter inside the pattern
+/// or the replacement is escaped with a backslash. `\\` is a backslash, but
+/// stays escaped in a regex pattern. Without the `g` flag, only the first
+/// match of each row is replaced.
+fn substitute(state: &mut EditorState, args: &CommandArgs) -> Result<(), CommandError> {
+    writable(state)?;
+    let mut chars = args.args.chars();
+    let delimiter = chars
+        .next()
+        .filter(|c| !c.is_alphanumeric() && !c.is_whitespace() && *c != '\\')
+        .ok_or_else(|| CommandError::InvalidArgument(args.args.clone()))?;
+    let mut parts = split_unescaped(chars.as_str(), delimiter);
+    for (i, part) in parts.iter_mut().enumerate().take(2) {
+        if i > 0 || !state.search.options.regex {
+            *part = part.replace("\\\\", "\\");
+        }
+    }
+    let [pattern, replacement, flags] = match parts.as_slice() {
+        [pattern] => [pattern.as_str(), "", ""],
+        [pattern, replacement] => [pattern.as_str(), replacement.as_str(), ""],
+        [pattern, replacement, flags] => [pattern.as_str(), replacement.as_str(), flags.as_str()],
+        _ => return Err(CommandError::InvalidArgument(args.args.clone())),
+    };
+    if pattern.is_empty() {
+        return Err(CommandError::InvalidArgument(args.args.clone()));
+    }
+    if let Some(flag) = flags.chars().find(|flag| !matches!(flag, 'g' | 'i' | 'c')) {
+        return Err(CommandError::InvalidArgument(flag.to_string()));
+    }
+    let mut search = state.search.options;
+    search.ignore_case |= flags.contains('i');
+    let replace_flags = ReplaceFlags {
+        first_per_line: !flags.contains('g'),
+        search,
+    };
+    let not_found = || CommandError::Failed(format!("Pattern not found: {pattern}"));
+
+    let rows = args.rows(state);
+    if flags.contains('c') {
+        let count = state.start_replace_rows(rows, pattern, replacement, replace_flags);
+        return if count == 0 { Err(not_found()) } else { Ok(()) };
+    }
+
+    let replaced = state.replace_rows(rows, pattern, replacement, replace_flags);
+    let row = replaced.last().ok_or_else(not_found)?.row;
+    state.cursor = Index2::new(row, indentation(&state.lines, row));
+    Ok(())
+}
+
+/// Splits `text` at each `delimiter` that is not escaped with a backslash,
+/// and removes the escapes of the delimiter. An escaped backslash (`\\`) is
+/// kept as it is and never escapes the character after it.
+fn split_unescaped(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().expect("parts is never empty");
+        if c == '\\' && chars.peek() == Some(&'\\') {
+            part.push_str("\\\\");
+            chars.next();
+        } else if c == '\\' && chars.peek() == Some(&delimiter) {
+            part.push(delimiter);
+            chars.next();
+        } else if c == delimiter {
+            parts.push(String::new());
+        } else {
+            part.push(c);
+        }
+    }
+    parts
+}
+
+/// The options of `:set`.
+const OPTIONS: [(&str, &str); 11] = [
+    ("wrap", "wrap"),
+    ("number", "nu"),
+    ("relativenumber", "rnu"),
+    ("tabstop", "ts"),
+    ("shiftwidth", "sw"),
+    ("expandtab", "et"),
+    ("autoindent", "ai"),
+    ("smartindent", "si"),
+    ("ignorecase", "ic"),
+    ("smartcase", "scs"),
+    ("regex", "regex"),
+];
+
+/// The options of `:set` that take a number.
+const NUMBER_OPTIONS: [&str; 2] = ["tabstop", "shiftwidth"];
+
+/// Sets options (`:set nowrap`, `:set number`, `:set tabstop=4`,
+/// `:set ignorecase`, `:set regex`).
+///
+/// Boolean options are enabled by their name, disabled with a `no` prefix
+/// and toggled with a `!` suffix.
+struct SetOption;
+
+impl SetOption {
+    fn set(state: &mut EditorState, arg: &str) -> Result<(), CommandError> {
+        let invalid = || CommandError::InvalidArgument(arg.to_string());
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg, None),
+        };
+        let (name, toggle) = match name.strip_suffix('!') {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        let option = |name: &str| {
+            OPTIONS
+                .iter()
+                .find(|(long, short)| name == *long || name == *short)
+                .map(|(long, _)| *long)
+        };
+        let (option, enable) = match (option(name), name.strip_prefix("no").and_then(option)) {
+            (Some(option), _) => (option, true),
+            (None, Some(option)) => (option, false),
+            (None, None) => return Err(invalid()),
+        };
+
+        if NUMBER_OPTIONS.contains(&option) {
+            let value = value.ok_or_else(invalid)?;
+            let width: usize = value.parse().map_err(|_| invalid())?;
+            match option {
+                "tabstop" if width == 0 => return Err(invalid()),
+                "tabstop" => state.view.tab_width = width,
+                _ => state.indent.shift_width = width,
+            }
+            return Ok(());
+        }
+        if value.is_some() {
+            return Err(invalid());
+        }
+
+        let (view, search) = (&mut state.view, &mut state.search.options);
+        let indent = &mut state.indent;
+        let current = match option {
+            "wrap" => view.wrap,
+            "number" => view.line_numbers == LineNumbers::Absolute,
+            "relativenumber" => view.line_numbers == LineNumbers::Relative,
+            "expandtab" => indent.expand_tab,
+            "autoindent" => indent.auto_indent,
+            "smartindent" => indent.smart_indent,
+            "ignorecase" => search.ignore_case,
+            "regex" => search.regex,
+            _ => search.smart_case,
+        };
+        let enable = if toggle { !current } else { enable };
+        match option {
+            "wrap" => view.wrap = enable,
+            "expandtab" => indent.expand_tab = enable,
+            "autoindent" => indent.auto_indent = enable,
+            "smartindent" => indent.smart_indent = enable,
+            "ignorecase" => search.ignore_case = enable,
+            "smartcase" => search.smart_case = enable,
+            "regex" => search.regex = enable,
+            "number" if enable => view.line_numbers = LineNumbers::Absolute,
+            "relativenumber" if enable => view.line_numbers = LineNumbers::Relative,
+            _ if current => view.line_numbers = LineNumbers::None,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Command for SetOption {
+    fn execute(&self, state: &mut EditorState, args: &CommandArgs) -> Result<(), CommandError> {
+        if args.args.is_empty() {
+            return Err(CommandError::InvalidArgument(String::from(
+                "missing option",
+            )));
+        }
+        for arg in args.iter() {
+            Self::set(state, arg)?;
+        }
+        Ok(())
+    }
+
+    fn complete(&self, _: &EditorState, partial: &str) -> Vec<String> {
+        let mut candidates: Vec<String> = OPTIONS
+            .iter()
+            .flat_map(|(long, _)| [(*long).to_string(), format!("no{long}")])
+            .filter(|candidate| {
+                let number = NUMBER_OPTIONS
+                    .iter()
+                    .any(|option| candidate == &format!("no{option}"));
+                candidate.starts_with(partial) && !number
+            })
+            .collect();
+        candidates.sort();
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IndentOptions, Lines};
+
+    fn test_state() -> EditorState {
+        EditorState::new(Lines::from("one\n  two\nthree one\none one"))
+    }
+
+    #[test]
+    fn test_goto_line() {
+        let mut state = test_state();
+
+        state.execute_command("2").unwrap();
+        assert_eq!(state.cursor, Index2::new(1, 2));
+
+        state.execute_command("$").unwrap();
+        assert_eq!(state.cursor, Index2::new(3, 0));
+
+        state.execute_command(".-2").unwrap();
+        assert_eq!(state.cursor, Index2::new(1, 2));
+
+        // Numbers past the end go to the last line.
+        state.execute_command("99").unwrap();
+        assert_eq!(state.cursor, Index2::new(3, 0));
+    }
+
+    #[test]
+    fn test_substitute() {
+        let mut state = test_state();
+        state.cursor = Index2::new(2, 0);
+
+        state.execute_command("s/one/1/").unwrap();
+        assert_eq!(state.lines, Lines::from("one\n  two\nthree 1\none one"));
+
+        state.execute_command("%s/one/1/g").unwrap();
+        assert_eq!(state.lines, Lines::from("1\n  two\nthree 1\n1 1"));
+        assert_eq!(state.cursor, Index2::new(3, 0));
+
+        state.execute_command("%s/THREE/3/i").unwrap();
+        assert_eq!(state.lines, Lines::from("1\n  two\n3 1\n1 1"));
+        state.undo();
+
+        state.execute_command("1,2s#t#\\##").unwrap();
+        assert_eq!(state.lines, Lines::from("1\n  #wo\nthree 1\n1 1"));
+
+        // An escaped backslash does not escape the delimiter after it.
+        state.execute_command("2s/#/\\\\/").unwrap();
+        assert_eq!(state.lines, Lines::from("1\n  \\wo\nthree 1\n1 1"));
+        state.execute_command("2s/\\\\/\\//").unwrap();
+        assert_eq!(state.lines, Lines::from("1\n  /wo\nthree 1\n1 1"));
+        state.execute_command("set regex").unwrap();
+        state.execute_command("2s/\\//\\\\/").unwrap();
+        state.execute_command("2s/\\\\w/t/").unwrap();
+        assert_eq!(state.lines, Lines::from("1\n  to\nthree 1\n1 1"));
+        state.execute_command("set noregex").unwrap();
+        state.undo();
+        state.undo();
+        state.undo();
+        state.undo();
+
+        // One substitution is one undo step.
+        state.undo();
+        state.undo();
+        assert_eq!(state.lines, Lines::from("one\n  two\nthree 1\none one"));
+    }
+
+    #[test]
+    fn test_substitute_errors() {
+        let mut state = test_state();
+
+        assert_eq!(
+            state.execute_command("s/xyz/abc/"),
+            Err(CommandError::Failed(String::from("Pattern not found: xyz")))
+        );
+        assert_eq!(
+            state.execute_command("3,1s/a/b/"),
+            Err(CommandError::InvalidRange(String::from("3,1")))
+        );
+        assert_eq!(
+            state.execute_command("s/xyz/abc/c"),
+            Err(CommandError::Failed(String::from("Pattern not found: xyz")))
+        );
+        assert!(!state.is_replacing());
+        assert_eq!(
+            state.execute_command("s/a/b/x"),
+            Err(CommandError::InvalidArgument(String::from("x")))
+        );
+    }
+
+    #[test]
+    fn test_set_option() {
+        let mut state = test_state();
+
+        state.execute_command("set nowrap nu ts=4").unwrap();
+        assert!(!state.view.wrap);
+        assert_eq!(state.view.line_numbers, LineNumbers::Absolute);
+        assert_eq!(state.view.tab_width, 4);
+
+        state.execute_command("se wrap! rnu").unwrap();
+        assert!(state.view.wrap);
+        assert_eq!(state.view.line_numbers, LineNumbers::Relative);
+
+        state.execute_command("set nonumber").unwrap();
+        assert_eq!(state.view.line_numbers, LineNumbers::Relative);
+
+        state.execute_command("set et sw=4 si").unwrap();
+        assert_eq!(
+            state.indent_options(),
+            IndentOptions {
+                expand_tab: true,
+                shift_width: 4,
+                auto_indent: false,
+                smart_indent: true,
+            }
+        );
+
+        state.execute_command("set ic scs").unwrap();
+        state.execute_command("set noignorecase").unwrap();
+        assert!(!state.search.options.ignore_case);
+        assert!(state.search.options.smart_case);
+
+        state.execute_command("set regex").unwrap();
+        state.execute_command("s/o+/0/").unwrap();
+        assert_eq!(state.lines, Lines::from("0ne\n  two\nthree one\none one"));
+        state.execute_command("set noregex").unwrap();
+        assert!(!state.search.options.regex);
+
+        assert!(state.execute_command("set colors").is_err());
+    }
+
+    #[test]
+    fn test_undo_commands() {
+        let mut state = test_state();
+        state.execute_command("s/one/1/").unwrap();
+        state.execute_command("u").unwrap();
+        state.execute_command("s/one/2/").unwrap();
+        assert_eq!(state.lines, Lines::from("2\n  two\nthree one\none one"));
+
+        state.execute_command("undo 1").unwrap();
+        assert_eq!(state.lines, Lines::from("1\n  two\nthree one\none one"));
+        assert_eq!(
+            state.execute_command("undo 9"),
+            Err(CommandError::Failed(String::from(
+                "Undo number 9 not found"
+            )))
+        );
+
+        state.execute_command("later").unwrap();
+        assert_eq!(state.lines, Lines::from("2\n  two\nthree one\none one"));
+        state.execute_command("earlier 2").unwrap();
+        assert_eq!(state.lines, test_state().lines);
+        state.execute_command("redo").unwrap();
+        assert_eq!(state.lines, Lines::from("2\n  two\nthree one\none one"));
+
+        state.execute_command("earlier 1d").unwrap();
+        assert_eq!(state.lines, test_state().lines);
+        state.execute_command("lat 1h").unwrap();
+        assert_eq!(state.lines, Lines::from("2\n  two\nthree one\none one"));
+        assert_eq!(
+            state.execute_command("earlier 1y"),
+            Err(CommandError::InvalidArgument(String::from("1y")))
+        );
+    }
+
+    #[test]
+    fn test_write_and_quit_events() {
+        let mut state = test_state();
+
+        state.execute_command("w notes.txt").unwrap();
+        state.execute_command("q!").unwrap();
+        assert_eq!(
+            state.take_command_events(),
+            vec![
+                CommandEvent::Write {
+                    path: Some(String::from("notes.txt")),
+                    force: false
+                },
+                CommandEvent::Quit { force: true }
+            ]
+        );
+        assert!(state.take_command_events().is_empty());
+    }
+
+    #[test]
+    fn test_complete() {
+        let state = test_state();
+        let registry = CommandRegistry::default();
+
+        assert_eq!(registry.complete(&state, "%su"), vec!["%substitute"]);
+        assert_eq!(
+            registry.complete(&state, "set nu norel"),
+            vec!["set nu norelativenumber"]
+        );
+        assert!(registry.complete(&state, "unknown ").is_empty());
+    }
+}
@@ -3,8 +3,8 @@ pub(crate) mod input;
 
 use crate::actions::cpaste::PasteOverSelection;
 use crate::actions::delete::{
-    DeleteBigWordForward, DeleteCharForward, DeleteToEndOfLine, DeleteToFirstCharOfLine,
-    DeleteWordBackward, DeleteWordForward,
+    DeleteCharForward, DeleteToEndOfLine, DeleteToFirstCharOfLine, DeleteWordBackward,
+    DeleteWordForward,
 };
 use crate::actions::motion::{
     MoveHalfPageDown, MovePageDown, MovePageUp, MoveToFirstRow, MoveToLastRow,
@@ -13,20 +13,29 @@ use crate::actions::search::StartSearch;
 #[cfg(feature = "system-editor")]
 use crate::actions::OpenSystemEditor;
 use crate::actions::{
-    Action, AppendCharToSearch, AppendNewline, Chainable, ChangeBigWord, ChangeFindForward,
-    ChangeInnerBetween, ChangeInnerBigWord, ChangeInnerWord, ChangeSelection, ChangeTillForward,
-    ChangeWord, CopyLine, CopySelection, DeleteChar, DeleteFindForward, DeleteInnerBetween,
-    DeleteInnerBigWord, DeleteInnerWord, DeleteLine, DeleteSelection, DeleteTillForward, Execute,
-    FindFirst, FindForward, FindNext, FindPrevious, InsertChar, InsertNewline,
-    JoinLineWithLineBelow, LineBreak, MoveBackward, MoveDown, MoveForward, MoveHalfPageUp,
-    MoveParagraphBackward, MoveParagraphForward, MoveToEndOfLine, MoveToFirst,
-    MoveToMatchinBracket, MoveToStartOfLine, MoveUp, MoveWordBackward, MoveWordForward,
-    MoveWordForwardToEndOfWord, Paste, PasteBefore, Redo, RemoveChar, RemoveCharFromSearch,
-    RepeatLastChange, SelectCurrentSearch, SelectInnerBetween, SelectInnerWord, SelectLine,
-    StopSearch, SwitchMode, TillForward, Undo,
+    Action, AppendBlock, AppendCharToCommand, AppendCharToSearch, AppendNewline, Case, Chainable,
+    ChangeSelection, ChangeSelectionCase, CompleteCommand, ConfirmReplace, CopySelection,
+    Decrement, DeleteChar, DeleteSelection, Execute, ExecuteCommand, FindBackward, FindFirst,
+    FindForward, FindNext, FindPrevious, Increment, InsertBlock, InsertChar, InsertNewline,
+    JoinLineWithLineBelow, JumpBackward, JumpForward, JumpToMark, JumpToMarkLine, LineBreak,
+    MoveBackward, MoveBigWordBackward, MoveBigWordForward, MoveBigWordForwardToEndOfWord, MoveDown,
+    MoveForward, MoveHalfPageUp, MoveParagraphBackward, MoveParagraphForward, MoveToEndOfLine,
+    MoveToFirst, MoveToMatchinBracket, MoveToStartOfLine, MoveUp, MoveWordBackward,
+    MoveWordForward, MoveWordForwardToEndOfWord, NextSearchHistory, Operate, OperateSelection,
+    Operator, OverwriteChar, Paste, PasteBefore, PreviousSearchHistory, RecordMacro, Redo,
+    RemoveChar, RemoveCharFromCommand, RemoveCharFromSearch, RepeatFind, RepeatLastChange,
+    ReplaceChars, ReplayMacro, RestoreChar, SearchWordBackward, SearchWordForward,
+    SelectAroundBetween, SelectAroundBigWord, SelectAroundParagraph, SelectAroundSentence,
+    SelectAroundTag, SelectAroundWord, SelectCurrentSearch, SelectInnerBetween, SelectInnerBigWord,
+    SelectInnerParagraph, SelectInnerSentence, SelectInnerTag, SelectInnerWord, SelectLine,
+    SelectLines, SelectRegister, SetMark, StartBackwardSearch, StartCommand, StopCommand,
+    StopSearch, SwitchMode, TillBackward, TillForward, ToggleCase, ToggleSearchOption, Undo,
+    UndoEarlier, UndoLater, YankLine,
 };
 use crate::events::KeyInput;
-use crate::{EditorMode, EditorState};
+use crate::state::changes::ViewChange;
+use crate::state::macros::MAX_REPLAYED_KEYS;
+use crate::{EditorMode, EditorState, ReplaceChoice, SearchOption};
 use crossterm::event::KeyCode;
 use std::collections::HashMap;
 
@@ -38,6 +47,19 @@ pub struct KeyEventHandler {
     /// An action awaiting a character argument (e.g. `f`/`t`/`df`/`dt`). The
     /// next keystroke is fed to it via [`Execute::char_arg`].
     pending_char: Option<Action>,
+    /// A count prefix typed in normal or visual mode (e.g. the `3` in `3w`).
+    count: Option<usize>,
+    /// An operator (e.g. `d`) waiting for a motion or text object.
+    operator: Option<PendingOperator>,
+}
+
+/// An operator waiting for a motion, together with the key that makes it
+/// linewise when repeated (`dd`, `>>`, `gUU`) and the count typed before it.
+#[derive(Clone, Debug)]
+struct PendingOperator {
+    action: Action,
+    key: KeyInput,
+    count: Option<usize>,
 }
 
 impl Default for KeyEventHandler {
@@ -55,6 +77,8 @@ impl KeyEventHandler {
             register,
             capture_on_insert,
             pending_char: None,
+            count: None,
+            operator: None,
         }
     }
 
@@ -67,6 +91,8 @@ impl KeyEventHandler {
             register,
             capture_on_insert: false,
             pending_char: None,
+            count: None,
+            operator: None,
         }
     }
 
@@ -79,6 +105,8 @@ impl KeyEventHandler {
             register,
             capture_on_insert: true,
             pending_char: None,
+            count: None,
+            operator: None,
         }
     }
 
@@ -114,24 +142,35 @@ impl KeyEventHandler {
     #[must_use]
     fn get(&mut self, c: KeyInput, mode: EditorMode) -> Option<Action> {
         self.lookup.push(c);
-        let key = KeyEventRegister::new(self.lookup.clone(), mode);
 
-        match self
+        // Visual line and block mode fall back to the visual mode bindings,
+        // replace mode to the insert mode bindings.
+        let modes = match mode {
+            EditorMode::VisualLine | EditorMode::VisualBlock => vec![mode, EditorMode::Visual],
+            EditorMode::Replace => vec![mode, EditorMode::Insert],
+            _ => vec![mode],
+        };
+        let mut candidates = self
             .register
             .keys()
-            .filter(|k| k.mode == key.mode && k.keys.starts_with(&key.keys))
-            .count()
-        {
-            0 => {
-                self.lookup.clear();
-                None
-            }
-            1 => self.register.get(&key).map(|action| {
-                self.lookup.clear();
-                action.clone()
-            }),
-            _ => None,
+            .filter(|k| modes.contains(&k.mode) && k.keys.starts_with(&self.lookup))
+            .peekable();
+
+        if candidates.peek().is_none() {
+            self.lookup.clear();
+            return None;
+        }
+        if !candidates.all(|k| k.keys == self.lookup) {
+            return None;
         }
+        let keys = std::mem::take(&mut self.lookup);
+        modes
+            .into_iter()
+            .find_map(|mode| {
+                self.register
+                    .get(&KeyEventRegister::new(keys.clone(), mode))
+            })
+            .cloned()
     }
 }
 
@@ -158,11 +197,84 @@ fn vim_keybindings() -> HashMap<KeyEventRegister, Action> {
             KeyEventRegister::n(vec![KeyInput::new('v')]),
             SwitchMode(EditorMode::Visual).into(),
         ),
+        // Go into replace mode
+        (
+            KeyEventRegister::n(vec![KeyInput::shift('R')]),
+            SwitchMode(EditorMode::Replace).into(),
+        ),
+        // Go into visual block mode
+        (
+            KeyEventRegister::n(vec![KeyInput::ctrl('v')]),
+            SwitchMode(EditorMode::VisualBlock).into(),
+        ),
+        // Switch between the visual modes, or leave them with their own key
+        (
+            KeyEventRegister::v(vec![KeyInput::new('v')]),
+            SwitchMode(EditorMode::Normal).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::shift('V')]),
+            SwitchMode(EditorMode::VisualLine).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::ctrl('v')]),
+            SwitchMode(EditorMode::VisualBlock).into(),
+        ),
+        (
+            KeyEventRegister::new(vec![KeyInput::new('v')], EditorMode::VisualLine),
+            SwitchMode(EditorMode::Visual).into(),
+        ),
+        (
+            KeyEventRegister::new(vec![KeyInput::shift('V')], EditorMode::VisualLine),
+            SwitchMode(EditorMode::Normal).into(),
+        ),
+        (
+            KeyEventRegister::new(vec![KeyInput::new('v')], EditorMode::VisualBlock),
+            SwitchMode(EditorMode::Visual).into(),
+        ),
+        (
+            KeyEventRegister::new(vec![KeyInput::ctrl('v')], EditorMode::VisualBlock),
+            SwitchMode(EditorMode::Normal).into(),
+        ),
+        // Insert or append on every row of a visual block
+        (
+            KeyEventRegister::new(vec![KeyInput::shift('I')], EditorMode::VisualBlock),
+            InsertBlock.into(),
+        ),
+        (
+            KeyEventRegister::new(vec![KeyInput::shift('A')], EditorMode::VisualBlock),
+            AppendBlock.into(),
+        ),
         // Goes into search mode and starts of a new search.
         (
             KeyEventRegister::n(vec![KeyInput::new('/')]),
             StartSearch.chain(SwitchMode(EditorMode::Search)).into(),
         ),
+        // Goes into search mode and starts a new backward search.
+        (
+            KeyEventRegister::n(vec![KeyInput::new('?')]),
+            StartBackwardSearch
+                .chain(SwitchMode(EditorMode::Search))
+                .into(),
+        ),
+        // Search for the word under the cursor
+        (
+            KeyEventRegister::n(vec![KeyInput::new('*')]),
+            SearchWordForward.into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('#')]),
+            SearchWordBackward.into(),
+        ),
+        // Browse the search history
+        (
+            KeyEventRegister::s(vec![KeyInput::new(KeyCode::Up)]),
+            PreviousSearchHistory.into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::new(KeyCode::Down)]),
+            NextSearchHistory.into(),
+        ),
         // Trigger initial search
         (
             KeyEventRegister::s(vec![KeyInput::new(KeyCode::Enter)]),
@@ -188,6 +300,48 @@ fn vim_keybindings() -> HashMap<KeyEventRegister, Action> {
             KeyEventRegister::s(vec![KeyInput::new(KeyCode::Backspace)]),
             RemoveCharFromSearch.into(),
         ),
+        // Toggle search options
+        (
+            KeyEventRegister::s(vec![KeyInput::alt('r')]),
+            ToggleSearchOption(SearchOption::Regex).into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::alt('c')]),
+            ToggleSearchOption(SearchOption::IgnoreCase).into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::alt('s')]),
+            ToggleSearchOption(SearchOption::SmartCase).into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::alt('w')]),
+            ToggleSearchOption(SearchOption::WholeWord).into(),
+        ),
+        // Goes into command mode and starts a new command line.
+        (
+            KeyEventRegister::n(vec![KeyInput::new(':')]),
+            StartCommand.chain(SwitchMode(EditorMode::Command)).into(),
+        ),
+        // Run the command line
+        (
+            KeyEventRegister::c(vec![KeyInput::new(KeyCode::Enter)]),
+            ExecuteCommand.into(),
+        ),
+        // Complete the command line
+        (
+            KeyEventRegister::c(vec![KeyInput::new(KeyCode::Tab)]),
+            CompleteCommand.into(),
+        ),
+        // Cancel the command line
+        (
+            KeyEventRegister::c(vec![KeyInput::new(KeyCode::Esc)]),
+            StopCommand.chain(SwitchMode(EditorMode::Normal)).into(),
+        ),
+        // Delete last character from the command line
+        (
+            KeyEventRegister::c(vec![KeyInput::new(KeyCode::Backspace)]),
+            RemoveCharFromCommand.into(),
+        ),
         // Go into insert mode and move one char forward
         (
             KeyEventRegister::n(vec![KeyInput::new('a')]),
@@ -297,19 +451,53 @@ fn vim_keybindings() -> HashMap<KeyEventRegister, Action> {
         // Find/till the next character on the line (target read from next key)
         (
             KeyEventRegister::n(vec![KeyInput::new('f')]),
-            FindForward(None).into(),
+            FindForward::new(1).into(),
         ),
         (
             KeyEventRegister::v(vec![KeyInput::new('f')]),
-            FindForward(None).into(),
+            FindForward::new(1).into(),
         ),
         (
             KeyEventRegister::n(vec![KeyInput::new('t')]),
-            TillForward(None).into(),
+            TillForward::new(1).into(),
         ),
         (
             KeyEventRegister::v(vec![KeyInput::new('t')]),
-            TillForward(None).into(),
+            TillForward::new(1).into(),
+        ),
+        // Find/till the previous character on the line
+        (
+            KeyEventRegister::n(vec![KeyInput::shift('F')]),
+            FindBackward::new(1).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::shift('F')]),
+            FindBackward::new(1).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::shift('T')]),
+            TillBackward::new(1).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::shift('T')]),
+            TillBackward::new(1).into(),
+        ),
+        // Repeat the last find/till, in the same or the opposite direction
+        (
+            KeyEventRegister::n(vec![KeyInput::new(';')]),
+            RepeatFind::new().into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new(';')]),
+            RepeatFind::new().into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new(',')]),
+            RepeatFind::reverse().into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new(',')]),
+            RepeatFind::reverse().into(),
         ),
         (
             KeyEventRegister::n(vec![KeyInput::new('e')]),
@@ -327,6 +515,31 @@ fn vim_keybindings() -> HashMap<KeyEventRegister, Action> {
             KeyEventRegister::v(vec![KeyInput::new('b')]),
             MoveWordBackward(1).into(),
         ),
+        // Move cursor by WORDs (whitespace-delimited)
+        (
+            KeyEventRegister::n(vec![KeyInput::shift('W')]),
+            MoveBigWordForward(1).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::shift('W')]),
+            MoveBigWordForward(1).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::shift('E')]),
+            MoveBigWordForwardToEndOfWord(1).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::shift('E')]),
+            MoveBigWordForwardToEndOfWord(1).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::shift('B')]),
+            MoveBigWordBackward(1).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::shift('B')]),
+            MoveBigWordBackward(1).into(),
+        ),
         // Move cursor to start/first/last position
         (
             KeyEventRegister::n(vec![KeyInput::new('0')]),
@@ -505,63 +718,186 @@ fn vim_keybindings() -> HashMap<KeyEventRegister, Action> {
             KeyEventRegister::n(vec![KeyInput::new(KeyCode::Delete)]),
             RemoveChar(1).into(),
         ),
+        // Replace characters
+        (
+            KeyEventRegister::n(vec![KeyInput::new('r')]),
+            ReplaceChars::new(1).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('r')]),
+            ReplaceChars::new(1).into(),
+        ),
         // Delete the previous character
         (
             KeyEventRegister::i(vec![KeyInput::new(KeyCode::Backspace)]),
             DeleteChar(1).into(),
         ),
+        // Restore the previous character in replace mode
+        (
+            KeyEventRegister::new(vec![KeyInput::new(KeyCode::Backspace)], EditorMode::Replace),
+            RestoreChar(1).into(),
+        ),
         // Delete the next character
         (
             KeyEventRegister::i(vec![KeyInput::new(KeyCode::Delete)]),
             DeleteCharForward(1).into(),
         ),
-        // Delete the current line
+        // Operators, applied to the motion or text object that follows
         (
-            KeyEventRegister::n(vec![KeyInput::new('d'), KeyInput::new('d')]),
-            DeleteLine(1).into(),
+            KeyEventRegister::n(vec![KeyInput::new('d')]),
+            Operate::new(Operator::Delete).into(),
         ),
-        // Delete word forward
         (
-            KeyEventRegister::n(vec![KeyInput::new('d'), KeyInput::new('w')]),
-            DeleteWordForward(1).into(),
+            KeyEventRegister::n(vec![KeyInput::new('c')]),
+            Operate::new(Operator::Change).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('y')]),
+            Operate::new(Operator::Yank).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('>')]),
+            Operate::new(Operator::IndentRight).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('<')]),
+            Operate::new(Operator::IndentLeft).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('=')]),
+            Operate::new(Operator::Reindent).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('>')]),
+            OperateSelection(Operator::IndentRight).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('<')]),
+            OperateSelection(Operator::IndentLeft).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('=')]),
+            OperateSelection(Operator::Reindent).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('g'), KeyInput::new('u')]),
+            Operate::new(Operator::Lowercase).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('g'), KeyInput::shift('U')]),
+            Operate::new(Operator::Uppercase).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('g'), KeyInput::new('~')]),
+            Operate::new(Operator::ToggleCase).into(),
+        ),
+        // Change the case of characters and the selection
+        (
+            KeyEventRegister::n(vec![KeyInput::new('~')]),
+            ToggleCase(1).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('~')]),
+            ChangeSelectionCase(Case::Toggle).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('u')]),
+            ChangeSelectionCase(Case::Lower).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::shift('U')]),
+            ChangeSelectionCase(Case::Upper).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('g'), KeyInput::new('~')]),
+            ChangeSelectionCase(Case::Toggle).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('g'), KeyInput::new('u')]),
+            ChangeSelectionCase(Case::Lower).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('g'), KeyInput::shift('U')]),
+            ChangeSelectionCase(Case::Upper).into(),
+        ),
+        // Increment and decrement numbers
+        (
+            KeyEventRegister::n(vec![KeyInput::ctrl('a')]),
+            Increment::new(1).into(),
         ),
-        // Delete big WORD forward
         (
-            KeyEventRegister::n(vec![KeyInput::new('d'), KeyInput::shift('W')]),
-            DeleteBigWordForward(1).into(),
+            KeyEventRegister::n(vec![KeyInput::ctrl('x')]),
+            Decrement::new(1).into(),
         ),
-        // Delete find/till the next character on the line (target read from next key)
         (
-            KeyEventRegister::n(vec![KeyInput::new('d'), KeyInput::new('f')]),
-            DeleteFindForward(None).into(),
+            KeyEventRegister::v(vec![KeyInput::ctrl('a')]),
+            Increment::new(1).into(),
         ),
         (
-            KeyEventRegister::n(vec![KeyInput::new('d'), KeyInput::new('t')]),
-            DeleteTillForward(None).into(),
+            KeyEventRegister::v(vec![KeyInput::ctrl('x')]),
+            Decrement::new(1).into(),
         ),
-        // Delete inner word
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new('w'),
-            ]),
-            DeleteInnerWord.into(),
+            KeyEventRegister::v(vec![KeyInput::new('g'), KeyInput::ctrl('a')]),
+            Increment::new(1).sequential().into(),
         ),
-        // Delete inner big WORD
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::shift('W'),
-            ]),
-            DeleteInnerBigWord.into(),
+            KeyEventRegister::v(vec![KeyInput::new('g'), KeyInput::ctrl('x')]),
+            Decrement::new(1).sequential().into(),
         ),
         // Delete from the cursor to the end of the line
         (
             KeyEventRegister::n(vec![KeyInput::shift('D')]),
             DeleteToEndOfLine.into(),
         ),
+        // Set a mark and jump to it
+        (
+            KeyEventRegister::n(vec![KeyInput::new('m')]),
+            SetMark(None).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('`')]),
+            JumpToMark(None).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('`')]),
+            JumpToMark(None).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('\'')]),
+            JumpToMarkLine(None).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('\'')]),
+            JumpToMarkLine(None).into(),
+        ),
+        // Move through the jump list
+        (
+            KeyEventRegister::n(vec![KeyInput::ctrl('o')]),
+            JumpBackward(1).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new(KeyCode::Tab)]),
+            JumpForward(1).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::ctrl('i')]),
+            JumpForward(1).into(),
+        ),
+        // Select the register for the next yank, delete or paste
+        (
+            KeyEventRegister::n(vec![KeyInput::new('"')]),
+            SelectRegister(None).into(),
+        ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('"')]),
+            SelectRegister(None).into(),
+        ),
+        // Copy the current line
+        (
+            KeyEventRegister::n(vec![KeyInput::shift('Y')]),
+            YankLine(1).into(),
+        ),
         // Delete the current selection
         (
             KeyEventRegister::v(vec![KeyInput::new('d')]),
@@ -577,6 +913,10 @@ fn vim_keybindings() -> HashMap<KeyEventRegister, Action> {
             KeyEventRegister::v(vec![KeyInput::new('i'), KeyInput::new('w')]),
             SelectInnerWord.into(),
         ),
+        (
+            KeyEventRegister::v(vec![KeyInput::new('i'), KeyInput::shift('W')]),
+            SelectInnerBigWord.into(),
+        ),
         (
             KeyEventRegister::v(vec![KeyInput::new('i'), KeyInput::new('"')]),
             SelectInnerBetween::new('"', '"').into(),
@@ -609,171 +949,79 @@ fn vim_keybindings() -> HashMap<KeyEventRegister, Action> {
             KeyEventRegister::v(vec![KeyInput::new('i'), KeyInput::new(']')]),
             SelectInnerBetween::new('[', ']').into(),
         ),
-        // Change to the end of the word
-        (
-            KeyEventRegister::n(vec![KeyInput::new('c'), KeyInput::new('w')]),
-            ChangeWord(1).into(),
-        ),
-        // Change to the end of the big WORD
-        (
-            KeyEventRegister::n(vec![KeyInput::new('c'), KeyInput::shift('W')]),
-            ChangeBigWord(1).into(),
-        ),
-        // Change find/till the next character on the line (target read from next key)
-        (
-            KeyEventRegister::n(vec![KeyInput::new('c'), KeyInput::new('f')]),
-            ChangeFindForward(None).into(),
-        ),
-        (
-            KeyEventRegister::n(vec![KeyInput::new('c'), KeyInput::new('t')]),
-            ChangeTillForward(None).into(),
-        ),
-        // Change inner word
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new('w'),
-            ]),
-            ChangeInnerWord.into(),
+            KeyEventRegister::v(vec![KeyInput::new('i'), KeyInput::new('`')]),
+            SelectInnerBetween::new('`', '`').into(),
         ),
-        // Change inner big WORD
+        // Select a word or delimited text including its surroundings
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::shift('W'),
-            ]),
-            ChangeInnerBigWord.into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('w')]),
+            SelectAroundWord.into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new('"'),
-            ]),
-            ChangeInnerBetween::new('"', '"').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::shift('W')]),
+            SelectAroundBigWord.into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new('\''),
-            ]),
-            ChangeInnerBetween::new('\'', '\'').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('"')]),
+            SelectAroundBetween::new('"', '"').into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new('('),
-            ]),
-            ChangeInnerBetween::new('(', ')').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('\'')]),
+            SelectAroundBetween::new('\'', '\'').into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new(')'),
-            ]),
-            ChangeInnerBetween::new('(', ')').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('`')]),
+            SelectAroundBetween::new('`', '`').into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new('{'),
-            ]),
-            ChangeInnerBetween::new('{', '}').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('(')]),
+            SelectAroundBetween::new('(', ')').into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new('}'),
-            ]),
-            ChangeInnerBetween::new('{', '}').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new(')')]),
+            SelectAroundBetween::new('(', ')').into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new('['),
-            ]),
-            ChangeInnerBetween::new('[', ']').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('{')]),
+            SelectAroundBetween::new('{', '}').into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('c'),
-                KeyInput::new('i'),
-                KeyInput::new(']'),
-            ]),
-            ChangeInnerBetween::new('[', ']').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('}')]),
+            SelectAroundBetween::new('{', '}').into(),
         ),
-        // Delete inner text between delimiters
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new('"'),
-            ]),
-            DeleteInnerBetween::new('"', '"').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('[')]),
+            SelectAroundBetween::new('[', ']').into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new('\''),
-            ]),
-            DeleteInnerBetween::new('\'', '\'').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new(']')]),
+            SelectAroundBetween::new('[', ']').into(),
         ),
+        // Select paragraphs, sentences and XML/HTML tag blocks
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new('('),
-            ]),
-            DeleteInnerBetween::new('(', ')').into(),
+            KeyEventRegister::v(vec![KeyInput::new('i'), KeyInput::new('p')]),
+            SelectInnerParagraph(1).into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new(')'),
-            ]),
-            DeleteInnerBetween::new('(', ')').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('p')]),
+            SelectAroundParagraph(1).into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new('{'),
-            ]),
-            DeleteInnerBetween::new('{', '}').into(),
+            KeyEventRegister::v(vec![KeyInput::new('i'), KeyInput::new('s')]),
+            SelectInnerSentence.into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new('}'),
-            ]),
-            DeleteInnerBetween::new('{', '}').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('s')]),
+            SelectAroundSentence.into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new('['),
-            ]),
-            DeleteInnerBetween::new('[', ']').into(),
+            KeyEventRegister::v(vec![KeyInput::new('i'), KeyInput::new('t')]),
+            SelectInnerTag(1).into(),
         ),
         (
-            KeyEventRegister::n(vec![
-                KeyInput::new('d'),
-                KeyInput::new('i'),
-                KeyInput::new(']'),
-            ]),
-            DeleteInnerBetween::new('[', ']').into(),
+            KeyEventRegister::v(vec![KeyInput::new('a'), KeyInput::new('t')]),
+            SelectAroundTag(1).into(),
         ),
         // Change selection
         (
@@ -793,25 +1041,42 @@ fn vim_keybindings() -> HashMap<KeyEventRegister, Action> {
         (KeyEventRegister::n(vec![KeyInput::new('u')]), Undo.into()),
         // Redo
         (KeyEventRegister::n(vec![KeyInput::ctrl('r')]), Redo.into()),
+        // Move through the undo history in time
+        (
+            KeyEventRegister::n(vec![KeyInput::new('g'), KeyInput::new('-')]),
+            UndoEarlier(1).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('g'), KeyInput::new('+')]),
+            UndoLater(1).into(),
+        ),
+        // Record and replay macros
+        (
+            KeyEventRegister::n(vec![KeyInput::new('q')]),
+            RecordMacro(None).into(),
+        ),
+        (
+            KeyEventRegister::n(vec![KeyInput::new('@')]),
+            ReplayMacro::new(1).into(),
+        ),
         // Repeat the last change
         (
             KeyEventRegister::n(vec![KeyInput::new('.')]),
-            RepeatLastChange.into(),
+            RepeatLastChange(None).into(),
         ),
         // Copy
         (
             KeyEventRegister::v(vec![KeyInput::new('y')]),
             CopySelection.chain(SwitchMode(EditorMode::Normal)).into(),
         ),
+        // Paste
         (
-            KeyEventRegister::n(vec![KeyInput::new('y'), KeyInput::new('y')]),
-            CopyLine.into(),
+            KeyEventRegister::n(vec![KeyInput::new('p')]),
+            Paste(1).into(),
         ),
-        // Paste
-        (KeyEventRegister::n(vec![KeyInput::new('p')]), Paste.into()),
         (
             KeyEventRegister::n(vec![KeyInput::shift('P')]),
-            PasteBefore.into(),
+            PasteBefore(1).into(),
         ),
         (
             KeyEventRegister::v(vec![KeyInput::new('p')]),
@@ -860,6 +1125,30 @@ fn emacs_keybindings() -> HashMap<KeyEventRegister, Action> {
             KeyEventRegister::s(vec![KeyInput::new(KeyCode::Backspace)]),
             RemoveCharFromSearch.into(),
         ),
+        (
+            KeyEventRegister::s(vec![KeyInput::alt('r')]),
+            ToggleSearchOption(SearchOption::Regex).into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::alt('c')]),
+            ToggleSearchOption(SearchOption::IgnoreCase).into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::alt('s')]),
+            ToggleSearchOption(SearchOption::SmartCase).into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::alt('w')]),
+            ToggleSearchOption(SearchOption::WholeWord).into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::new(KeyCode::Up)]),
+            PreviousSearchHistory.into(),
+        ),
+        (
+            KeyEventRegister::s(vec![KeyInput::new(KeyCode::Down)]),
+            NextSearchHistory.into(),
+        ),
         (
             KeyEventRegister::i(vec![KeyInput::ctrl('f')]),
             MoveForward(1).into(),
@@ -997,7 +1286,10 @@ fn emacs_keybindings() -> HashMap<KeyEventRegister, Action> {
         ),
         (KeyEventRegister::i(vec![KeyInput::ctrl('u')]), Undo.into()),
         (KeyEventRegister::i(vec![KeyInput::ctrl('r')]), Redo.into()),
-        (KeyEventRegister::i(vec![KeyInput::ctrl('y')]), Paste.into()),
+        (
+            KeyEventRegister::i(vec![KeyInput::ctrl('y')]),
+            Paste(1).into(),
+        ),
         #[cfg(feature = "system-editor")]
         (
             KeyEventRegister::i(vec![KeyInput::alt('e')]),
@@ -1007,7 +1299,8 @@ fn emacs_keybindings() -> HashMap<KeyEventRegister, Action> {
 }
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
-pub struct KeyInputSequence(Vec<KeyInput>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyInputSequence(pub(crate) Vec<KeyInput>);
 
 impl KeyInputSequence {
     pub fn new(keys: Vec<KeyInput>) -> Self {
@@ -1083,75 +1376,282 @@ impl KeyEventRegister {
     {
         Self::new(key, EditorMode::Search)
     }
+
+    pub fn c<T>(key: T) -> Self
+    where
+        T: Into<KeyInputSequence>,
+    {
+        Self::new(key, EditorMode::Command)
+    }
 }
 
 impl KeyEventHandler {
     pub(crate) fn on_event<T>(&mut self, key: T, state: &mut EditorState)
+    where
+        T: Into<KeyInput> + Copy + std::fmt::Debug,
+    {
+        let view = ViewChange::new(state);
+        let recording = state.macros.is_recording();
+        self.handle_key(key.into(), state);
+
+        // The keys that start and stop a recording are not part of it.
+        if recording && state.macros.is_recording() {
+            state.macros.record(key.into());
+        }
+        self.replay_macros(state);
+        view.emit(state);
+    }
+
+    /// Handles a key and finishes the undo step it made.
+    fn handle_key(&mut self, key: KeyInput, state: &mut EditorState) {
+        self.handle_event(key, state);
+
+        // Unless every insertion is captured, an insert session is a single
+        // undo step.
+        let insert_session = !self.capture_on_insert
+            && matches!(state.mode, EditorMode::Insert | EditorMode::Replace);
+        state.history.set_insert_session(insert_session);
+        state.finish_undo_step();
+    }
+
+    /// Replays the keys of the macros played by the last key, as if they
+    /// were typed. A motion that fails drops the remaining keys.
+    fn replay_macros(&mut self, state: &mut EditorState) {
+        let mut replayed = 0;
+        while let Some(key) = state.macros.next_key() {
+            if replayed == MAX_REPLAYED_KEYS {
+                state.macros.clear_replay();
+                break;
+            }
+            replayed += 1;
+            self.handle_key(key, state);
+        }
+    }
+
+    fn handle_event<T>(&mut self, key: T, state: &mut EditorState)
     where
         T: Into<KeyInput> + Copy + std::fmt::Debug,
     {
         let mode = state.mode;
         let key_input = key.into().normalize_altgr();
 
-        // Always insert characters in insert mode
-        if mode == EditorMode::Insert {
-            if let input::KeyCode::Char(c) = key_input.key {
-                if key_input.modifiers == input::Modifiers::NONE
-                    || key_input.modifiers == input::Modifiers::SHIFT
+        // The error of the last command is shown until the next key.
+        state.command.error = None;
+
+        // Always insert characters in insert mode and overwrite them in
+        // replace mode, unless the text is read-only
+        if matches!(mode, EditorMode::Insert | EditorMode::Replace) && !state.read_only {
+            let typed = match key_input.key {
+                input::KeyCode::Char(c)
+                    if key_input.modifiers == input::Modifiers::NONE
+                        || key_input.modifiers == input::Modifiers::SHIFT =>
                 {
-                    if self.capture_on_insert {
-                        state.capture();
-                    }
-                    InsertChar(c).execute(state);
-                    return;
+                    Some(c)
                 }
-            }
-
-            if matches!(key_input.key, input::KeyCode::Tab)
-                && key_input.modifiers == input::Modifiers::NONE
-            {
+                input::KeyCode::Tab if key_input.modifiers == input::Modifiers::NONE => Some('\t'),
+                _ => None,
+            };
+            if let Some(c) = typed {
                 if self.capture_on_insert {
                     state.capture();
                 }
-                InsertChar('\t').execute(state);
+                if mode == EditorMode::Replace {
+                    OverwriteChar(c).execute(state);
+                } else {
+                    InsertChar(c).execute(state);
+                }
                 return;
             }
         }
 
+        // Always add characters to the command line in command mode
+        if mode == EditorMode::Command {
+            if let input::KeyCode::Char(c) = key_input.key {
+                if key_input.modifiers == input::Modifiers::NONE
+                    || key_input.modifiers == input::Modifiers::SHIFT
+                {
+                    AppendCharToCommand(c).execute(state);
+                    return;
+                }
+            }
+        }
+
         // Always add characters to search in search mode
         if mode == EditorMode::Search {
             if let input::KeyCode::Char(c) = key_input.key {
-                if key_input.modifiers == input::Modifiers::NONE {
+                if key_input.modifiers == input::Modifiers::NONE
+                    || key_input.modifiers == input::Modifiers::SHIFT
+                {
                     AppendCharToSearch(c).execute(state);
                     return;
                 }
             }
         }
 
+        // A replace waits for the confirmation of the current match.
+        if state.is_replacing() {
+            let choice = match key_input.key {
+                input::KeyCode::Char('y') => Some(ReplaceChoice::Yes),
+                input::KeyCode::Char('n') => Some(ReplaceChoice::No),
+                input::KeyCode::Char('a') => Some(ReplaceChoice::All),
+                input::KeyCode::Char('q') | input::KeyCode::Esc => Some(ReplaceChoice::Quit),
+                _ => None,
+            };
+            if let Some(choice) = choice {
+                ConfirmReplace(choice).execute(state);
+            }
+            return;
+        }
+
         // An action from a previous key is waiting for input.
         if let Some(mut action) = self.pending_char.take() {
             if let input::KeyCode::Char(c) = key_input.key {
                 if let Some(slot) = action.char_arg() {
                     *slot = Some(c);
                 }
-                state.execute_recorded(action);
+                self.dispatch(action, state);
+            } else {
+                self.cancel(state);
             }
             return;
         }
 
+        // Collect a count prefix. A leading `0` is a motion, not a count.
+        if (mode == EditorMode::Normal || mode.is_visual()) && self.lookup.is_empty() {
+            if let Some(digit) = count_digit(key_input, self.count) {
+                let count = self.count.unwrap_or(0).saturating_mul(10);
+                self.count = Some(count.saturating_add(digit));
+                return;
+            }
+        }
+
+        // While an operator is pending, motions and text objects are looked
+        // up among the visual mode bindings. Repeating the operator's key
+        // applies it to whole lines instead.
+        let lookup_mode = match &self.operator {
+            Some(operator) if self.lookup.is_empty() && key_input == operator.key => {
+                let count = self.count.take().unwrap_or(1);
+                self.dispatch(SelectLines(count).into(), state);
+                return;
+            }
+            Some(_) => EditorMode::Visual,
+            None => mode,
+        };
+
         // Else lookup an action from the register. Actions that still need a
         // character argument are held back until the next keystroke.
-        if let Some(mut action) = self.get(key_input, mode) {
-            if action.char_arg().is_some_and(|slot| slot.is_none()) {
-                self.pending_char = Some(action);
-            } else {
-                state.execute_recorded(action);
+        let action = self.get(key_input, lookup_mode);
+        if !self.lookup.is_empty() {
+            return;
+        }
+        // A register prefix (`3"add`) keeps the count for the command after it.
+        if let Some(Action::SelectRegister(_)) = action {
+            self.pending_char = action;
+            return;
+        }
+        // While recording, `q` stops the recording without a register name.
+        if let Some(Action::RecordMacro(_)) = action {
+            if state.macros.is_recording() {
+                self.count = None;
+                self.dispatch(RecordMacro(None).into(), state);
+                return;
             }
         }
+        let count = self.count.take();
+        let Some(mut action) = action else {
+            self.cancel(state);
+            return;
+        };
+
+        // Operators wait for a motion or text object.
+        if action.motion_arg().is_some_and(|slot| slot.is_none()) && self.operator.is_none() {
+            self.operator = Some(PendingOperator {
+                action,
+                key: key_input,
+                count,
+            });
+            return;
+        }
+
+        if let Some(count) = count {
+            action.apply_count(count);
+        }
+        if action.char_arg().is_some_and(|slot| slot.is_none()) {
+            self.pending_char = Some(action);
+        } else {
+            self.dispatch(action, state);
+        }
     }
-}
 
-#[cfg(test)]
+    /// Executes an action, or hands it to the pending operator as its motion.
+    fn dispatch(&mut self, mut action: Action, state: &mut EditorState) {
+        let Some(mut operator) = self.operator.take() else {
+            let selects_register = matches!(action, Action::SelectRegister(_));
+            let motion = can_fail(&action).then(|| (state.cursor, state.selection.clone()));
+            state.execute_recorded(action);
+            // Like in Vim, a motion that fails aborts the macros being replayed.
+            if motion == Some((state.cursor, state.selection.clone())) {
+                state.macros.clear_replay();
+            }
+            if !selects_register {
+                state.clip.deselect();
+            }
+            return;
+        };
+        if action.motion_kind().is_none() {
+            state.clip.deselect();
+            return;
+        }
+
+        // Counts before the operator and before the motion multiply (`2d3w`).
+        if let Some(count) = operator.count {
+            let motion_count = action.count().map_or(1, |count| *count);
+            action.apply_count(count.saturating_mul(motion_count));
+        }
+        if let Some(slot) = operator.action.motion_arg() {
+            *slot = Some(Box::new(action));
+        }
+        state.execute_recorded(operator.action);
+        state.clip.deselect();
+    }
+
+    /// Drops a pending operator and register selection.
+    fn cancel(&mut self, state: &mut EditorState) {
+        self.operator = None;
+        state.clip.deselect();
+    }
+}
+
+/// Returns whether an action is a motion that fails if it does not move the
+/// cursor. Jumps to the start or end of a line or buffer never fail.
+fn can_fail(action: &Action) -> bool {
+    action.motion_kind().is_some()
+        && !matches!(
+            action,
+            Action::MoveToStartOfLine(_)
+                | Action::MoveToFirst(_)
+                | Action::MoveToEndOfLine(_)
+                | Action::MoveToFirstRow(_)
+                | Action::MoveToLastRow(_)
+                | Action::MoveToLine(_)
+        )
+}
+
+/// Returns the value of a count digit, if the key continues a count prefix.
+fn count_digit(key_input: KeyInput, count: Option<usize>) -> Option<usize> {
+    if key_input.modifiers != input::Modifiers::NONE {
+        return None;
+    }
+    let input::KeyCode::Char(c) = key_input.key else {
+        return None;
+    };
+    match c.to_digit(10)? {
+        0 if count.is_none() => None,
+        digit => Some(digit as usize),
+    }
+}
+
+#[cfg(test)]
 mod tests {
     #[allow(deprecated)]
     use super::deprecated::KeyEvent;
@@ -1197,8 +1697,10 @@ mod tests {
     fn test_insert_hello_world() {
         use crate::EditorState;
 
-        let mut state = EditorState::default();
-        state.mode = EditorMode::Insert;
+        let mut state = EditorState {
+            mode: EditorMode::Insert,
+            ..Default::default()
+        };
 
         let mut handler = KeyEventHandler::default();
 
@@ -1587,8 +2089,10 @@ mod tests {
         use crate::EditorState;
         use crossterm::event::{KeyEvent as CTKeyEvent, KeyModifiers as CTMods};
 
-        let mut state = EditorState::default();
-        state.mode = EditorMode::Insert;
+        let mut state = EditorState {
+            mode: EditorMode::Insert,
+            ..Default::default()
+        };
 
         let mut handler = KeyEventHandler::emacs_mode();
 
@@ -1640,4 +2144,1174 @@ mod tests {
         assert_eq!(state.cursor.col, 6);
         assert_eq!(state.lines.to_string(), "Hello World");
     }
+
+    #[test]
+    fn test_count_prefix_motion() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("one two three four"));
+        let mut handler = KeyEventHandler::default();
+
+        handler.on_event(KeyInput::new('3'), &mut state);
+        handler.on_event(KeyInput::new('w'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 14));
+
+        // The count is consumed by the first action.
+        handler.on_event(KeyInput::new('b'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 8));
+    }
+
+    #[test]
+    fn test_count_prefix_multi_digit() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("abcdefghijklmnop"));
+        let mut handler = KeyEventHandler::default();
+
+        handler.on_event(KeyInput::new('1'), &mut state);
+        handler.on_event(KeyInput::new('0'), &mut state);
+        handler.on_event(KeyInput::new('l'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 10));
+
+        // A leading `0` still moves to the start of the line.
+        handler.on_event(KeyInput::new('0'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
+    #[test]
+    fn test_count_prefix_delete() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("1\n2\n3\n4\n5\n6\n7"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(1, 0);
+
+        handler.on_event(KeyInput::new('5'), &mut state);
+        handler.on_event(KeyInput::new('d'), &mut state);
+        handler.on_event(KeyInput::new('d'), &mut state);
+        assert_eq!(state.lines, Lines::from("1\n7"));
+
+        handler.on_event(KeyInput::new('2'), &mut state);
+        handler.on_event(KeyInput::new('x'), &mut state);
+        assert_eq!(state.lines, Lines::from("1\n"));
+    }
+
+    #[test]
+    fn test_count_prefix_goto_line() {
+        use crate::{EditorState, Index2, Lines};
+
+        let lines = (1..=20).map(|i| i.to_string()).collect::<Vec<_>>();
+        let mut state = EditorState::new(Lines::from(lines.join("\n").as_str()));
+        let mut handler = KeyEventHandler::default();
+
+        handler.on_event(KeyInput::new('1'), &mut state);
+        handler.on_event(KeyInput::new('0'), &mut state);
+        handler.on_event(KeyInput::shift('G'), &mut state);
+        assert_eq!(state.cursor, Index2::new(9, 0));
+
+        handler.on_event(KeyInput::new('3'), &mut state);
+        handler.on_event(KeyInput::new('g'), &mut state);
+        handler.on_event(KeyInput::new('g'), &mut state);
+        assert_eq!(state.cursor, Index2::new(2, 0));
+
+        // Without a count, `G` still jumps to the last row.
+        handler.on_event(KeyInput::shift('G'), &mut state);
+        assert_eq!(state.cursor, Index2::new(19, 0));
+    }
+
+    #[test]
+    fn test_count_prefix_dot_repeat() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("abcdefghij"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(0, 0);
+
+        // `2x` then `.` repeats with the original count.
+        handler.on_event(KeyInput::new('2'), &mut state);
+        handler.on_event(KeyInput::new('x'), &mut state);
+        handler.on_event(KeyInput::new('.'), &mut state);
+        assert_eq!(state.lines.to_string(), "efghij");
+
+        // `3.` replaces the count, and the new count sticks for `.`.
+        handler.on_event(KeyInput::new('3'), &mut state);
+        handler.on_event(KeyInput::new('.'), &mut state);
+        assert_eq!(state.lines.to_string(), "hij");
+        handler.on_event(KeyInput::new('.'), &mut state);
+        assert_eq!(state.lines.to_string(), "");
+    }
+
+    #[test]
+    fn test_count_prefix_find_and_paste() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("a,b,c,d"));
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                let key = match c {
+                    c if c.is_ascii_uppercase() => KeyInput::shift(c),
+                    c => KeyInput::new(c),
+                };
+                handler.on_event(key, state);
+            }
+        };
+
+        keys(&mut handler, &mut state, "2f,");
+        assert_eq!(state.cursor, Index2::new(0, 3));
+        state.cursor = Index2::new(0, 0);
+        keys(&mut handler, &mut state, "2t,");
+        assert_eq!(state.cursor, Index2::new(0, 2));
+
+        // Without enough matches the cursor stays.
+        keys(&mut handler, &mut state, "5f,");
+        assert_eq!(state.cursor, Index2::new(0, 2));
+
+        state.cursor = Index2::new(0, 0);
+        keys(&mut handler, &mut state, "d2f,");
+        assert_eq!(state.lines, Lines::from("c,d"));
+
+        let mut state = EditorState::new(Lines::from("ab"));
+        keys(&mut handler, &mut state, "yl3p");
+        assert_eq!(state.lines, Lines::from("aaaab"));
+        keys(&mut handler, &mut state, "2P");
+        assert_eq!(state.lines, Lines::from("aaaaaab"));
+    }
+
+    #[test]
+    fn test_operator_with_motion() {
+        use crate::clipboard::ClipboardTrait;
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("foo\nbar\n\nbaz"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(0, 1);
+
+        // `d}` deletes up to the blank line.
+        handler.on_event(KeyInput::new('d'), &mut state);
+        handler.on_event(KeyInput::new('}'), &mut state);
+        assert_eq!(state.lines, Lines::from("f\n\nbaz"));
+
+        // `c$` deletes to the end of the line and enters insert mode.
+        state.cursor = Index2::new(2, 1);
+        handler.on_event(KeyInput::new('c'), &mut state);
+        handler.on_event(KeyInput::new('$'), &mut state);
+        assert_eq!(state.lines, Lines::from("f\n\nb"));
+        assert_eq!(state.mode, EditorMode::Insert);
+
+        // `y%` yanks up to the matching bracket.
+        let mut state = EditorState::new(Lines::from("(a b) c"));
+        handler.on_event(KeyInput::new('y'), &mut state);
+        handler.on_event(KeyInput::new('%'), &mut state);
+        assert_eq!(state.clip.get_text(), "(a b)");
+        assert_eq!(state.lines, Lines::from("(a b) c"));
+    }
+
+    #[test]
+    fn test_operator_linewise() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("one\ntwo\nthree\nfour"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(1, 1);
+
+        // `dG` deletes to the last line.
+        handler.on_event(KeyInput::new('d'), &mut state);
+        handler.on_event(KeyInput::shift('G'), &mut state);
+        assert_eq!(state.lines, Lines::from("one"));
+
+        // `guu` lowercases and `>>` indents the current line.
+        let mut state = EditorState::new(Lines::from("ABC\nDEF"));
+        handler.on_event(KeyInput::new('g'), &mut state);
+        handler.on_event(KeyInput::new('u'), &mut state);
+        handler.on_event(KeyInput::new('u'), &mut state);
+        handler.on_event(KeyInput::new('>'), &mut state);
+        handler.on_event(KeyInput::new('>'), &mut state);
+        assert_eq!(state.lines, Lines::from("\tabc\nDEF"));
+    }
+
+    #[test]
+    fn test_operator_counts_multiply() {
+        use crate::{EditorState, Lines};
+
+        let mut state = EditorState::new(Lines::from("a b c d e f g h"));
+        let mut handler = KeyEventHandler::default();
+
+        handler.on_event(KeyInput::new('2'), &mut state);
+        handler.on_event(KeyInput::new('d'), &mut state);
+        handler.on_event(KeyInput::new('3'), &mut state);
+        handler.on_event(KeyInput::new('w'), &mut state);
+        assert_eq!(state.lines.to_string(), "g h");
+
+        // The whole operation repeats with `.`.
+        handler.on_event(KeyInput::new('.'), &mut state);
+        assert_eq!(state.lines.to_string(), "");
+    }
+
+    #[test]
+    fn test_around_text_objects() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                handler.on_event(KeyInput::new(c), state);
+            }
+        };
+
+        // `a"` includes the whitespace after the closing quote.
+        let mut state = EditorState::new(Lines::from("say \"hi there\" ok"));
+        keys(&mut handler, &mut state, "fhda\"");
+        assert_eq!(state.lines.to_string(), "say ok");
+
+        // A count selects an outer pair of brackets.
+        let mut state = EditorState::new(Lines::from("f((a) b) x"));
+        state.cursor = Index2::new(0, 3);
+        keys(&mut handler, &mut state, "d2a(");
+        assert_eq!(state.lines.to_string(), "f x");
+
+        let mut state = EditorState::new(Lines::from("f((a) b) x"));
+        state.cursor = Index2::new(0, 3);
+        keys(&mut handler, &mut state, "d2i(");
+        assert_eq!(state.lines.to_string(), "f() x");
+
+        // A count selects several paragraphs.
+        let mut state = EditorState::new(Lines::from("a\n\nb\n\nc"));
+        keys(&mut handler, &mut state, "d2ap");
+        assert_eq!(state.lines.to_string(), "c");
+
+        // And tags the count-th enclosing tag block.
+        let mut state = EditorState::new(Lines::from("<a><b>x</b></a>"));
+        state.cursor = Index2::new(0, 6);
+        keys(&mut handler, &mut state, "d2it");
+        assert_eq!(state.lines.to_string(), "<a></a>");
+    }
+
+    #[test]
+    fn test_operator_with_custom_motion() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("abc def"));
+        let mut handler = KeyEventHandler::default();
+        handler.insert(
+            KeyEventRegister::v(vec![KeyInput::new('L')]),
+            MoveToEndOfLine(),
+        );
+        state.cursor = Index2::new(0, 4);
+
+        handler.on_event(KeyInput::new('d'), &mut state);
+        handler.on_event(KeyInput::new('L'), &mut state);
+        assert_eq!(state.lines.to_string(), "abc ");
+    }
+
+    #[test]
+    fn test_operator_cancelled() {
+        use crate::{EditorState, Lines};
+
+        let mut state = EditorState::new(Lines::from("abc"));
+        let mut handler = KeyEventHandler::default();
+
+        // An unknown key drops the operator, the next key acts on its own.
+        handler.on_event(KeyInput::new('d'), &mut state);
+        handler.on_event(KeyInput::new(KeyCode::Esc), &mut state);
+        handler.on_event(KeyInput::new('x'), &mut state);
+        assert_eq!(state.lines.to_string(), "bc");
+        assert_eq!(state.mode, EditorMode::Normal);
+    }
+
+    #[test]
+    fn test_register_prefix() {
+        use crate::clipboard::InternalClipboard;
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("one\ntwo\nthree"));
+        state.set_clipboard(InternalClipboard::default());
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                handler.on_event(KeyInput::new(c), state);
+            }
+        };
+
+        // `"ayy` yanks into register `a` and leaves the clipboard alone.
+        keys(&mut handler, &mut state, "\"ayy");
+        assert_eq!(state.register('a'), Some(String::from("one")));
+        assert_eq!(state.register('+'), Some(String::new()));
+
+        // `"_dd` deletes without touching any register.
+        state.cursor = Index2::new(1, 0);
+        keys(&mut handler, &mut state, "\"_dd");
+        assert_eq!(state.lines, Lines::from("one\nthree"));
+        assert_eq!(state.register('1'), None);
+
+        // A count before the register applies to the command.
+        state.cursor = Index2::new(0, 0);
+        keys(&mut handler, &mut state, "2\"byy");
+        assert_eq!(state.register('b'), Some(String::from("one\nthree")));
+
+        // `"ap` pastes register `a`, a plain `p` the clipboard.
+        keys(&mut handler, &mut state, "\"ap");
+        assert_eq!(state.lines, Lines::from("one\none\nthree"));
+        keys(&mut handler, &mut state, "p");
+        assert_eq!(state.lines, Lines::from("one\none\nthree"));
+    }
+
+    fn type_keys(handler: &mut KeyEventHandler, state: &mut EditorState, keys: &[KeyInput]) {
+        for key in keys {
+            handler.on_event(*key, state);
+        }
+    }
+
+    #[test]
+    fn test_count_find_backward_and_repeat() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("a,b,c,d"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(0, 6);
+
+        let keys = [KeyInput::new('2'), KeyInput::shift('F'), KeyInput::new(',')];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.cursor, Index2::new(0, 3));
+
+        state.cursor = Index2::new(0, 6);
+        let keys = [KeyInput::new('2'), KeyInput::shift('T'), KeyInput::new(',')];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.cursor, Index2::new(0, 4));
+
+        state.cursor = Index2::new(0, 0);
+        let keys = [
+            KeyInput::new('f'),
+            KeyInput::new(','),
+            KeyInput::new('2'),
+            KeyInput::new(';'),
+        ];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.cursor, Index2::new(0, 5));
+
+        // Counts also work in visual and operator-pending mode.
+        let keys = [
+            KeyInput::new('v'),
+            KeyInput::new('2'),
+            KeyInput::shift('F'),
+            KeyInput::new(','),
+            KeyInput::new('d'),
+        ];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("ad"));
+
+        let mut state = EditorState::new(Lines::from("a,b,c,d"));
+        state.cursor = Index2::new(0, 6);
+        let keys = [
+            KeyInput::new('d'),
+            KeyInput::new('2'),
+            KeyInput::shift('F'),
+            KeyInput::new(','),
+        ];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("a,bd"));
+    }
+
+    #[test]
+    fn test_find_backward_and_repeat() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("a.b.c.d.e"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(0, 6);
+
+        let keys = [KeyInput::new('d'), KeyInput::shift('F'), KeyInput::new('.')];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("a.b.cd.e"));
+
+        // `,` repeats the search forward, so `d,` acts like `df.`.
+        let keys = [KeyInput::new('d'), KeyInput::new(',')];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("a.b.ce"));
+
+        handler.on_event(KeyInput::new(';'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 3));
+
+        let keys = [KeyInput::new('v'), KeyInput::new(';'), KeyInput::new('d')];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("ace"));
+    }
+
+    #[test]
+    fn test_text_objects_with_operators() {
+        use crate::clipboard::InternalClipboard;
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("one two\n\n<p>hi</p> `x`"));
+        state.set_clipboard(InternalClipboard::default());
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                handler.on_event(KeyInput::new(c), state);
+            }
+        };
+
+        keys(&mut handler, &mut state, "daw");
+        assert_eq!(state.lines, Lines::from("two\n\n<p>hi</p> `x`"));
+
+        keys(&mut handler, &mut state, "dap");
+        assert_eq!(state.lines, Lines::from("<p>hi</p> `x`"));
+
+        state.cursor = Index2::new(0, 11);
+        keys(&mut handler, &mut state, "ya`");
+        assert_eq!(state.register('+'), Some(String::from(" `x`")));
+
+        state.cursor = Index2::new(0, 4);
+        keys(&mut handler, &mut state, "cit");
+        assert_eq!(state.lines, Lines::from("<p></p> `x`"));
+        assert_eq!(state.mode, EditorMode::Insert);
+
+        handler.on_event(KeyInput::new(KeyCode::Esc), &mut state);
+        keys(&mut handler, &mut state, "vatd");
+        assert_eq!(state.lines, Lines::from(" `x`"));
+    }
+
+    #[test]
+    fn test_command_mode() {
+        use crate::command::CommandError;
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("one\ntwo\nthree"));
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                handler.on_event(KeyInput::new(c), state);
+            }
+        };
+
+        keys(&mut handler, &mut state, ":3");
+        assert_eq!(state.mode, EditorMode::Command);
+        assert_eq!(state.command_line(), "3");
+        handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+        assert_eq!(state.mode, EditorMode::Normal);
+        assert_eq!(state.cursor, Index2::new(2, 0));
+
+        // Tab completes the command name.
+        keys(&mut handler, &mut state, ":%subs");
+        handler.on_event(KeyInput::new(KeyCode::Tab), &mut state);
+        assert_eq!(state.command_line(), "%substitute");
+        keys(&mut handler, &mut state, "/o/0/");
+        handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+        assert_eq!(state.lines, Lines::from("0ne\ntw0\nthree"));
+
+        // Errors are shown until the next key.
+        keys(&mut handler, &mut state, ":nope");
+        handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+        let want = CommandError::NotFound(String::from("nope"));
+        assert_eq!(state.command_error(), Some(&want));
+        handler.on_event(KeyInput::new('k'), &mut state);
+        assert_eq!(state.command_error(), None);
+
+        // Backspace on an empty command line leaves command mode.
+        keys(&mut handler, &mut state, ":x");
+        handler.on_event(KeyInput::new(KeyCode::Backspace), &mut state);
+        handler.on_event(KeyInput::new(KeyCode::Backspace), &mut state);
+        assert_eq!(state.mode, EditorMode::Normal);
+        assert!(state.take_command_events().is_empty());
+    }
+
+    #[test]
+    fn test_toggle_search_options() {
+        use crate::{EditorState, Index2, Lines, SearchOptions};
+
+        let mut state = EditorState::new(Lines::from("Foo foo fo+"));
+        let mut handler = KeyEventHandler::default();
+        for c in "/fo+".chars() {
+            handler.on_event(KeyInput::new(c), &mut state);
+        }
+        handler.on_event(KeyInput::alt('r'), &mut state);
+        handler.on_event(KeyInput::alt('c'), &mut state);
+        assert_eq!(
+            state.search_options(),
+            SearchOptions {
+                regex: true,
+                ignore_case: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(state.search.matches.len(), 3);
+
+        handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+        handler.on_event(KeyInput::new('n'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 4));
+    }
+
+    #[test]
+    fn test_smart_case_search() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("foo\nfoo Foo"));
+        state.execute_command("set ic scs").unwrap();
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                let key = match c {
+                    '\n' => KeyInput::new(KeyCode::Enter),
+                    c if c.is_uppercase() => KeyInput::shift(c),
+                    c => KeyInput::new(c),
+                };
+                handler.on_event(key, state);
+            }
+        };
+
+        // An uppercase letter makes the search case-sensitive.
+        keys(&mut handler, &mut state, "/Foo\n");
+        assert_eq!(state.cursor, Index2::new(1, 4));
+
+        state.cursor = Index2::new(1, 1);
+        keys(&mut handler, &mut state, "/foo\n");
+        assert_eq!(state.cursor, Index2::new(1, 4));
+    }
+
+    #[test]
+    fn test_backward_search() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("ab\nab\nab"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(1, 1);
+
+        for c in "?ab".chars() {
+            handler.on_event(KeyInput::new(c), &mut state);
+        }
+        assert_eq!(state.cursor, Index2::new(1, 0));
+        handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+
+        // `n` keeps going backward, `N` goes forward.
+        handler.on_event(KeyInput::new('n'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+        handler.on_event(KeyInput::new('n'), &mut state);
+        assert_eq!(state.cursor, Index2::new(2, 0));
+        handler.on_event(KeyInput::shift('N'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
+    #[test]
+    fn test_search_history() {
+        use crate::{EditorState, Lines};
+
+        let mut state = EditorState::new(Lines::from("one two"));
+        let mut handler = KeyEventHandler::default();
+        for pattern in ["one", "two"] {
+            handler.on_event(KeyInput::new('/'), &mut state);
+            for c in pattern.chars() {
+                handler.on_event(KeyInput::new(c), &mut state);
+            }
+            handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+        }
+
+        handler.on_event(KeyInput::new('/'), &mut state);
+        handler.on_event(KeyInput::new('o'), &mut state);
+        handler.on_event(KeyInput::new(KeyCode::Up), &mut state);
+        assert_eq!(state.search_pattern(), "two");
+        handler.on_event(KeyInput::new(KeyCode::Up), &mut state);
+        handler.on_event(KeyInput::new(KeyCode::Up), &mut state);
+        assert_eq!(state.search_pattern(), "one");
+        handler.on_event(KeyInput::new(KeyCode::Down), &mut state);
+        handler.on_event(KeyInput::new(KeyCode::Down), &mut state);
+        assert_eq!(state.search_pattern(), "o");
+    }
+
+    #[test]
+    fn test_search_word_under_cursor() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("foo foobar\n(foo) foo"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(0, 1);
+
+        handler.on_event(KeyInput::new('*'), &mut state);
+        assert_eq!(state.cursor, Index2::new(1, 1));
+        handler.on_event(KeyInput::new('n'), &mut state);
+        assert_eq!(state.cursor, Index2::new(1, 6));
+
+        handler.on_event(KeyInput::new('#'), &mut state);
+        assert_eq!(state.cursor, Index2::new(1, 1));
+        handler.on_event(KeyInput::new('n'), &mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        // Off a word, the next word on the line is used.
+        state.cursor = Index2::new(1, 0);
+        handler.on_event(KeyInput::new('*'), &mut state);
+        assert_eq!(state.cursor, Index2::new(1, 6));
+    }
+
+    #[test]
+    fn test_undo_branches() {
+        use crate::{EditorState, Lines};
+
+        let mut state = EditorState::new(Lines::from("abc"));
+        let mut handler = KeyEventHandler::default();
+        for key in [
+            KeyInput::new('x'),
+            KeyInput::new('u'),
+            KeyInput::shift('A'),
+            KeyInput::new('d'),
+            KeyInput::new(KeyCode::Esc),
+        ] {
+            handler.on_event(key, &mut state);
+        }
+        assert_eq!(state.lines, Lines::from("abcd"));
+
+        handler.on_event(KeyInput::new('g'), &mut state);
+        handler.on_event(KeyInput::new('-'), &mut state);
+        assert_eq!(state.lines, Lines::from("bc"));
+        handler.on_event(KeyInput::new('2'), &mut state);
+        handler.on_event(KeyInput::new('g'), &mut state);
+        handler.on_event(KeyInput::new('+'), &mut state);
+        assert_eq!(state.lines, Lines::from("abcd"));
+    }
+
+    #[test]
+    fn test_undo_visual_change() {
+        use crate::{EditorState, Lines};
+
+        // Undo leaves the editor in normal mode, so `u` undoes again.
+        let mut state = EditorState::new(Lines::from("AB CD"));
+        let mut handler = KeyEventHandler::default();
+        for c in "xvld".chars() {
+            handler.on_event(KeyInput::new(c), &mut state);
+        }
+        assert_eq!(state.lines, Lines::from("CD"));
+
+        handler.on_event(KeyInput::new('u'), &mut state);
+        assert_eq!(state.lines, Lines::from("B CD"));
+        assert_eq!(state.mode, EditorMode::Normal);
+        handler.on_event(KeyInput::new('u'), &mut state);
+        assert_eq!(state.lines, Lines::from("AB CD"));
+    }
+
+    #[test]
+    fn test_read_only() {
+        use crate::clipboard::InternalClipboard;
+        use crate::command::CommandError;
+        use crate::events::EditorEventHandler;
+        use crate::{EditorState, Index2, Lines, ReplaceFlags};
+
+        let mut state = EditorState::new(Lines::from("one two\nthree"));
+        state.set_clipboard(InternalClipboard::default());
+        state.set_read_only(true);
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                handler.on_event(KeyInput::new(c), state);
+            }
+        };
+
+        keys(&mut handler, &mut state, "xddiab");
+        handler.on_event(KeyInput::new(KeyCode::Esc), &mut state);
+        keys(&mut handler, &mut state, "pJ");
+        assert_eq!(state.lines, Lines::from("one two\nthree"));
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        // Visual shifts and deletes are refused too.
+        for visual in ["vj>", "Vj<", "vj=", "vjd"] {
+            keys(&mut handler, &mut state, visual);
+            handler.on_event(KeyInput::new(KeyCode::Esc), &mut state);
+            assert_eq!(state.lines, Lines::from("one two\nthree"), "{visual}");
+        }
+        keys(&mut handler, &mut state, "gg0");
+
+        // Navigation, search, selection and copy still work.
+        keys(&mut handler, &mut state, "wviwy/thr");
+        handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+        assert_eq!(state.cursor, Index2::new(1, 0));
+        assert_eq!(state.register('"'), Some(String::from("two")));
+
+        keys(&mut handler, &mut state, ":s/t/T/");
+        handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+        assert_eq!(state.command_error(), Some(&CommandError::ReadOnly));
+
+        EditorEventHandler::default().on_paste_event(String::from("x"), &mut state);
+        assert_eq!(state.lines, Lines::from("one two\nthree"));
+
+        // A replace waiting for confirmation is cancelled, and none starts.
+        state.set_read_only(false);
+        state.start_replace("t", "T", ReplaceFlags::default());
+        state.set_read_only(true);
+        assert!(!state.is_replacing());
+        keys(&mut handler, &mut state, "ya");
+        assert_eq!(state.start_replace("t", "T", ReplaceFlags::default()), 0);
+        assert_eq!(state.replace_all("t", "T", ReplaceFlags::default()), 0);
+        assert_eq!(state.lines, Lines::from("one two\nthree"));
+    }
+
+    #[test]
+    fn test_protected_range() {
+        use crate::{EditorState, Index2, Lines, ProtectedRange};
+
+        let mut state = EditorState::new(Lines::from("Subject: \n\nbody"));
+        state.add_protected_range(ProtectedRange::new(Index2::new(0, 0), Index2::new(0, 7)));
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                let key = match c {
+                    '\x1b' => KeyInput::new(KeyCode::Esc),
+                    c if c.is_uppercase() => KeyInput::shift(c),
+                    c => KeyInput::new(c),
+                };
+                handler.on_event(key, state);
+            }
+        };
+
+        keys(&mut handler, &mut state, "lllxddwdwiab\x1b");
+        assert_eq!(state.lines, Lines::from("Subject: \n\nbody"));
+        assert_eq!(state.cursor, Index2::new(0, 6));
+
+        keys(&mut handler, &mut state, "Afix\x1bGAed\x1b");
+        assert_eq!(state.lines, Lines::from("Subject: fix\n\nbodyed"));
+
+        // Edits before the range move it.
+        keys(&mut handler, &mut state, "ggOtop\x1bjx");
+        assert_eq!(state.lines, Lines::from("top\nSubject: fix\n\nbodyed"));
+        let range = ProtectedRange::new(Index2::new(1, 0), Index2::new(1, 7));
+        assert_eq!(state.protected_ranges(), &[range]);
+
+        let flags = crate::ReplaceFlags::default();
+        assert_eq!(state.replace_all("[a-z]", "_", flags), 0);
+        let flags = crate::ReplaceFlags {
+            search: crate::SearchOptions {
+                regex: true,
+                ..Default::default()
+            },
+            first_per_line: false,
+        };
+        assert_eq!(state.replace_all("[a-z]", "_", flags), 12);
+        assert_eq!(state.lines, Lines::from("___\nSubject: ___\n\n______"));
+
+        // Text typed in front of equal protected text is not inside it.
+        let mut state = EditorState::new(Lines::from("xaaab"));
+        let range = ProtectedRange::new(Index2::new(0, 1), Index2::new(0, 3));
+        state.add_protected_range(range.clone());
+        keys(&mut handler, &mut state, "lia\x1b");
+        assert_eq!(state.lines, Lines::from("xaaaab"));
+        keys(&mut handler, &mut state, "x");
+        assert_eq!(state.lines, Lines::from("xaaab"));
+        assert_eq!(state.protected_ranges(), &[range]);
+        keys(&mut handler, &mut state, "lx");
+        assert_eq!(state.lines, Lines::from("xaaab"));
+    }
+
+    #[test]
+    fn test_macros() {
+        use crate::{EditorState, Lines};
+
+        let mut state = EditorState::new(Lines::from("x;y\nx;y\nx;y\nx;y"));
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                let key = match c {
+                    '\x1b' => KeyInput::new(KeyCode::Esc),
+                    c => KeyInput::new(c),
+                };
+                handler.on_event(key, state);
+            }
+        };
+
+        keys(&mut handler, &mut state, "qa0f;i-\x1bj");
+        assert_eq!(state.recording_macro(), Some('a'));
+        keys(&mut handler, &mut state, "q");
+        assert_eq!(state.recording_macro(), None);
+        let recorded: Vec<KeyInput> = state.macro_register('a').unwrap().clone().into();
+        assert_eq!(recorded.len(), 7);
+
+        // Replays re-enter the pending `f` and the insert mode.
+        keys(&mut handler, &mut state, "2@a@@");
+        assert_eq!(state.lines, Lines::from("x-;y\nx-;y\nx-;y\nx-;y"));
+        keys(&mut handler, &mut state, "u");
+        assert_eq!(state.lines, Lines::from("x-;y\nx-;y\nx-;y\nx;y"));
+
+        // A macro that replays itself stops.
+        keys(&mut handler, &mut state, "qbq");
+        keys(&mut handler, &mut state, "qb@bq@b");
+        assert_eq!(state.lines, Lines::from("x-;y\nx-;y\nx-;y\nx;y"));
+
+        // A recursive macro stops at the first motion that fails.
+        let mut state = EditorState::new(Lines::from("abc\nabc\nabc"));
+        keys(&mut handler, &mut state, "qaqqaxj@aq@a");
+        assert_eq!(state.lines, Lines::from("bc\nbc\nbc"));
+    }
+
+    #[test]
+    fn test_marks_and_jump_list() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("one\n  two\nthree\nfour"));
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                let key = match c {
+                    '\x1b' => KeyInput::new(KeyCode::Esc),
+                    c if c.is_uppercase() => KeyInput::shift(c),
+                    c => KeyInput::new(c),
+                };
+                handler.on_event(key, state);
+            }
+        };
+
+        keys(&mut handler, &mut state, "jlllmaG`a");
+        assert_eq!(state.cursor, Index2::new(1, 3));
+        keys(&mut handler, &mut state, "''");
+        assert_eq!(state.cursor, Index2::new(3, 0));
+
+        handler.on_event(KeyInput::ctrl('o'), &mut state);
+        assert_eq!(state.cursor, Index2::new(1, 3));
+        handler.on_event(KeyInput::new(KeyCode::Tab), &mut state);
+        assert_eq!(state.cursor, Index2::new(3, 0));
+
+        // Marks move with the edits and work as motions.
+        keys(&mut handler, &mut state, "ggOnew\x1b`a");
+        assert_eq!(state.cursor, Index2::new(2, 3));
+        keys(&mut handler, &mut state, "ggd'a");
+        assert_eq!(state.lines, Lines::from("three\nfour"));
+    }
+
+    #[test]
+    fn test_confirm_replace() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("ab\nab\nab\nab"));
+        let mut handler = KeyEventHandler::default();
+        for c in ":%s/a/x/c".chars() {
+            handler.on_event(KeyInput::new(c), &mut state);
+        }
+        handler.on_event(KeyInput::new(KeyCode::Enter), &mut state);
+        assert!(state.is_replacing());
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        // Other keys are ignored while waiting for an answer.
+        for c in "yjnq".chars() {
+            handler.on_event(KeyInput::new(c), &mut state);
+        }
+        assert!(!state.is_replacing());
+        assert_eq!(state.lines, Lines::from("xb\nab\nab\nab"));
+        assert_eq!(state.cursor, Index2::new(2, 0));
+
+        handler.on_event(KeyInput::new('u'), &mut state);
+        assert_eq!(state.lines, Lines::from("ab\nab\nab\nab"));
+    }
+
+    #[test]
+    fn test_visual_line_mode() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("one\ntwo\nthree"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(0, 1);
+
+        let keys = [KeyInput::shift('V'), KeyInput::new('j'), KeyInput::new('d')];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("three"));
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        // `v` switches to characterwise visual mode, keeping the anchor.
+        let mut state = EditorState::new(Lines::from("one\ntwo"));
+        let keys = [KeyInput::shift('V'), KeyInput::new('v')];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.mode, EditorMode::Visual);
+        assert!(!state.selection.as_ref().is_some_and(|s| s.line_mode));
+    }
+
+    #[test]
+    fn test_visual_block_delete_and_paste() {
+        use crate::clipboard::{InternalClipboard, RegisterKind};
+        use crate::{EditorState, Lines};
+
+        let mut state = EditorState::new(Lines::from("abcd\nefgh\nijkl"));
+        state.set_clipboard(InternalClipboard::default());
+        let mut handler = KeyEventHandler::default();
+
+        let keys = [
+            KeyInput::new('l'),
+            KeyInput::ctrl('v'),
+            KeyInput::new('j'),
+            KeyInput::new('l'),
+            KeyInput::new('d'),
+        ];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("ad\neh\nijkl"));
+        assert_eq!(state.register('+'), Some(String::from("bc\nfg")));
+        assert_eq!(state.register_kind('+'), Some(RegisterKind::Blockwise));
+
+        // Pasting puts the block back column by column.
+        handler.on_event(KeyInput::shift('P'), &mut state);
+        assert_eq!(state.lines, Lines::from("abcd\nefgh\nijkl"));
+    }
+
+    #[test]
+    fn test_visual_block_insert_and_append() {
+        use crate::{EditorState, Lines};
+
+        let mut state = EditorState::new(Lines::from("ab\ncd\ne"));
+        let mut handler = KeyEventHandler::default();
+
+        let keys = [
+            KeyInput::ctrl('v'),
+            KeyInput::new('j'),
+            KeyInput::new('j'),
+            KeyInput::shift('I'),
+            KeyInput::new('-'),
+            KeyInput::new(KeyCode::Esc),
+        ];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("-ab\n-cd\n-e"));
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        // `A` pads rows that are too short.
+        let keys = [
+            KeyInput::new('l'),
+            KeyInput::new('l'),
+            KeyInput::ctrl('v'),
+            KeyInput::new('j'),
+            KeyInput::new('j'),
+            KeyInput::shift('A'),
+            KeyInput::new('|'),
+            KeyInput::new(KeyCode::Esc),
+        ];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("-ab|\n-cd|\n-e |"));
+    }
+
+    #[test]
+    fn test_visual_block_change() {
+        use crate::{EditorState, Lines};
+
+        let mut state = EditorState::new(Lines::from("a1b\na2b"));
+        let mut handler = KeyEventHandler::default();
+
+        let keys = [
+            KeyInput::new('l'),
+            KeyInput::ctrl('v'),
+            KeyInput::new('j'),
+            KeyInput::new('c'),
+            KeyInput::new('x'),
+            KeyInput::new('y'),
+            KeyInput::new(KeyCode::Esc),
+        ];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("axyb\naxyb"));
+    }
+
+    #[test]
+    fn test_visual_block_to_end_of_ragged_lines() {
+        use crate::clipboard::InternalClipboard;
+        use crate::{EditorState, Lines};
+
+        let text = "abc\nlonger line\nab";
+        let mut handler = KeyEventHandler::default();
+        let select = [
+            KeyInput::ctrl('v'),
+            KeyInput::new('j'),
+            KeyInput::new('j'),
+            KeyInput::new('$'),
+        ];
+
+        // `A` appends to the end of every row.
+        let mut state = EditorState::new(Lines::from(text));
+        type_keys(&mut handler, &mut state, &select);
+        let keys = [
+            KeyInput::shift('A'),
+            KeyInput::new('X'),
+            KeyInput::new(KeyCode::Esc),
+        ];
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("abcX\nlonger lineX\nabX"));
+
+        // `d` and `y` take every row up to its end.
+        let mut state = EditorState::new(Lines::from(text));
+        state.set_clipboard(InternalClipboard::default());
+        state.cursor.col = 1;
+        type_keys(&mut handler, &mut state, &select);
+        handler.on_event(KeyInput::new('y'), &mut state);
+        assert_eq!(state.register('+'), Some(String::from("bc\nonger line\nb")));
+
+        type_keys(&mut handler, &mut state, &select);
+        handler.on_event(KeyInput::new('d'), &mut state);
+        assert_eq!(state.lines, Lines::from("a\nl\na"));
+
+        // A horizontal motion after `$` shrinks the block again.
+        let mut state = EditorState::new(Lines::from(text));
+        let keys = [KeyInput::new('h'), KeyInput::new('d')];
+        type_keys(&mut handler, &mut state, &select);
+        type_keys(&mut handler, &mut state, &keys);
+        assert_eq!(state.lines, Lines::from("c\nnger line\n"));
+    }
+
+    #[test]
+    fn test_replace_mode() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("abc\nxyz"));
+        let mut handler = KeyEventHandler::default();
+        state.cursor = Index2::new(0, 1);
+
+        // Typed characters overwrite the text and extend the line at its end.
+        handler.on_event(KeyInput::shift('R'), &mut state);
+        assert_eq!(state.mode.name(), "Replace");
+        for c in "QWE".chars() {
+            handler.on_event(KeyInput::shift(c), &mut state);
+        }
+        assert_eq!(state.lines, Lines::from("aQWE\nxyz"));
+
+        // Backspace restores the original characters of the session.
+        let backspace = KeyInput::new(KeyCode::Backspace);
+        handler.on_event(backspace, &mut state);
+        handler.on_event(backspace, &mut state);
+        assert_eq!(state.lines, Lines::from("aQc\nxyz"));
+        handler.on_event(backspace, &mut state);
+        handler.on_event(backspace, &mut state);
+        assert_eq!(state.lines, Lines::from("abc\nxyz"));
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        // The replace session is a single undo step.
+        handler.on_event(KeyInput::new('x'), &mut state);
+        handler.on_event(KeyInput::new('y'), &mut state);
+        handler.on_event(KeyInput::new(KeyCode::Esc), &mut state);
+        assert_eq!(state.mode, EditorMode::Normal);
+        assert_eq!(state.lines, Lines::from("xyc\nxyz"));
+        handler.on_event(KeyInput::new('u'), &mut state);
+        assert_eq!(state.lines, Lines::from("abc\nxyz"));
+    }
+
+    #[test]
+    fn test_replace_chars() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("abc\nwxyz"));
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                handler.on_event(KeyInput::new(c), state);
+            }
+        };
+
+        // A count replaces that many characters, or none if the line is
+        // too short.
+        state.cursor = Index2::new(1, 0);
+        keys(&mut handler, &mut state, "3r-");
+        assert_eq!(state.lines, Lines::from("abc\n---z"));
+        assert_eq!(state.cursor, Index2::new(1, 2));
+        keys(&mut handler, &mut state, "3r+");
+        assert_eq!(state.lines, Lines::from("abc\n---z"));
+
+        // In visual mode every selected character is replaced.
+        state.cursor = Index2::new(0, 1);
+        keys(&mut handler, &mut state, "vjr*");
+        assert_eq!(state.lines, Lines::from("a**\n**-z"));
+        assert_eq!(state.mode, EditorMode::Normal);
+        assert_eq!(state.cursor, Index2::new(0, 1));
+    }
+
+    #[test]
+    fn test_case_and_numbers() {
+        use crate::{EditorState, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("hello world\nv1.9\nid 0\nid 0\nid 0"));
+        let mut handler = KeyEventHandler::default();
+
+        // `~` toggles the case and moves on, visual `U` uppercases.
+        type_keys(
+            &mut handler,
+            &mut state,
+            &[KeyInput::new('3'), KeyInput::new('~')],
+        );
+        assert_eq!(state.lines.to_string().lines().next(), Some("HELlo world"));
+        assert_eq!(state.cursor, Index2::new(0, 3));
+        let keys_v = [KeyInput::new('v'), KeyInput::new('$'), KeyInput::shift('U')];
+        type_keys(&mut handler, &mut state, &keys_v);
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        // `Ctrl+a` adds the count to the number after the cursor.
+        state.cursor = Index2::new(1, 2);
+        type_keys(
+            &mut handler,
+            &mut state,
+            &[KeyInput::new('2'), KeyInput::ctrl('a')],
+        );
+        assert_eq!(state.cursor, Index2::new(1, 4));
+        type_keys(&mut handler, &mut state, &[KeyInput::ctrl('x')]);
+
+        // `g Ctrl+a` turns the selected numbers into a sequence.
+        state.cursor = Index2::new(2, 0);
+        let keys_g = [
+            KeyInput::shift('V'),
+            KeyInput::new('j'),
+            KeyInput::new('j'),
+            KeyInput::new('g'),
+            KeyInput::ctrl('a'),
+        ];
+        type_keys(&mut handler, &mut state, &keys_g);
+        assert_eq!(
+            state.lines,
+            Lines::from("HELLO WORLD\nv1.10\nid 1\nid 2\nid 3")
+        );
+    }
+
+    #[test]
+    fn test_indentation() {
+        use crate::{EditorState, IndentOptions, Index2, Lines};
+
+        let mut state = EditorState::new(Lines::from("fn main() {}"));
+        state.set_indent_options(IndentOptions {
+            expand_tab: true,
+            shift_width: 4,
+            smart_indent: true,
+            ..Default::default()
+        });
+        let mut handler = KeyEventHandler::default();
+        let keys = |handler: &mut KeyEventHandler, state: &mut EditorState, keys: &str| {
+            for c in keys.chars() {
+                let key = match c {
+                    '\x1b' => KeyInput::new(KeyCode::Esc),
+                    '\n' => KeyInput::new(KeyCode::Enter),
+                    c if c.is_ascii_uppercase() => KeyInput::shift(c),
+                    c => KeyInput::new(c),
+                };
+                handler.on_event(key, state);
+            }
+        };
+
+        // Enter between brackets indents the new line and the closing
+        // bracket gets a line of its own.
+        keys(&mut handler, &mut state, "$i\nlet x = [\n1,\x1b");
+        assert_eq!(
+            state.lines,
+            Lines::from("fn main() {\n    let x = [\n        1,\n}")
+        );
+
+        // `o` continues the indentation, `=` re-indents lines.
+        keys(&mut handler, &mut state, "o2,\x1bo]\x1b");
+        assert_eq!(state.lines.to_string().lines().nth(4), Some("        ]"));
+        keys(&mut handler, &mut state, "gg=G");
+        assert_eq!(
+            state.lines,
+            Lines::from("fn main() {\n    let x = [\n        1,\n        2,\n    ]\n}")
+        );
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        // Visual `>` and `<` shift the selected lines.
+        keys(&mut handler, &mut state, "jVj>");
+        assert_eq!(
+            state.lines.to_string().lines().nth(2),
+            Some("            1,")
+        );
+        keys(&mut handler, &mut state, "Vj<");
+        assert_eq!(
+            state.lines.to_string().lines().nth(1),
+            Some("    let x = [")
+        );
+
+        // `.` shifts the same number of lines again.
+        keys(&mut handler, &mut state, "Vj>.");
+        let lines = state.lines.to_string();
+        let lines: Vec<&str> = lines.lines().collect();
+        assert_eq!(lines[1], "            let x = [");
+        assert_eq!(lines[2], "                1,");
+        assert_eq!(lines[3], "        2,");
+    }
 }
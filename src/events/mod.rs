@@ -10,7 +10,7 @@ pub use key::{input::KeyInput, KeyEventHandler, KeyEventRegister, KeyInputSequen
 #[cfg(feature = "mouse-support")]
 pub use mouse::{MouseEvent, MouseEventHandler};
 
-use crate::{events::paste::PasteEventHandler, EditorState};
+use crate::{events::paste::PasteEventHandler, state::changes::ViewChange, EditorState};
 use crossterm::event::Event as CTEvent;
 
 /// Handles key and mouse events.
@@ -76,12 +76,17 @@ impl EditorEventHandler {
     where
         T: Into<MouseEvent>,
     {
+        let view = ViewChange::new(state);
         MouseEventHandler::on_event(event.into(), state);
+        view.emit(state);
     }
 
     /// Handles paste events.
     pub fn on_paste_event(&self, text: String, state: &mut EditorState) {
+        let view = ViewChange::new(state);
         PasteEventHandler::on_event(text, state);
+        state.finish_undo_step();
+        view.emit(state);
     }
 }
 
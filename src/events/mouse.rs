@@ -49,14 +49,15 @@ impl MouseEventHandler {
         }
 
         if let MouseEvent::Down(_) = event {
+            state.push_jump();
             state.selection = None;
-            if state.mode == EditorMode::Visual {
+            if state.mode.is_visual() {
                 SwitchMode(EditorMode::Normal).execute(state);
             }
         }
 
         if let MouseEvent::Drag(_) = event {
-            if state.mode != EditorMode::Visual {
+            if !state.mode.is_visual() {
                 SwitchMode(EditorMode::Visual).execute(state);
             }
             set_selection(&mut state.selection, state.cursor);
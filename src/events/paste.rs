@@ -9,11 +9,18 @@ use crate::{
 pub(crate) struct PasteEventHandler {}
 impl PasteEventHandler {
     pub(crate) fn on_event(text: String, state: &mut EditorState) {
+        if state.read_only {
+            return;
+        }
         state.clip.set_text(text);
         match state.mode {
-            crate::EditorMode::Normal | crate::EditorMode::Insert => Paste.execute(state),
-            crate::EditorMode::Visual => PasteOverSelection.execute(state),
-            crate::EditorMode::Search => {} // TODO: Insert into search
+            crate::EditorMode::Normal | crate::EditorMode::Insert | crate::EditorMode::Replace => {
+                Paste(1).execute(state);
+            }
+            crate::EditorMode::Visual
+            | crate::EditorMode::VisualLine
+            | crate::EditorMode::VisualBlock => PasteOverSelection.execute(state),
+            crate::EditorMode::Search | crate::EditorMode::Command => {} // TODO: Insert into search
         }
     }
 }
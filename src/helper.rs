@@ -1,65 +1,156 @@
+use std::ops::Range;
+
 use jagged::index::RowIndex;
 use ratatui_core::{layout::Rect, text::Span};
 
+use crate::state::changes::{end_of, ChangeLog};
 use crate::{EditorMode, EditorState, Index2, Lines};
 
 /// Inserts a character into the lines data at the given `index`.
-pub(crate) fn insert_char(lines: &mut Lines, index: &mut Index2, ch: char, skip_move: bool) {
-    if lines.len() == index.row {
-        lines.push(Vec::new());
-    }
+pub(crate) fn insert_char(
+    lines: &mut Lines,
+    changes: &mut ChangeLog,
+    index: &mut Index2,
+    ch: char,
+    skip_move: bool,
+) {
     if ch == '\n' {
-        line_break(lines, index);
-    } else {
-        let Some(len_col) = lines.len_col(index.row) else {
-            return;
-        };
-        if index.col > len_col {
-            index.col = len_col.saturating_sub(1);
-        }
-        lines.insert(*index, ch);
-        if !skip_move {
-            index.col += 1;
-        }
+        line_break(lines, changes, index);
+        return;
+    }
+    if index.row > lines.len() {
+        return;
+    }
+    let len_col = lines.len_col(index.row).unwrap_or_default();
+    if index.col > len_col {
+        index.col = len_col.saturating_sub(1);
+    }
+    if changes.insert(lines, *index, &ch.to_string()) && !skip_move {
+        index.col += 1;
     }
 }
 
-/// Inserts a string into the lines data at the given `index`.
-pub(crate) fn insert_str(lines: &mut Lines, index: &mut Index2, text: &str) {
-    for (i, ch) in text.chars().enumerate() {
-        let is_last = i == text.len().saturating_sub(1);
-        insert_char(lines, index, ch, is_last);
+/// Inserts a string into the lines data at the given `index`. The index
+/// ends up on the last inserted character.
+pub(crate) fn insert_str(
+    lines: &mut Lines,
+    changes: &mut ChangeLog,
+    index: &mut Index2,
+    text: &str,
+) {
+    if text.is_empty() || index.row > lines.len() {
+        return;
+    }
+    let len_col = lines.len_col(index.row).unwrap_or_default();
+    if index.col > len_col {
+        index.col = len_col.saturating_sub(1);
+    }
+    if changes.insert(lines, *index, text) {
+        *index = end_of(*index, text);
+        if !text.ends_with('\n') {
+            index.col = index.col.saturating_sub(1);
+        }
     }
 }
 
 /// Appends a string into the lines data next to a given `index`.
-pub(crate) fn append_str(lines: &mut Lines, index: &mut Index2, text: &str) {
+pub(crate) fn append_str(
+    lines: &mut Lines,
+    changes: &mut ChangeLog,
+    index: &mut Index2,
+    text: &str,
+) {
+    if index.row > lines.len() {
+        return;
+    }
+    let mut pos = *index;
     if !lines.is_empty() && lines.len_col(index.row).unwrap_or_default() > 0 {
-        index.col += 1;
+        pos.col += 1;
     }
-    for ch in text.chars() {
-        insert_char(lines, index, ch, false);
+    if changes.insert(lines, pos, text) {
+        *index = end_of(pos, text);
+        index.col = index.col.saturating_sub(1);
     }
-    index.col = index.col.saturating_sub(1);
 }
 
 /// Inserts a line break at a given index. Forces a splitting of lines if
 /// the index is in the middle of a line.
-pub(crate) fn line_break(lines: &mut Lines, index: &mut Index2) {
-    if index.col == 0 {
-        lines.insert(RowIndex::new(index.row), vec![]);
-    } else if index.col >= lines.len_col(index.row).unwrap_or_default() {
-        if index.row == lines.len() {
-            lines.insert(RowIndex::new(index.row), vec![]);
-        } else {
-            lines.insert(RowIndex::new(index.row + 1), vec![]);
+pub(crate) fn line_break(lines: &mut Lines, changes: &mut ChangeLog, index: &mut Index2) {
+    if index.row > lines.len() {
+        return;
+    }
+    let len_col = lines.len_col(index.row).unwrap_or_default();
+    let pos = Index2::new(index.row, index.col.min(len_col));
+    // In the row after the last one, the line break is the new row itself.
+    let text = if index.row == lines.len() { "" } else { "\n" };
+    if changes.insert(lines, pos, text) {
+        index.row += 1;
+        index.col = 0;
+    }
+}
+
+/// Replaces the characters in `cols` of a row by `f` of them. Only the span
+/// from the first to the last changed character is replaced, so marks on
+/// unchanged text stay where they are.
+pub(crate) fn map_chars(
+    lines: &mut Lines,
+    changes: &mut ChangeLog,
+    row: usize,
+    cols: Range<usize>,
+    f: impl Fn(char) -> char,
+) {
+    let Some(line) = lines.get(RowIndex::new(row)) else {
+        return;
+    };
+    let cols = cols.start.min(line.len())..cols.end.min(line.len());
+    let changed = |col: &usize| f(line[*col]) != line[*col];
+    let Some(first) = cols.clone().find(changed) else {
+        return;
+    };
+    let last = cols.rev().find(changed).unwrap_or(first);
+    let text: String = line[first..=last].iter().map(|ch| f(*ch)).collect();
+    let start = Index2::new(row, first);
+    let end = Index2::new(row, last + 1);
+    let _ = changes.replace(lines, start, end, &text);
+}
+
+/// Returns the text between `start` (inclusive) and `end` (exclusive). A
+/// column equal to the length of its row addresses the line break after it.
+pub(crate) fn text_in_range(lines: &Lines, start: Index2, end: Index2) -> String {
+    let mut text = String::new();
+    for row in start.row..=end.row {
+        let Some(line) = lines.get(RowIndex::new(row)) else {
+            break;
+        };
+        if row > start.row {
+            text.push('\n');
         }
-    } else {
-        let mut rest = lines.split_off(*index);
-        lines.append(&mut rest);
+        let from = if row == start.row { start.col } else { 0 };
+        let to = if row == end.row { end.col } else { line.len() };
+        let to = to.min(line.len());
+        text.extend(&line[from.min(to)..to]);
     }
-    index.row += 1;
-    index.col = 0;
+    text
+}
+
+/// Removes the text between `start` (inclusive) and `end` (exclusive) and
+/// returns it. See [`text_in_range`] for how columns are interpreted.
+pub(crate) fn remove_range(
+    lines: &mut Lines,
+    changes: &mut ChangeLog,
+    start: Index2,
+    end: Index2,
+) -> String {
+    let text = text_in_range(lines, start, end);
+    let _ = changes.replace(lines, start, end, "");
+    text
+}
+
+/// Returns the number of leading whitespace characters in a row.
+pub(crate) fn indentation(lines: &Lines, row: usize) -> usize {
+    lines.get(RowIndex::new(row)).map_or(0, |line| {
+        line.iter().take_while(|ch| ch.is_whitespace()).count()
+    })
 }
 
 /// Returns the maximum permissible column value. In normal mode
@@ -301,7 +392,7 @@ mod tests {
         let mut lines = test_lines();
         let mut index = Index2::new(0, 5);
 
-        insert_str(&mut lines, &mut index, ",\n");
+        insert_str(&mut lines, &mut ChangeLog::default(), &mut index, ",\n");
         assert_eq!(index, Index2::new(1, 0));
         assert_eq!(lines, Lines::from("Hello,\n World!\n\n123."));
     }
@@ -311,7 +402,13 @@ mod tests {
         let mut lines = test_lines();
         let mut index = Index2::new(0, 5);
 
-        insert_char(&mut lines, &mut index, '?', false);
+        insert_char(
+            &mut lines,
+            &mut ChangeLog::default(),
+            &mut index,
+            '?',
+            false,
+        );
         assert_eq!(index, Index2::new(0, 6));
         assert_eq!(lines, Lines::from("Hello? World!\n\n123."));
     }
@@ -321,7 +418,13 @@ mod tests {
         let mut lines = test_lines();
         let mut index = Index2::new(99, 0);
 
-        insert_char(&mut lines, &mut index, '?', false);
+        insert_char(
+            &mut lines,
+            &mut ChangeLog::default(),
+            &mut index,
+            '?',
+            false,
+        );
         assert_eq!(index, Index2::new(99, 0));
         assert_eq!(lines, Lines::from("Hello World!\n\n123."));
     }
@@ -331,13 +434,13 @@ mod tests {
         let mut lines = test_lines();
         let mut index = Index2::new(0, 5);
 
-        append_str(&mut lines, &mut index, ",\n");
+        append_str(&mut lines, &mut ChangeLog::default(), &mut index, ",\n");
         assert_eq!(index, Index2::new(1, 0));
         assert_eq!(lines, Lines::from("Hello ,\nWorld!\n\n123."));
 
         let mut lines = test_lines();
         let mut index = Index2::new(1, 0);
-        append_str(&mut lines, &mut index, "abc");
+        append_str(&mut lines, &mut ChangeLog::default(), &mut index, "abc");
         assert_eq!(index, Index2::new(1, 2));
         assert_eq!(lines, Lines::from("Hello World!\nabc\n123."));
     }
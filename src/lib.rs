@@ -58,6 +58,7 @@
 //! | `.theme(EditorTheme)`                            | Sets the editor theme (see [Theming](#theming)).             |
 //! | `.wrap(bool)`                                    | Enables line wrapping.                                       |
 //! | `.tab_width(usize)`                              | Number of spaces used to render a tab.                       |
+//! | `.indent_options(IndentOptions)`                 | Tabs or spaces, shift width and auto-indent.                 |
 //! | `.line_numbers(LineNumbers)`                     | Shows absolute or relative line numbers.                     |
 //! | `.single_line(bool)`                             | Restricts the editor to a single line.                       |
 //! | `.syntax_highlighter(Option<SyntaxHighlighter>)` | Enables syntax highlighting (`syntax-highlighting` feature). |
@@ -74,7 +75,10 @@
 //! - Line wrapping.
 //! - Syntax highlighting.
 //! - Line numbers (absolute and relative).
+//! - Indentation with tabs or spaces, auto-indent and smart indent.
+//! - Change events and a modified flag for autosave and dirty markers.
 //! - System editor support (optional, via `system-editor` feature).
+//! - Saving the undo history and macros across sessions (optional, via `serde` feature).
 //!
 //! ## Theming
 //!
@@ -97,7 +101,10 @@
 //! | `.cursor_style(Style)`           | Cursor style.                    |
 //! | `.hide_cursor()`                 | Hides the cursor.                |
 //! | `.selection_style(Style)`        | Style of the selected text.      |
+//! | `.search_match_style(Style)`     | Style of the search matches.     |
+//! | `.current_match_style(Style)`    | Style of the selected match.     |
 //! | `.line_numbers_style(Style)`     | Style of the line numbers.       |
+//! | `.marks_style(Style)`            | Style of the marks.              |
 //! | `.status_line(EditorStatusLine)` | Sets and styles the status line. |
 //! | `.hide_status_line()`            | Hides the status line.           |
 //!
@@ -132,6 +139,96 @@
 //!
 //! When enabled, newline insertion is blocked and pasting text with newlines will replace them with spaces.
 //!
+//! ## Read-Only Mode
+//!
+//! For log and diff viewers, enable read-only mode to refuse every change of the text:
+//!
+//! ```
+//! use edtui::{EditorState, Lines};
+//!
+//! let mut state = EditorState::new(Lines::from("read me"));
+//! state.set_read_only(true);
+//! ```
+//!
+//! When enabled, edits, pastes and commands like `:s` are refused, while navigation, search, selection and copy keep working. The status line shows `[RO]`.
+//!
+//! ## Protected Ranges
+//!
+//! For form-like editors, protect ranges of text that must stay as they are, like a fixed header:
+//!
+//! ```
+//! use edtui::{EditorState, Index2, Lines, ProtectedRange};
+//!
+//! let mut state = EditorState::new(Lines::from("Subject: \n\nBody"));
+//! state.add_protected_range(ProtectedRange::new(Index2::new(0, 0), Index2::new(0, 7)));
+//! ```
+//!
+//! Inserts, deletes, pastes and replaces that would touch a protected range are rejected. Protected ranges move with the edits before them.
+//!
+//! ## Extmarks
+//!
+//! For diagnostics, search results or other overlays that must stay on the same text while it is edited, add an extmark and attach a highlight to it:
+//!
+//! ```ignore
+//! use edtui::{EditorState, Extmark, Highlight, Index2, Lines};
+//! use ratatui::style::{Color, Style};
+//!
+//! let mut state = EditorState::new(Lines::from("let x = 1;"));
+//! let id = state.add_extmark(Extmark::new(Index2::new(0, 4), Index2::new(0, 5)));
+//! state.add_highlight(Highlight::at_extmark(id, Style::default().bg(Color::Red)));
+//! ```
+//!
+//! Extmarks move with every edit, including undo and redo. Their gravity decides whether text inserted right at an end goes inside or outside of the range.
+//!
+//! ## Marks and Jump List
+//!
+//! Marks remember positions to jump back to. They are set with `m` and a letter, or from code, and move along with the edits:
+//!
+//! ```ignore
+//! use edtui::{EditorState, EditorView, Index2, Lines};
+//!
+//! let mut state = EditorState::new(Lines::from("fn main() {}"));
+//! state.set_mark('a', Index2::new(0, 3));
+//! EditorView::new(&mut state).show_marks(true);
+//! ```
+//!
+//! `show_marks` displays the marks in a gutter column. Long-distance motions like `G`, `%`, `{`, `}`, a search hit or a mouse click add the position they leave to the jump list, which is walked with `ctrl+o` and `ctrl+i`.
+//!
+//! ## Macros
+//!
+//! Macros are recorded with `q` and a register name, stopped with `q` and replayed with `@` and the name, `@@` replays the last one. A count replays a macro several times (`3@a`). They can be saved and loaded as `KeyInputSequence`s, which are serializable with the `serde` feature:
+//!
+//! ```ignore
+//! use edtui::events::{KeyInput, KeyInputSequence};
+//! use edtui::{EditorState, Lines};
+//!
+//! let mut state = EditorState::new(Lines::from("Hello"));
+//! state.set_macro_register('a', KeyInputSequence::new(vec![KeyInput::new('x')]));
+//! let saved: Vec<(char, KeyInputSequence)> = state
+//!     .macro_registers()
+//!     .map(|(name, keys)| (name, keys.clone()))
+//!     .collect();
+//! ```
+//!
+//! ## Indentation
+//!
+//! `>>`, `<<` and `=` shift or re-indent lines by one shift width, written with tabs or, with `expand_tab`, spaces. `auto_indent` starts a new line with the indentation of the line above, `smart_indent` indents one level more after `{`, `(`, `[` or `:`:
+//!
+//! ```ignore
+//! use edtui::{EditorState, EditorView, IndentOptions, Lines};
+//!
+//! let mut state = EditorState::new(Lines::from("key:"));
+//! let options = IndentOptions {
+//!     expand_tab: true,
+//!     shift_width: 2,
+//!     smart_indent: true,
+//!     ..Default::default()
+//! };
+//! EditorView::new(&mut state).indent_options(options);
+//! ```
+//!
+//! The options are also set with `:set sw=2 et si`.
+//!
 //! ## Mouse Events
 //!
 //! `Edtui` supports mouse input for moving the cursor and selecting text.
@@ -217,27 +314,41 @@
 //! | Keybinding                | Description                                              |
 //! |---------------------------|----------------------------------------------------------|
 //! | `i`                       | Enter Insert mode                                        |
+//! | `R`                       | Enter Replace mode, where typed characters overwrite     |
 //! | `v`                       | Enter Visual mode                                        |
+//! | `V`                       | Enter Visual Line mode                                   |
+//! | `ctrl+v`                  | Enter Visual Block mode                                  |
+//! | `I`, `A`                  | Insert/append on every row of the block (Visual Block)   |
 //! | `h`, `j`, `k`, `l`        | Navigate left, down, up, and right                       |
 //! | `w`                       | Move forward to the start of a word                      |
 //! | `e`                       | Move forward to the end of a word                        |
 //! | `b`                       | Move backward to the start of a word                     |
+//! | `W`, `E`, `B`             | Like `w`, `e`, `b` for WORDs (whitespace-delimited)      |
 //! | `f` + `<char>`            | Move to the next occurrence of `<char>` on the line      |
 //! | `t` + `<char>`            | Move just before the next occurrence of `<char>`         |
+//! | `F`, `T` + `<char>`       | Like `f`, `t`, but search backward on the line           |
+//! | `;`, `,`                  | Repeat the last `f`/`F`/`t`/`T`, forward/reversed        |
 //! | `ctrl+d`                  | Jump a half page down                                    |
 //! | `ctrl+u`                  | Jump a half page up                                      |
 //! | `PageDown`                | Jump a full page down                                    |
 //! | `PageUp`                  | Jump a full page up                                      |
 //! | `x`                       | Delete the character under the cursor                    |
+//! | `r` + `<char>`            | Replace `<n>` characters or the selection with `<char>`  |
 //! | `u`, `ctrl+r`             | Undo/Redo last action                                    |
+//! | `g-`, `g+`                | Go to older/newer text state, across undo branches       |
 //! | `Esc`                     | Escape Visual mode                                       |
 //! | `0`                       | Move cursor to start of line                             |
 //! | `_`                       | Move cursor to first non-blank character                 |
 //! | `$`                       | Move cursor to end of line                               |
 //! | `gg`                      | Move cursor to the first row                             |
 //! | `G `                      | Move cursor to the last row                              |
+//! | `<n>G`, `<n>gg`           | Move cursor to line `<n>`                                |
 //! | `%`                       | Move cursor to closing/opening bracket                   |
 //! | `{,}`                     | Move cursor to next/previous paragraph                   |
+//! | `m` + `<a-z>`             | Set a mark at the cursor                                 |
+//! | `` ` ``, `'` + `<a-z>`    | Jump to the mark/the first non-blank in its row          |
+//! | ``` `` ```, `''`          | Jump back to the position before the latest jump         |
+//! | `ctrl+o`, `ctrl+i`        | Go to the older/newer position in the jump list          |
 //! | `a`                       | Append after the cursor                                  |
 //! | `A`                       | Append at the end of the line                            |
 //! | `o`                       | Add a new line below and enter Insert mode               |
@@ -251,6 +362,15 @@
 //! | `diW`                     | Delete inner WORD (whitespace-delimited)                 |
 //! | `df` + `<char>`           | Delete up to and including the next `<char>` on the line |
 //! | `dt` + `<char>`           | Delete up to (but not including) the next `<char>`       |
+//! | `d`, `c`, `y` + motion    | Delete/change/copy over a motion or text object (`d}`)   |
+//! | `>`, `<` + motion         | Indent/dedent the lines covered by a motion (`>>`)       |
+//! | `=` + motion              | Re-indent the lines covered by a motion (`==`, `=G`)     |
+//! | `>`, `<`, `=`             | Indent/dedent/re-indent the selection (Visual mode)      |
+//! | `gu`, `gU`, `g~` + motion | Lower/upper/toggle case over a motion (`gUiw`, `guu`)    |
+//! | `~`                       | Toggle the case of `<n>` characters and move right       |
+//! | `u`, `U`, `~`             | Lower/upper/toggle case of the selection (Visual mode)   |
+//! | `ctrl+a`, `ctrl+x`        | Add/subtract `<n>` to the number at or after the cursor  |
+//! | `g ctrl+a`, `g ctrl+x`    | Count up/down the selected numbers (Visual mode)         |
 //! | `cw`                      | Change to the end of the word                            |
 //! | `cW`                      | Change to the end of the WORD (whitespace-delimited)     |
 //! | `cf` + `<char>`           | Change up to and including the next `<char>` on the line |
@@ -262,16 +382,35 @@
 //! | `vi` + `", ', (, [ or {`  | Select between delimiter `", ', (, [ or {`               |
 //! | `di` + `", ', (, [ or {`  | Delete between delimiter `", ', (, [ or {`               |
 //! | `ci` + `", ', (, [ or {`  | Change between delimiter `", ', (, [ or {`               |
+//! | `vaw`, `vaW`              | Select a word/WORD with its surrounding whitespace       |
+//! | `vi`, `va` + delimiter    | Select inside/around quotes, backticks or brackets       |
+//! | `vip`, `vap`              | Select inner/around paragraph                            |
+//! | `vis`, `vas`              | Select inner/around sentence                             |
+//! | `vit`, `vat`              | Select inner/around XML/HTML tag block                   |
+//! | `d`, `c`, `y` + object    | Delete/change/copy a text object (`daw`, `cit`, `yap`)   |
 //! | `.`                       | Repeat the last change                                   |
+//! | `q` + `<a-z>`, `q`        | Record a macro into a register, stop the recording       |
+//! | `@` + `<a-z>`, `@@`       | Replay a macro, or the last played one                   |
+//! | `<n>` + command           | Repeat a motion or operator `<n>` times (`3w`, `5dd`)    |
+//! | `"` + `<reg>` + command   | Use register `<reg>` for the next yank, delete or paste  |
 //! | `u`                       | Undo the last change                                     |
 //! | `r`                       | Redo the last undone action                              |
 //! | `y`                       | Copy the selected text in visual mode                    |
 //! | `yy`                      | Copy the current line in normal mode                     |
+//! | `Y`                       | Copy the current line in normal mode                     |
 //! | `p`                       | Paste the copied text after the cursor                   |
 //! | `P`                       | Paste the copied text before the cursor                  |
 //! | `Home`                    | Move cursor to start of line                             |
 //! | `End`                     | Move cursor to end of line                               |
 //! | `ctrl+e`                  | Open in system editor (requires `system-editor` feature) |
+//! | `:`                       | Enter Command mode (`:42`, `:%s/a/b/g`, `:w`)            |
+//! | `/`                       | Search forward, `Enter` to jump to the match             |
+//! | `?`                       | Search backward                                          |
+//! | `*`, `#`                  | Search forward/backward for the word under the cursor    |
+//! | `n`, `N`                  | Go to the next match in/against the search direction     |
+//! | `Alt+r`, `Alt+c`          | Search mode: Toggle regex/ignore case                    |
+//! | `Alt+s`, `Alt+w`          | Search mode: Toggle smart case/whole word                |
+//! | `Up`, `Down`              | Search mode: Recall older/newer searches                 |
 //!
 //! #### Insert Mode:
 //!
@@ -290,6 +429,27 @@
 //! | `PageUp`    | Jump a full page up                     |
 //! | `ctrl+u`    | Delete until first character            |
 //!
+//! #### Command Mode:
+//!
+//! Press `:` in Normal mode to type a command line. See the `command` module for
+//! registering custom commands.
+//!
+//! | Keybinding / Command          | Description                                       |
+//! |-------------------------------|---------------------------------------------------|
+//! | `Enter`                       | Run the command line                              |
+//! | `Tab`                         | Complete the command name or option               |
+//! | `Esc`                         | Return to Normal mode                             |
+//! | `:<n>`                        | Go to line `<n>`                                  |
+//! | `:[range]s/<pat>/<rep>/[gic]` | Replace text, in all rows with `:%s`              |
+//! | `y`, `n`, `a`, `q`            | Confirm, skip, replace all or quit after `:s///c` |
+//! | `:set <option>`               | Set `wrap`, `nu`, `rnu`, `ts=<n>`, `ic`, `scs`    |
+//! | `:set sw=<n>`                 | Set the width of one level of indentation         |
+//! | `:set et`, `ai`, `si`         | Indent with spaces, auto-indent or smart indent   |
+//! | `:u [n]`, `:red`              | Undo, redo or go to the text after change `<n>`   |
+//! | `:earlier <n>[smhd]`          | Go back `<n>` changes or seconds, minutes, ...    |
+//! | `:later <n>[smhd]`            | Go forward `<n>` changes or in time               |
+//! | `:w`, `:q`, `:wq`             | Ask the host app to save or quit                  |
+//!
 //! ### Emacs Mode
 //!
 //! Emacs Mode was added in version 0.10.1.
@@ -336,6 +496,8 @@
 //! | `Ctrl+s`        | Search mode: Go to next match                            |
 //! | `Ctrl+r`        | Search mode: Go to previous match                        |
 //! | `Enter`         | Search mode: Select current match                        |
+//! | `Alt+r/c/s/w`   | Search mode: Toggle regex/ignore/smart case/whole word   |
+//! | `Up`, `Down`    | Search mode: Recall older/newer searches                 |
 //!
 //! [Crate Badge]: https://img.shields.io/crates/v/edtui?logo=rust&style=flat-square&logoColor=E05D44&color=E05D44
 //! [License Badge]: https://img.shields.io/crates/l/edtui?style=flat-square&color=1370D3
@@ -346,6 +508,7 @@
 )]
 pub mod actions;
 pub mod clipboard;
+pub mod command;
 mod debug;
 pub mod events;
 #[cfg(feature = "system-editor")]
@@ -355,9 +518,23 @@ mod state;
 mod view;
 
 pub use events::EditorEventHandler;
-pub use state::{highlight::Highlight, mode::EditorMode, EditorState};
+pub use state::{
+    changes::ChangeEvent,
+    extmark::{Extmark, ExtmarkId, Gravity},
+    highlight::Highlight,
+    indent::IndentOptions,
+    mode::EditorMode,
+    protected::ProtectedRange,
+    replace::{ReplaceChoice, ReplaceFlags},
+    search::{SearchOption, SearchOptions},
+    undo::UndoEntry,
+    EditorState,
+};
 pub use view::{theme::EditorTheme, EditorStatusLine, EditorView, LineNumbers};
 
+#[cfg(feature = "serde")]
+pub use state::undo::{UndoRestoreError, UndoSnapshot};
+
 #[cfg(feature = "syntax-highlighting")]
 pub use view::syntax_higlighting::{
     SyntaxHighlighter, SyntaxHighlighterError, SYNTAX_SET, THEME_SET,
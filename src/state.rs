@@ -1,17 +1,32 @@
 //! The editors state
+pub mod changes;
+mod command;
+pub mod extmark;
 pub mod highlight;
+pub mod indent;
+pub(crate) mod macros;
+mod marks;
 pub mod mode;
-mod search;
+pub mod protected;
+pub mod replace;
+pub mod search;
 pub mod selection;
-mod undo;
+pub mod undo;
 mod view;
 
+use self::changes::{ChangeLog, ViewChange};
+use self::command::CommandState;
 use self::highlight::Highlight;
-use self::search::SearchState;
+use self::indent::IndentOptions;
+use self::macros::Macros;
+use self::protected::ProtectedRange;
+use self::replace::ReplaceState;
+use self::search::{SearchOptions, SearchState};
 use self::view::ViewState;
-use self::{mode::EditorMode, selection::Selection, undo::Stack};
-use crate::actions::{Action, Execute};
-use crate::clipboard::{Clipboard, ClipboardTrait};
+use self::{mode::EditorMode, selection::Selection, undo::UndoHistory};
+use crate::actions::{insert::BlockInsert, motion::CharSearch, Action, Execute, SwitchMode};
+use crate::clipboard::{Clipboard, ClipboardTrait, RegisterKind, Registers};
+use crate::command::{Command, CommandError, CommandEvent, CommandRegistry};
 use crate::helper::max_col;
 use crate::{Index2, Lines};
 use ratatui_core::layout::Position;
@@ -40,14 +55,26 @@ pub struct EditorState {
     /// State holding the search results in search mode.
     pub(crate) search: SearchState,
 
-    /// Stack for undo operations.
-    pub(crate) undo: Stack,
+    /// State of a replace waiting for the confirmation of each match.
+    pub(crate) replace: ReplaceState,
 
-    /// Stack for redo operations.
-    pub(crate) redo: Stack,
+    /// State holding the command line in command mode.
+    pub(crate) command: CommandState,
 
-    /// Clipboard for yank and paste operations.
-    pub(crate) clip: Clipboard,
+    /// The commands that can be run in command mode.
+    pub(crate) commands: CommandRegistry,
+
+    /// Refuses every action that changes the text.
+    pub(crate) read_only: bool,
+
+    /// The recorded edits for undo and redo.
+    pub(crate) history: UndoHistory,
+
+    /// The change events and the revision of the text.
+    pub(crate) changes: ChangeLog,
+
+    /// Registers for yank and paste operations, backed by the clipboard.
+    pub(crate) clip: Registers,
 
     /// The last buffer-changing command thath can be replayed by dot-repeat.
     pub(crate) last_change: Option<Action>,
@@ -58,6 +85,22 @@ pub struct EditorState {
     /// Text typed in the current insert session.
     pub(crate) insert_recording: Option<String>,
 
+    /// Text typed in the first row of a visual block, for `I` and `A`.
+    pub(crate) block_insert: Option<BlockInsert>,
+
+    /// The characters overwritten in the current replace session, restored
+    /// by backspace. `None` for a character appended to the end of a line.
+    pub(crate) replaced: Vec<(Index2, Option<char>)>,
+
+    /// The last character search, repeated by `;` and `,`.
+    pub(crate) last_find: Option<CharSearch>,
+
+    /// The macro registers and the recording of a macro.
+    pub(crate) macros: Macros,
+
+    /// The options of how lines are indented.
+    pub(crate) indent: IndentOptions,
+
     /// Flag indicating a system editor was requested.
     #[cfg(feature = "system-editor")]
     pub(crate) system_edit_requested: bool,
@@ -83,6 +126,9 @@ impl EditorState {
     #[must_use]
     pub fn new(lines: Lines) -> EditorState {
         EditorState {
+            read_only: false,
+            history: UndoHistory::new(),
+            changes: ChangeLog::default(),
             lines,
             cursor: Index2::new(0, 0),
             mode: EditorMode::Normal,
@@ -90,12 +136,18 @@ impl EditorState {
             highlights: Vec::new(),
             view: ViewState::default(),
             search: SearchState::default(),
-            undo: Stack::new(),
-            redo: Stack::new(),
-            clip: Clipboard::default(),
+            replace: ReplaceState::default(),
+            command: CommandState::default(),
+            commands: CommandRegistry::default(),
+            clip: Registers::default(),
             last_change: None,
             last_insert: None,
             insert_recording: None,
+            block_insert: None,
+            replaced: Vec::new(),
+            last_find: None,
+            macros: Macros::default(),
+            indent: IndentOptions::default(),
             #[cfg(feature = "system-editor")]
             system_edit_requested: false,
         }
@@ -112,11 +164,20 @@ impl EditorState {
     /// state.execute(DeleteLine(1))
     /// ```
     pub fn execute(&mut self, mut action: impl Execute) {
+        if self.refuses(&action) {
+            return;
+        }
+        let view = ViewChange::new(self);
         action.execute(self);
+        self.finish_undo_step();
+        view.emit(self);
     }
 
     /// Executes an action, recording it for the dot-repeat command.
     pub(crate) fn execute_recorded(&mut self, mut action: Action) {
+        if self.refuses(&action) {
+            return;
+        }
         let mode_before = self.mode;
         action.execute(self);
 
@@ -139,7 +200,79 @@ impl EditorState {
 
     /// Set a custom clipboard.
     pub fn set_clipboard(&mut self, clipboard: impl ClipboardTrait + 'static) {
-        self.clip = Clipboard::new(clipboard);
+        self.clip.set_clipboard(Clipboard::new(clipboard));
+    }
+
+    /// Returns the contents of a register, or `None` if it is empty.
+    ///
+    /// Registers are named like in Vim: `a` to `z` for named registers, `0`
+    /// to `9` for the yank and delete history, `-` for small deletes and
+    /// `+`, `*` or `"` for the clipboard.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::clipboard::RegisterKind;
+    /// use edtui::{EditorState, Lines};
+    ///
+    /// let mut state = EditorState::new(Lines::from("Hello"));
+    /// state.set_register('a', "World", RegisterKind::Linewise);
+    /// assert_eq!(state.register('a'), Some(String::from("World")));
+    /// assert_eq!(state.register_kind('a'), Some(RegisterKind::Linewise));
+    /// ```
+    pub fn register(&mut self, name: char) -> Option<String> {
+        self.clip.get(name).map(|(text, _)| text)
+    }
+
+    /// Returns how the contents of a register are pasted, or `None` if it is
+    /// empty.
+    pub fn register_kind(&mut self, name: char) -> Option<RegisterKind> {
+        self.clip.get(name).map(|(_, kind)| kind)
+    }
+
+    /// Writes text to a register. Writing to `A` to `Z` appends to the named
+    /// register, writing to `+`, `*` or `"` sets the clipboard.
+    pub fn set_register(&mut self, name: char, text: impl Into<String>, kind: RegisterKind) {
+        self.clip.set(name, text.into(), kind);
+    }
+
+    /// Registers a command that can be run from command mode (`:name`),
+    /// replacing any command with the same name, including built-in ones.
+    ///
+    /// See the [`command`](crate::command) module for an example.
+    pub fn register_command(&mut self, name: impl Into<String>, command: impl Command + 'static) {
+        self.commands
+            .register(name.into(), std::rc::Rc::new(command));
+    }
+
+    /// Runs a command line as if it was typed in command mode, e.g.
+    /// `"%s/foo/bar/g"` or `"42"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is unknown, its arguments are invalid
+    /// or it fails.
+    pub fn execute_command(&mut self, input: &str) -> Result<(), CommandError> {
+        crate::command::execute(self, input)
+    }
+
+    /// Returns the requests of commands to the host app since the last call,
+    /// such as saving after `:w` or quitting after `:q`.
+    pub fn take_command_events(&mut self) -> Vec<CommandEvent> {
+        std::mem::take(&mut self.command.events)
+    }
+
+    /// Returns the command line typed in command mode.
+    #[must_use]
+    pub fn command_line(&self) -> String {
+        self.command.input.clone()
+    }
+
+    /// Returns the error of the last command run from command mode, if it
+    /// failed. Cleared when the next key is handled.
+    #[must_use]
+    pub fn command_error(&self) -> Option<&CommandError> {
+        self.command.error.as_ref()
     }
 
     /// Returns the current search pattern.
@@ -148,6 +281,27 @@ impl EditorState {
         self.search.pattern.clone()
     }
 
+    /// Returns the position of the selected search match, starting at 1, and
+    /// the number of matches, or `None` if there is no search pattern.
+    /// The status line shows it as `[3/17]` in search mode.
+    #[must_use]
+    pub fn search_counter(&self) -> Option<(usize, usize)> {
+        self.search.counter()
+    }
+
+    /// Returns the options of how search patterns are matched.
+    #[must_use]
+    pub fn search_options(&self) -> SearchOptions {
+        self.search.options
+    }
+
+    /// Sets the options of how search patterns are matched, e.g. to
+    /// enable regular expressions.
+    pub fn set_search_options(&mut self, options: SearchOptions) {
+        self.search.options = options;
+        self.search.trigger_search(&self.lines);
+    }
+
     /// Clamps the column of the cursor if the cursor is out of bounds.
     /// In normal or visual mode, clamps on `col = len() - 1`, in insert
     /// mode on `col = len()`.
@@ -193,6 +347,47 @@ impl EditorState {
         self.view.single_line
     }
 
+    /// Sets the editor to read-only mode.
+    ///
+    /// When enabled, every action that changes the text is refused, including
+    /// pastes and commands like `:s`. Navigation, search, selection and copy
+    /// keep working. The text can still be changed by assigning
+    /// [`lines`](Self::lines). Enabling it cancels a replace that waits for
+    /// confirmation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Lines};
+    /// use edtui::actions::DeleteLine;
+    ///
+    /// let mut state = EditorState::new(Lines::from("Hello"));
+    /// state.set_read_only(true);
+    /// state.execute(DeleteLine(1));
+    /// assert_eq!(state.lines, Lines::from("Hello"));
+    /// ```
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+        if read_only && matches!(self.mode, EditorMode::Insert | EditorMode::Replace) {
+            SwitchMode(EditorMode::Normal).execute(self);
+        }
+        if read_only && self.is_replacing() {
+            self.stop_replace();
+        }
+    }
+
+    /// Returns whether read-only mode is enabled.
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Returns whether an action is refused because it would change the text
+    /// of a read-only editor.
+    pub(crate) fn refuses(&self, action: &impl Execute) -> bool {
+        self.read_only && action.is_edit()
+    }
+
     /// Add a custom highlight range.
     pub fn add_highlight(&mut self, highlight: Highlight) {
         self.highlights.push(highlight);
@@ -208,6 +403,45 @@ impl EditorState {
         self.highlights = highlights;
     }
 
+    /// Add a range of text that can not be edited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Index2, Lines, ProtectedRange};
+    /// use edtui::actions::{DeleteLine, InsertChar};
+    ///
+    /// let mut state = EditorState::new(Lines::from("Subject:\n"));
+    /// state.add_protected_range(ProtectedRange::new(Index2::new(0, 0), Index2::new(0, 8)));
+    ///
+    /// state.execute(DeleteLine(1));
+    /// assert_eq!(state.lines, Lines::from("Subject:\n"));
+    ///
+    /// state.cursor = Index2::new(1, 0);
+    /// state.execute(InsertChar('x'));
+    /// assert_eq!(state.lines, Lines::from("Subject:\nx"));
+    /// ```
+    pub fn add_protected_range(&mut self, range: ProtectedRange) {
+        self.changes.protected.push(range);
+    }
+
+    /// Clear all protected ranges.
+    pub fn clear_protected_ranges(&mut self) {
+        self.changes.protected.clear();
+    }
+
+    /// Set all protected ranges, replacing any existing ones.
+    pub fn set_protected_ranges(&mut self, ranges: Vec<ProtectedRange>) {
+        self.changes.protected = ranges;
+    }
+
+    /// Returns the protected ranges, moved along with the edits since they
+    /// were added.
+    #[must_use]
+    pub fn protected_ranges(&self) -> &[ProtectedRange] {
+        &self.changes.protected
+    }
+
     /// Returns the current viewport offset as (x, y).
     ///
     /// The viewport offset represents the top-left corner of the visible area
@@ -298,6 +532,40 @@ mod tests {
         assert_eq!(pos.y, 2);
     }
 
+    #[test]
+    fn test_search_matches_render() {
+        use crate::actions::{search::StartSearch, AppendCharToSearch};
+        use crate::EditorTheme;
+
+        let mut state = EditorState::new(Lines::from("ab ab ab"));
+        state.cursor = Index2::new(0, 1);
+        state.mode = EditorMode::Search;
+        state.execute(StartSearch);
+        state.execute(AppendCharToSearch('a'));
+        state.execute(AppendCharToSearch('b'));
+        assert_eq!(state.cursor, Index2::new(0, 3));
+        assert_eq!(state.search_counter(), Some((2, 3)));
+
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buffer = Buffer::empty(area);
+        EditorView::new(&mut state).render(area, &mut buffer);
+
+        let theme = EditorTheme::default();
+        let bg = |x: u16| Some(buffer[(x, 0)].bg);
+        assert_eq!(bg(1), theme.search_match_style.bg);
+        assert_eq!(bg(2), theme.base.bg);
+        assert_eq!(bg(4), theme.current_match_style.bg);
+        assert_eq!(bg(7), theme.search_match_style.bg);
+
+        let status: String = (10..20).map(|x| buffer[(x, 2)].symbol()).collect();
+        assert_eq!(status, "/ab [2/3] ");
+
+        // Without a match, the cursor returns to where the search started.
+        state.execute(AppendCharToSearch('c'));
+        assert_eq!(state.cursor, Index2::new(0, 1));
+        assert_eq!(state.search_counter(), Some((0, 0)));
+    }
+
     #[test]
     fn test_single_line_mode_blocks_line_break() {
         use crate::actions::LineBreak;
@@ -339,9 +607,89 @@ mod tests {
 
         // Paste text with newlines
         state.clip.set_text("Line1\nLine2\nLine3".to_string());
-        Paste.execute(&mut state);
+        Paste(1).execute(&mut state);
 
         // Newlines should be replaced with spaces
         assert_eq!(state.lines, Lines::from("HelloLine1 Line2 Line3"));
     }
+
+    #[test]
+    fn test_read_only_refuses_edits() {
+        use crate::actions::{CopyLine, DeleteLine, InsertChar, MoveForward, Paste, Undo};
+        use crate::clipboard::InternalClipboard;
+
+        let mut state = EditorState::new(Lines::from("Hello"));
+        state.set_clipboard(InternalClipboard::default());
+        state.execute(DeleteLine(1));
+        state.set_read_only(true);
+
+        state.execute(Undo);
+        state.execute(InsertChar('x'));
+        state.execute(SwitchMode(EditorMode::Insert));
+        assert_eq!(state.lines, Lines::from(""));
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        state.set_read_only(false);
+        state.execute(Undo);
+        state.set_read_only(true);
+        state.execute(MoveForward(1));
+        state.execute(CopyLine);
+        state.execute(Paste(1));
+        assert_eq!(state.cursor, Index2::new(0, 1));
+        assert_eq!(state.lines, Lines::from("Hello"));
+        assert_eq!(state.register('"'), Some(String::from("Hello")));
+
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buffer = Buffer::empty(area);
+        EditorView::new(&mut state).render(area, &mut buffer);
+        let status: String = (10..14).map(|x| buffer[(x, 2)].symbol()).collect();
+        assert_eq!(status, "[RO]");
+    }
+
+    #[test]
+    fn test_extmark_highlight_follows_edits() {
+        use crate::actions::InsertNewline;
+        use crate::Extmark;
+        use ratatui_core::style::{Color, Style};
+
+        let mut state = EditorState::new(Lines::from("let x = 1;"));
+        let id = state.add_extmark(Extmark::new(Index2::new(0, 4), Index2::new(0, 5)));
+        state.add_highlight(Highlight::at_extmark(id, Style::default().bg(Color::Red)));
+        state.execute(InsertNewline(1));
+
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buffer = Buffer::empty(area);
+        EditorView::new(&mut state).render(area, &mut buffer);
+        assert_eq!(buffer[(4, 1)].bg, Color::Red);
+        assert_ne!(buffer[(4, 0)].bg, Color::Red);
+        assert_ne!(buffer[(5, 1)].bg, Color::Red);
+
+        state.remove_extmark(id);
+        let mut buffer = Buffer::empty(area);
+        EditorView::new(&mut state).render(area, &mut buffer);
+        assert_ne!(buffer[(4, 1)].bg, Color::Red);
+    }
+
+    #[test]
+    fn test_marks_gutter_render() {
+        use crate::LineNumbers;
+
+        let mut state = EditorState::new(Lines::from("one\ntwo\nthree"));
+        state.set_mark('b', Index2::new(1, 2));
+        state.set_mark('a', Index2::new(1, 0));
+        state.set_mark('c', Index2::new(2, 0));
+
+        let area = Rect::new(0, 0, 20, 4);
+        let mut buffer = Buffer::empty(area);
+        EditorView::new(&mut state)
+            .show_marks(true)
+            .line_numbers(LineNumbers::Absolute)
+            .render(area, &mut buffer);
+
+        let row = |y: u16| -> String { (0..6).map(|x| buffer[(x, y)].symbol()).collect() };
+        assert_eq!(row(0), " 1 one");
+        assert_eq!(row(1), "a2 two");
+        assert_eq!(row(2), "c3 thr");
+        assert_eq!(state.cursor_screen_position(), Some(Position::new(3, 0)));
+    }
 }
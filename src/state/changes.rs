@@ -0,0 +1,582 @@
+//! Change events, for host apps that react to edits, e.g. to autosave, show
+//! a dirty marker or re-validate the text.
+//!
+//! Events are read with [`EditorState::take_change_events`] after
+//! [`EditorState::record_change_events`] was enabled, or passed to the
+//! callbacks registered with [`EditorState::on_change`] as they happen.
+//! Changes made by assigning [`EditorState::lines`] directly are not reported.
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+use jagged::index::RowIndex;
+
+use super::{
+    extmark::Extmarks,
+    marks::Marks,
+    protected::{is_protected, ProtectedRange},
+    selection::Selection,
+    undo::Edit,
+};
+use crate::helper::text_in_range;
+use crate::{EditorMode, EditorState, Index2, Lines};
+
+/// A change of the editor state.
+///
+/// Positions of text changes are given in the text before the change. An
+/// end is exclusive, and a column equal to the length of its row stands for
+/// the line break after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeEvent {
+    /// `text` was inserted at `start`. It ends before `end`.
+    Inserted {
+        start: Index2,
+        end: Index2,
+        text: String,
+    },
+    /// The `text` from `start` up to `end` was deleted.
+    Deleted {
+        start: Index2,
+        end: Index2,
+        text: String,
+    },
+    /// The editor switched from one mode to another.
+    ModeChanged { from: EditorMode, to: EditorMode },
+    /// The cursor moved.
+    CursorMoved { from: Index2, to: Index2 },
+    /// The selection changed, or was removed.
+    SelectionChanged(Option<Selection>),
+}
+
+/// A callback registered with [`EditorState::on_change`].
+type Callback = Rc<dyn Fn(&ChangeEvent)>;
+
+/// Records the changes of the text and emits them as events.
+#[derive(Clone, Default)]
+pub(crate) struct ChangeLog {
+    events: Vec<ChangeEvent>,
+    record: bool,
+    callbacks: Vec<Callback>,
+    /// The number of text changes.
+    revision: u64,
+    /// The extmarks, which move along with the edits.
+    pub(crate) extmarks: Extmarks,
+    /// The marks and the jump list, which move along with the edits.
+    pub(crate) marks: Marks,
+    /// The ranges edits must not touch.
+    pub(crate) protected: Vec<ProtectedRange>,
+    /// The number of edits rejected for touching a protected range.
+    pub(crate) rejections: u64,
+    /// The edits since the last undo step, with adjoining edits merged.
+    pub(crate) edits: Vec<Edit>,
+}
+
+impl ChangeLog {
+    /// Replaces the text from `start` up to `end` (exclusive) by `text` and
+    /// records the edit. Returns the replaced text, or `None` if the edit
+    /// touches a protected range and is rejected. A column equal to the
+    /// length of its row addresses the line break after it.
+    pub(crate) fn replace(
+        &mut self,
+        lines: &mut Lines,
+        start: Index2,
+        end: Index2,
+        text: &str,
+    ) -> Option<String> {
+        let len_col = lines.len_col(start.row).unwrap_or_default();
+        let start = Index2::new(start.row, start.col.min(len_col));
+        let edit = Edit {
+            start,
+            deleted: text_in_range(lines, start, end),
+            inserted: text.to_string(),
+        };
+        if edit.deleted.is_empty() && edit.inserted.is_empty() {
+            return Some(String::new());
+        }
+        if is_protected(&self.protected, &edit) {
+            self.rejections += 1;
+            return None;
+        }
+        edit.apply(lines);
+        self.record(&edit);
+        self.push_edit(&edit);
+        Some(edit.deleted)
+    }
+
+    /// Inserts `text` at `pos` and records the edit. Returns false if the
+    /// edit is rejected. A position in the row after the last one starts a
+    /// new row.
+    pub(crate) fn insert(&mut self, lines: &mut Lines, pos: Index2, text: &str) -> bool {
+        if lines.is_empty() {
+            lines.push(Vec::new());
+        }
+        let last = lines.last_row_index();
+        if pos.row > last {
+            let end = Index2::new(last, lines.len_col(last).unwrap_or_default());
+            return self
+                .replace(lines, end, end, &format!("\n{text}"))
+                .is_some();
+        }
+        self.replace(lines, pos, pos, text).is_some()
+    }
+
+    /// Runs `f`, which changes the text only within `rows`, and records the
+    /// change. Rows may be added or removed within the range. A change that
+    /// touches a protected range is reverted.
+    ///
+    /// The edit is found by comparing the rows before and after `f`, which
+    /// can not tell where text went next to equal characters. Edits with a
+    /// known position go through [`replace`](Self::replace) instead.
+    pub(crate) fn edit<R>(
+        &mut self,
+        lines: &mut Lines,
+        rows: RangeInclusive<usize>,
+        f: impl FnOnce(&mut Lines) -> R,
+    ) -> R {
+        // One more row on each side lets added or removed rows be expressed
+        // as inserted or deleted line breaks.
+        let len = lines.len();
+        let first = rows.start().saturating_sub(1);
+        let end = (rows.end() + 2).min(len).max(first);
+        let before = copy_rows(lines, first, end);
+
+        let result = f(lines);
+
+        let end = (end + lines.len()).saturating_sub(len).max(first);
+        let after = copy_rows(lines, first, end);
+        let Some(mut edit) = Edit::between(&before, &after) else {
+            return result;
+        };
+        edit.start.row += first;
+        if is_protected(&self.protected, &edit) {
+            edit.inverse().apply(lines);
+            self.rejections += 1;
+        } else {
+            self.record(&edit);
+            self.push_edit(&edit);
+        }
+        result
+    }
+
+    /// Adds an edit to the edits of the undo step, merged into the last one
+    /// if they adjoin.
+    fn push_edit(&mut self, edit: &Edit) {
+        if let Some(last) = self.edits.last_mut() {
+            if last.merge(edit) {
+                if last.is_noop() {
+                    self.edits.pop();
+                }
+                return;
+            }
+        }
+        self.edits.push(edit.clone());
+    }
+
+    /// Records a change of the text.
+    pub(crate) fn record(&mut self, edit: &Edit) {
+        self.revision += 1;
+        self.extmarks.shift(edit);
+        self.marks.shift(edit);
+        for range in &mut self.protected {
+            range.shift(edit);
+        }
+        if !self.record && self.callbacks.is_empty() {
+            return;
+        }
+        if !edit.deleted.is_empty() {
+            self.emit(ChangeEvent::Deleted {
+                start: edit.start,
+                end: end_of(edit.start, &edit.deleted),
+                text: edit.deleted.clone(),
+            });
+        }
+        if !edit.inserted.is_empty() {
+            self.emit(ChangeEvent::Inserted {
+                start: edit.start,
+                end: end_of(edit.start, &edit.inserted),
+                text: edit.inserted.clone(),
+            });
+        }
+    }
+
+    fn emit(&mut self, event: ChangeEvent) {
+        for callback in &self.callbacks {
+            callback(&event);
+        }
+        if self.record {
+            self.events.push(event);
+        }
+    }
+}
+
+fn copy_rows(lines: &Lines, start: usize, end: usize) -> Lines {
+    let rows = (start..end).filter_map(|row| lines.get(RowIndex::new(row)).cloned());
+    Lines::new(rows.collect::<Vec<_>>())
+}
+
+/// Returns the position after `text` inserted at `start`.
+pub(crate) fn end_of(start: Index2, text: &str) -> Index2 {
+    let rows = text.matches('\n').count();
+    match text.rfind('\n') {
+        Some(i) => Index2::new(start.row + rows, text[i + 1..].chars().count()),
+        None => Index2::new(start.row, start.col + text.chars().count()),
+    }
+}
+
+/// The cursor, mode and selection before an event, to report their changes.
+pub(crate) struct ViewChange {
+    cursor: Index2,
+    mode: EditorMode,
+    selection: Option<Selection>,
+    rejections: u64,
+}
+
+impl ViewChange {
+    pub(crate) fn new(state: &EditorState) -> Self {
+        Self {
+            cursor: state.cursor,
+            mode: state.mode,
+            selection: state.selection.clone(),
+            rejections: state.changes.rejections,
+        }
+    }
+
+    /// Emits the changes of the cursor, mode and selection since `new`. If
+    /// an edit was rejected, the cursor returns to where it was.
+    pub(crate) fn emit(self, state: &mut EditorState) {
+        if state.changes.rejections != self.rejections {
+            state.cursor = self.cursor;
+        }
+        let changes = &mut state.changes;
+        if !changes.record && changes.callbacks.is_empty() {
+            return;
+        }
+        if self.mode != state.mode {
+            changes.emit(ChangeEvent::ModeChanged {
+                from: self.mode,
+                to: state.mode,
+            });
+        }
+        if self.cursor != state.cursor {
+            changes.emit(ChangeEvent::CursorMoved {
+                from: self.cursor,
+                to: state.cursor,
+            });
+        }
+        if self.selection != state.selection {
+            changes.emit(ChangeEvent::SelectionChanged(state.selection.clone()));
+        }
+    }
+}
+
+impl EditorState {
+    /// Starts or stops queueing change events for
+    /// [`take_change_events`](Self::take_change_events). Stopping drops the
+    /// queued events. Off by default.
+    pub fn record_change_events(&mut self, record: bool) {
+        self.changes.record = record;
+        if !record {
+            self.changes.events.clear();
+        }
+    }
+
+    /// Returns and clears the queued change events, oldest first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{ChangeEvent, EditorState, Index2, Lines};
+    /// use edtui::actions::{DeleteLine, Execute};
+    ///
+    /// let mut state = EditorState::new(Lines::from("a\nb"));
+    /// state.record_change_events(true);
+    /// state.execute(DeleteLine(1));
+    ///
+    /// assert_eq!(
+    ///     state.take_change_events(),
+    ///     vec![ChangeEvent::Deleted {
+    ///         start: Index2::new(0, 0),
+    ///         end: Index2::new(1, 0),
+    ///         text: String::from("a\n"),
+    ///     }]
+    /// );
+    /// assert!(state.is_modified());
+    /// ```
+    pub fn take_change_events(&mut self) -> Vec<ChangeEvent> {
+        std::mem::take(&mut self.changes.events)
+    }
+
+    /// Registers a callback that is called with every change event as it
+    /// happens.
+    pub fn on_change(&mut self, callback: impl Fn(&ChangeEvent) + 'static) {
+        self.changes.callbacks.push(Rc::new(callback));
+    }
+
+    /// Returns a number that grows with every change of the text, including
+    /// undo and redo.
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.changes.revision
+    }
+
+    /// Returns true if the text changed since it was loaded or since
+    /// [`mark_saved`](Self::mark_saved). Undoing back to the saved text
+    /// clears it again.
+    #[must_use]
+    pub fn is_modified(&self) -> bool {
+        self.history.saved != Some(self.history.current) || !self.changes.edits.is_empty()
+    }
+
+    /// Marks the current text as saved, e.g. after handling a
+    /// [`CommandEvent::Write`](crate::command::CommandEvent::Write).
+    pub fn mark_saved(&mut self) {
+        self.history.saved = Some(self.history.current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crossterm::event::KeyCode;
+
+    use super::*;
+    use crate::actions::{Execute, InsertChar, SwitchMode};
+    use crate::events::{KeyEventHandler, KeyInput};
+
+    fn type_keys(state: &mut EditorState, keys: &str) {
+        let mut handler = KeyEventHandler::default();
+        for ch in keys.chars() {
+            let key = match ch {
+                '\x1b' => KeyInput::new(KeyCode::Esc),
+                '\n' => KeyInput::new(KeyCode::Enter),
+                '\x08' => KeyInput::new(KeyCode::Backspace),
+                '\x16' => KeyInput::ctrl('v'),
+                '\x12' => KeyInput::ctrl('r'),
+                ch if ch.is_uppercase() => KeyInput::shift(ch),
+                ch => KeyInput::new(ch),
+            };
+            handler.on_event(key, state);
+        }
+    }
+
+    fn text_events(events: Vec<ChangeEvent>) -> Vec<ChangeEvent> {
+        events
+            .into_iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    ChangeEvent::Inserted { .. } | ChangeEvent::Deleted { .. }
+                )
+            })
+            .collect()
+    }
+
+    /// Applies the text events to `lines`.
+    fn replay(lines: &mut Lines, events: &[ChangeEvent]) {
+        for event in events {
+            let edit = match event {
+                ChangeEvent::Inserted { start, text, .. } => Edit {
+                    start: *start,
+                    deleted: String::new(),
+                    inserted: text.clone(),
+                },
+                ChangeEvent::Deleted { start, text, .. } => Edit {
+                    start: *start,
+                    deleted: text.clone(),
+                    inserted: String::new(),
+                },
+                _ => continue,
+            };
+            edit.apply(lines);
+        }
+    }
+
+    #[test]
+    fn test_text_events_replay() {
+        let text = "fn main() {\n    let a = 1;\n\n    foo(a, b);\n}";
+        for keys in [
+            "x3xdd",
+            "jdwdWdeD",
+            "jjdfajdtb",
+            "Ahello\nworld\x1b",
+            "ihi\x08\x08\x08\x1b",
+            "oabc\x1bOdef\x1b",
+            "jJJ",
+            "yyjpPkyyP",
+            "jvjjd",
+            "jVjd",
+            "jvly$p",
+            "ra3rb",
+            "jciwxyz\x1b",
+            "jcc\x1b",
+            ">>j<<",
+            "gUUjg~~",
+            "dGu",
+            "xxuu",
+            "ggdG",
+            "jlllI12\x1b",
+        ] {
+            let mut state = EditorState::new(Lines::from(text));
+            state.record_change_events(true);
+            type_keys(&mut state, keys);
+
+            let events = state.take_change_events();
+            assert!(!events.is_empty(), "keys: {keys:?}");
+            let mut lines = Lines::from(text);
+            replay(&mut lines, &events);
+            assert_eq!(lines.to_string(), state.lines.to_string(), "keys: {keys:?}");
+        }
+    }
+
+    #[test]
+    fn test_text_event_positions() {
+        let mut state = EditorState::new(Lines::from("ab\ncd"));
+        state.record_change_events(true);
+        state.cursor = Index2::new(1, 0);
+        type_keys(&mut state, "i\x08");
+
+        let events = state.take_change_events();
+        assert!(events.contains(&ChangeEvent::Deleted {
+            start: Index2::new(0, 2),
+            end: Index2::new(1, 0),
+            text: String::from("\n"),
+        }));
+
+        state.cursor = Index2::new(0, 2);
+        InsertChar('x').execute(&mut state);
+        assert_eq!(
+            state.take_change_events(),
+            vec![ChangeEvent::Inserted {
+                start: Index2::new(0, 2),
+                end: Index2::new(0, 3),
+                text: String::from("x"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_text_events_replay_repeated_chars() {
+        let text = "aab
+aab
+
+aaaa";
+        for keys in [
+            "x", "lx", "ia\x1b", "la\x1b", "aa\x1b", "rb", "~~", "dd", "yyp", "vld", "jJ",
+            "cwaa\x1b", "oaa\x1b", "i\n\x1b", "jddP", "\x16jld", "gUU", ">>", "xu", "3xuu",
+        ] {
+            let mut state = EditorState::new(Lines::from(text));
+            state.record_change_events(true);
+            type_keys(&mut state, keys);
+
+            let events = state.take_change_events();
+            assert!(!events.is_empty(), "keys: {keys:?}");
+            let mut lines = Lines::from(text);
+            replay(&mut lines, &events);
+            assert_eq!(lines.to_string(), state.lines.to_string(), "keys: {keys:?}");
+        }
+    }
+
+    #[test]
+    fn test_text_event_positions_repeated_chars() {
+        let mut state = EditorState::new(Lines::from("xab"));
+        state.record_change_events(true);
+        state.cursor = Index2::new(0, 1);
+        type_keys(&mut state, "ia\x1b");
+        assert_eq!(
+            text_events(state.take_change_events()),
+            vec![ChangeEvent::Inserted {
+                start: Index2::new(0, 1),
+                end: Index2::new(0, 2),
+                text: String::from("a"),
+            }]
+        );
+
+        let mut state = EditorState::new(Lines::from("aab"));
+        state.record_change_events(true);
+        type_keys(&mut state, "x");
+        assert_eq!(
+            text_events(state.take_change_events()),
+            vec![ChangeEvent::Deleted {
+                start: Index2::new(0, 0),
+                end: Index2::new(0, 1),
+                text: String::from("a"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_view_events() {
+        let mut state = EditorState::new(Lines::from("abc"));
+        state.record_change_events(true);
+
+        type_keys(&mut state, "l");
+        assert_eq!(
+            state.take_change_events(),
+            vec![ChangeEvent::CursorMoved {
+                from: Index2::new(0, 0),
+                to: Index2::new(0, 1),
+            }]
+        );
+
+        type_keys(&mut state, "v");
+        assert_eq!(
+            state.take_change_events(),
+            vec![
+                ChangeEvent::ModeChanged {
+                    from: EditorMode::Normal,
+                    to: EditorMode::Visual,
+                },
+                ChangeEvent::SelectionChanged(Some(Selection::new(
+                    Index2::new(0, 1),
+                    Index2::new(0, 1),
+                ))),
+            ]
+        );
+
+        state.execute(SwitchMode(EditorMode::Normal));
+        let events = state.take_change_events();
+        assert!(events.contains(&ChangeEvent::SelectionChanged(None)));
+
+        state.record_change_events(false);
+        type_keys(&mut state, "x");
+        assert!(state.take_change_events().is_empty());
+    }
+
+    #[test]
+    fn test_callbacks_and_revision() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut state = EditorState::new(Lines::from("abc"));
+        let sink = Rc::clone(&seen);
+        state.on_change(move |event| sink.borrow_mut().push(event.clone()));
+        assert!(!state.is_modified());
+
+        type_keys(&mut state, "x");
+        assert_eq!(
+            seen.borrow().first(),
+            Some(&ChangeEvent::Deleted {
+                start: Index2::new(0, 0),
+                end: Index2::new(0, 1),
+                text: String::from("a"),
+            })
+        );
+        assert_eq!(state.revision(), 1);
+        assert!(state.is_modified());
+        assert!(state.take_change_events().is_empty());
+
+        state.mark_saved();
+        assert!(!state.is_modified());
+        type_keys(&mut state, "l");
+        assert!(!state.is_modified());
+        type_keys(&mut state, "u");
+        assert_eq!(state.revision(), 2);
+        assert!(state.is_modified());
+
+        // Undoing back to the saved text clears the modified flag.
+        type_keys(&mut state, "\x12");
+        assert!(!state.is_modified());
+        type_keys(&mut state, "xu");
+        assert!(!state.is_modified());
+        type_keys(&mut state, "ia");
+        assert!(state.is_modified());
+    }
+}
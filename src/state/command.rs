@@ -0,0 +1,51 @@
+use crate::command::{CommandError, CommandEvent};
+
+/// Represents the state of the command line in command mode.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CommandState {
+    /// The command line typed after `:`.
+    pub(crate) input: String,
+    /// Completions of the input, cycled through with tab.
+    pub(crate) completions: Vec<String>,
+    /// The completion that is currently shown.
+    pub(crate) completion_index: Option<usize>,
+    /// The error of the last command, shown in the status line.
+    pub(crate) error: Option<CommandError>,
+    /// Requests to the host app, e.g. to save or quit.
+    pub(crate) events: Vec<CommandEvent>,
+}
+
+impl CommandState {
+    pub(crate) fn start(&mut self) {
+        self.input.clear();
+        self.clear_completions();
+        self.error = None;
+    }
+
+    pub(crate) fn push_char(&mut self, ch: char) {
+        self.input.push(ch);
+        self.clear_completions();
+    }
+
+    pub(crate) fn remove_char(&mut self) {
+        self.input.pop();
+        self.clear_completions();
+    }
+
+    pub(crate) fn clear_completions(&mut self) {
+        self.completions.clear();
+        self.completion_index = None;
+    }
+
+    /// Replaces the input with the next completion.
+    pub(crate) fn next_completion(&mut self) {
+        if self.completions.is_empty() {
+            return;
+        }
+        let index = self
+            .completion_index
+            .map_or(0, |index| (index + 1) % self.completions.len());
+        self.completion_index = Some(index);
+        self.input.clone_from(&self.completions[index]);
+    }
+}
@@ -0,0 +1,339 @@
+//! Extmarks: ranges of text that move along with the edits, so that
+//! highlights and other overlays keep pointing at the same characters.
+//!
+//! An extmark is added with [`EditorState::add_extmark`], which returns its
+//! id. A [`Highlight`](crate::Highlight) attaches to it with
+//! [`Highlight::at_extmark`](crate::Highlight::at_extmark).
+use std::collections::BTreeMap;
+
+use super::{changes::end_of, undo::Edit};
+use crate::{EditorState, Highlight, Index2};
+
+/// The id of an extmark, returned by [`EditorState::add_extmark`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExtmarkId(u64);
+
+/// Where a position goes when text is inserted right at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gravity {
+    /// The position stays in front of the inserted text.
+    Left,
+    /// The position moves behind the inserted text.
+    Right,
+}
+
+/// A range of text from `start` up to `end` (exclusive) that moves along with
+/// the edits.
+///
+/// By default, text inserted at either end stays outside of the range. A
+/// column equal to the length of its row stands for the line break after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extmark {
+    /// Start position (row, column).
+    pub start: Index2,
+    /// End position (row, column), exclusive.
+    pub end: Index2,
+    /// Where `start` goes when text is inserted at it. Defaults to
+    /// [`Gravity::Right`].
+    pub start_gravity: Gravity,
+    /// Where `end` goes when text is inserted at it. Defaults to
+    /// [`Gravity::Left`].
+    pub end_gravity: Gravity,
+}
+
+impl Extmark {
+    #[must_use]
+    pub fn new(start: Index2, end: Index2) -> Self {
+        let (start, end) = if end < start {
+            (end, start)
+        } else {
+            (start, end)
+        };
+        Self {
+            start,
+            end,
+            start_gravity: Gravity::Right,
+            end_gravity: Gravity::Left,
+        }
+    }
+
+    /// Creates an empty extmark at a position, e.g. to anchor a sign or a
+    /// virtual text.
+    #[must_use]
+    pub fn point(pos: Index2) -> Self {
+        Self::new(pos, pos)
+    }
+
+    /// Sets where the ends go when text is inserted at them.
+    #[must_use]
+    pub fn gravity(mut self, start: Gravity, end: Gravity) -> Self {
+        self.start_gravity = start;
+        self.end_gravity = end;
+        self
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Moves the range along with an edit.
+    fn shift(&mut self, edit: &Edit) {
+        self.start = shift(self.start, self.start_gravity, edit);
+        self.end = shift(self.end, self.end_gravity, edit);
+        if self.end < self.start {
+            self.end = self.start;
+        }
+    }
+}
+
+/// Returns where the position `pos` is after `edit`. A position at an
+/// insertion goes in front of or behind the inserted text, depending on its
+/// gravity. A position within replaced text stays in place, but not past the
+/// end of the text replacing it.
+pub(crate) fn shift(pos: Index2, gravity: Gravity, edit: &Edit) -> Index2 {
+    if pos < edit.start {
+        return pos;
+    }
+    let deleted_end = end_of(edit.start, &edit.deleted);
+    let inserted_end = end_of(edit.start, &edit.inserted);
+    if edit.deleted.is_empty() && pos == edit.start {
+        return match gravity {
+            Gravity::Left => edit.start,
+            Gravity::Right => inserted_end,
+        };
+    }
+    if pos < deleted_end {
+        let row = pos.row - edit.start.row;
+        return match edit.inserted.split('\n').nth(row) {
+            Some(line) if row == 0 => {
+                Index2::new(pos.row, pos.col.min(edit.start.col + line.chars().count()))
+            }
+            Some(line) => Index2::new(pos.row, pos.col.min(line.chars().count())),
+            None => inserted_end,
+        };
+    }
+    if pos.row == deleted_end.row {
+        Index2::new(
+            inserted_end.row,
+            inserted_end.col + pos.col - deleted_end.col,
+        )
+    } else {
+        Index2::new(pos.row - deleted_end.row + inserted_end.row, pos.col)
+    }
+}
+
+/// The extmarks of an editor, by id.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Extmarks {
+    marks: BTreeMap<ExtmarkId, Extmark>,
+    next_id: u64,
+}
+
+impl Extmarks {
+    /// Moves every extmark along with an edit.
+    pub(crate) fn shift(&mut self, edit: &Edit) {
+        for mark in self.marks.values_mut() {
+            mark.shift(edit);
+        }
+    }
+
+    pub(crate) fn get(&self, id: ExtmarkId) -> Option<&Extmark> {
+        self.marks.get(&id)
+    }
+}
+
+impl EditorState {
+    /// Adds an extmark and returns its id.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Extmark, Index2, Lines};
+    /// use edtui::actions::{Execute, InsertNewline};
+    ///
+    /// let mut state = EditorState::new(Lines::from("let x = 1;"));
+    /// let id = state.add_extmark(Extmark::new(Index2::new(0, 4), Index2::new(0, 5)));
+    ///
+    /// state.execute(InsertNewline(1));
+    /// let mark = state.extmark(id).unwrap();
+    /// assert_eq!(mark.start, Index2::new(1, 4));
+    /// ```
+    pub fn add_extmark(&mut self, mark: Extmark) -> ExtmarkId {
+        let marks = &mut self.changes.extmarks;
+        let id = ExtmarkId(marks.next_id);
+        marks.next_id += 1;
+        marks.marks.insert(id, mark);
+        id
+    }
+
+    /// Returns an extmark, or `None` if it was removed.
+    #[must_use]
+    pub fn extmark(&self, id: ExtmarkId) -> Option<&Extmark> {
+        self.changes.extmarks.get(id)
+    }
+
+    /// Returns all extmarks, oldest first.
+    pub fn extmarks(&self) -> impl Iterator<Item = (ExtmarkId, &Extmark)> {
+        self.changes
+            .extmarks
+            .marks
+            .iter()
+            .map(|(id, mark)| (*id, mark))
+    }
+
+    /// Removes an extmark and returns it.
+    pub fn remove_extmark(&mut self, id: ExtmarkId) -> Option<Extmark> {
+        self.changes.extmarks.marks.remove(&id)
+    }
+
+    /// Removes all extmarks.
+    pub fn clear_extmarks(&mut self) {
+        self.changes.extmarks.marks.clear();
+    }
+
+    /// Returns a highlight with the range of the extmark it is attached to,
+    /// or `None` if the extmark was removed or is empty.
+    pub(crate) fn resolve_highlight(&self, highlight: &Highlight) -> Option<Highlight> {
+        let Some(id) = highlight.extmark else {
+            return Some(highlight.clone());
+        };
+        let mark = self.extmark(id).filter(|mark| !mark.is_empty())?;
+        // A highlight ends at its last character.
+        let end = if mark.end.col > 0 {
+            Index2::new(mark.end.row, mark.end.col - 1)
+        } else {
+            let row = mark.end.row.saturating_sub(1);
+            Index2::new(row, self.lines.len_col(row).unwrap_or_default())
+        };
+        Some(Highlight {
+            start: mark.start,
+            end,
+            ..highlight.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::*;
+    use crate::events::{KeyEventHandler, KeyInput};
+    use crate::Lines;
+
+    fn edit(start: Index2, deleted: &str, inserted: &str) -> Edit {
+        Edit {
+            start,
+            deleted: deleted.to_string(),
+            inserted: inserted.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_gravity() {
+        let insert = edit(Index2::new(0, 2), "", "ab");
+        assert_eq!(
+            shift(Index2::new(0, 2), Gravity::Left, &insert),
+            Index2::new(0, 2)
+        );
+        assert_eq!(
+            shift(Index2::new(0, 2), Gravity::Right, &insert),
+            Index2::new(0, 4)
+        );
+        assert_eq!(
+            shift(Index2::new(0, 1), Gravity::Right, &insert),
+            Index2::new(0, 1)
+        );
+        assert_eq!(
+            shift(Index2::new(0, 3), Gravity::Left, &insert),
+            Index2::new(0, 5)
+        );
+
+        let replace = edit(Index2::new(0, 2), "cd\ne", "x");
+        assert_eq!(
+            shift(Index2::new(0, 2), Gravity::Right, &replace),
+            Index2::new(0, 2)
+        );
+        assert_eq!(
+            shift(Index2::new(0, 3), Gravity::Left, &replace),
+            Index2::new(0, 3)
+        );
+        assert_eq!(
+            shift(Index2::new(0, 4), Gravity::Left, &replace),
+            Index2::new(0, 3)
+        );
+        assert_eq!(
+            shift(Index2::new(1, 0), Gravity::Right, &replace),
+            Index2::new(0, 3)
+        );
+        assert_eq!(
+            shift(Index2::new(1, 4), Gravity::Left, &replace),
+            Index2::new(0, 6)
+        );
+        assert_eq!(
+            shift(Index2::new(2, 4), Gravity::Left, &replace),
+            Index2::new(1, 4)
+        );
+    }
+
+    #[test]
+    fn test_extmark_follows_edits() {
+        let mut mark = Extmark::new(Index2::new(0, 2), Index2::new(0, 4));
+
+        // Text inserted at the ends stays outside.
+        mark.shift(&edit(Index2::new(0, 2), "", "x"));
+        mark.shift(&edit(Index2::new(0, 5), "", "y"));
+        assert_eq!(mark, Extmark::new(Index2::new(0, 3), Index2::new(0, 5)));
+
+        let mut mark = mark.gravity(Gravity::Left, Gravity::Right);
+        mark.shift(&edit(Index2::new(0, 5), "", "z"));
+        assert_eq!(mark.end, Index2::new(0, 6));
+
+        // Deleting the whole range leaves it empty.
+        mark.shift(&edit(Index2::new(0, 0), "abcdefgh", ""));
+        assert!(mark.is_empty());
+    }
+
+    fn type_keys(state: &mut EditorState, keys: &str) {
+        let mut handler = KeyEventHandler::default();
+        for ch in keys.chars() {
+            let key = match ch {
+                '\x1b' => KeyInput::new(KeyCode::Esc),
+                ch if ch.is_ascii_uppercase() => KeyInput::shift(ch),
+                ch => KeyInput::new(ch),
+            };
+            handler.on_event(key, state);
+        }
+    }
+
+    #[test]
+    fn test_extmark_next_to_equal_chars() {
+        // An `a` typed in front of the marked `a` of "xab" stays outside.
+        let mut state = EditorState::new(Lines::from("xab"));
+        let id = state.add_extmark(Extmark::new(Index2::new(0, 1), Index2::new(0, 2)));
+        state.cursor = Index2::new(0, 1);
+        type_keys(&mut state, "ia\x1b");
+        let mark = Extmark::new(Index2::new(0, 2), Index2::new(0, 3));
+        assert_eq!(state.extmark(id), Some(&mark));
+
+        // Deleting the `a` in front of the marked `a` of "aab" keeps it.
+        let mut state = EditorState::new(Lines::from("aab"));
+        let id = state.add_extmark(Extmark::new(Index2::new(0, 1), Index2::new(0, 2)));
+        type_keys(&mut state, "x");
+        let mark = Extmark::new(Index2::new(0, 0), Index2::new(0, 1));
+        assert_eq!(state.extmark(id), Some(&mark));
+    }
+
+    #[test]
+    fn test_extmark_keeps_replaced_text() {
+        for keys in ["gUiw", "~", "rX", "RXY\x1b"] {
+            let mut state = EditorState::new(Lines::from("abc xyz"));
+            let mark = Extmark::new(Index2::new(0, 4), Index2::new(0, 7));
+            let id = state.add_extmark(mark.clone());
+            state.cursor = Index2::new(0, 4);
+            type_keys(&mut state, keys);
+            assert_eq!(state.extmark(id), Some(&mark), "{keys}");
+        }
+    }
+}
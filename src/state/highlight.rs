@@ -1,9 +1,14 @@
 //! Custom highlight ranges for the editor.
 
+use super::extmark::ExtmarkId;
 use crate::Index2;
 use ratatui_core::style::Style;
 
 /// A highlighted range in the editor with a custom style.
+///
+/// A highlight attached to an extmark with [`Highlight::at_extmark`] covers
+/// the range of the extmark instead of `start` and `end`, and moves along
+/// with the edits.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Highlight {
     /// Start position (row, column).
@@ -12,12 +17,30 @@ pub struct Highlight {
     pub end: Index2,
     /// Style to apply to the highlighted range.
     pub style: Style,
+    /// The extmark the highlight is attached to, if any.
+    pub extmark: Option<ExtmarkId>,
 }
 
 impl Highlight {
     #[must_use]
     pub fn new(start: Index2, end: Index2, style: Style) -> Self {
-        Self { start, end, style }
+        Self {
+            start,
+            end,
+            style,
+            extmark: None,
+        }
+    }
+
+    /// Creates a highlight that covers the range of an extmark.
+    #[must_use]
+    pub fn at_extmark(id: ExtmarkId, style: Style) -> Self {
+        Self {
+            start: Index2::default(),
+            end: Index2::default(),
+            style,
+            extmark: Some(id),
+        }
     }
 
     #[must_use]
@@ -0,0 +1,226 @@
+//! Indentation settings and the indentation of new and re-indented lines.
+//!
+//! Indentation is measured in columns, with a tab reaching up to the next
+//! multiple of the tab width. `>>`, `<<` and `=` change it by one shift width
+//! and write it with tabs or, with [`IndentOptions::expand_tab`], spaces.
+use jagged::index::RowIndex;
+
+use crate::{EditorState, Index2};
+
+/// Options of how lines are indented.
+///
+/// # Example
+///
+/// ```
+/// use edtui::actions::{Execute, LineBreak};
+/// use edtui::{EditorState, IndentOptions, Index2, Lines};
+///
+/// let mut state = EditorState::new(Lines::from("fn main() {}"));
+/// state.set_indent_options(IndentOptions {
+///     expand_tab: true,
+///     shift_width: 4,
+///     smart_indent: true,
+///     ..Default::default()
+/// });
+/// state.cursor = Index2::new(0, 11);
+/// state.execute(LineBreak(1));
+/// assert_eq!(state.lines, Lines::from("fn main() {\n    \n}"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IndentOptions {
+    /// Indent with spaces instead of tabs.
+    pub expand_tab: bool,
+
+    /// The number of columns of one level of indentation. Zero uses the tab
+    /// width.
+    pub shift_width: usize,
+
+    /// Start a new line with the indentation of the line it continues.
+    pub auto_indent: bool,
+
+    /// Like [`auto_indent`](Self::auto_indent), but indent one more level
+    /// after a line that ends with `{`, `(`, `[` or `:`, and one less before
+    /// a line that starts with `}`, `)` or `]`.
+    pub smart_indent: bool,
+}
+
+impl EditorState {
+    /// Returns the options of how lines are indented.
+    #[must_use]
+    pub fn indent_options(&self) -> IndentOptions {
+        self.indent
+    }
+
+    /// Sets the options of how lines are indented, e.g. to indent with
+    /// spaces.
+    pub fn set_indent_options(&mut self, options: IndentOptions) {
+        self.indent = options;
+    }
+
+    /// Returns the number of columns of one level of indentation.
+    pub(crate) fn shift_width(&self) -> usize {
+        match self.indent.shift_width {
+            0 => self.view.tab_width.max(1),
+            width => width,
+        }
+    }
+
+    /// Returns the width of the indentation of a row in columns.
+    pub(crate) fn indent_width(&self, row: usize) -> usize {
+        let tab_width = self.view.tab_width.max(1);
+        let Some(line) = self.lines.get(RowIndex::new(row)) else {
+            return 0;
+        };
+        line.iter()
+            .take_while(|ch| ch.is_whitespace())
+            .fold(0, |width, ch| match ch {
+                '\t' => (width / tab_width + 1) * tab_width,
+                _ => width + 1,
+            })
+    }
+
+    /// Replaces the indentation of a row by `width` columns of indentation,
+    /// written with tabs or spaces.
+    pub(crate) fn set_indent(&mut self, row: usize, width: usize) {
+        let indent = self.indent_text(width);
+        let Some(line) = self.lines.get(RowIndex::new(row)) else {
+            return;
+        };
+        let blanks = line.iter().take_while(|ch| ch.is_whitespace()).count();
+        let indent: String = indent.into_iter().collect();
+        let start = Index2::new(row, 0);
+        let end = Index2::new(row, blanks);
+        let _ = self.changes.replace(&mut self.lines, start, end, &indent);
+    }
+
+    /// Returns the indentation of a new line below a row, or `None` without
+    /// auto-indent.
+    pub(crate) fn indent_below(&self, row: usize) -> Option<Vec<char>> {
+        if !self.indent.auto_indent && !self.indent.smart_indent {
+            return None;
+        }
+        let line = self.lines.get(RowIndex::new(row))?;
+        if self.indent.smart_indent && opener(line).is_some() {
+            return Some(self.indent_text(self.indent_width(row) + self.shift_width()));
+        }
+        Some(leading_whitespace(line))
+    }
+
+    /// Returns the indentation of a new line above a row, or `None` without
+    /// auto-indent.
+    pub(crate) fn indent_above(&self, row: usize) -> Option<Vec<char>> {
+        if !self.indent.auto_indent && !self.indent.smart_indent {
+            return None;
+        }
+        let line = self.lines.get(RowIndex::new(row))?;
+        if self.indent.smart_indent && starts_with_closer(line) {
+            return Some(self.indent_text(self.indent_width(row) + self.shift_width()));
+        }
+        Some(leading_whitespace(line))
+    }
+
+    /// Returns the width of the indentation a row gets from `=`: that of the
+    /// previous non-blank row, one level deeper after an opening bracket or
+    /// colon and one level less before a closing bracket.
+    pub(crate) fn reindent_width(&self, row: usize) -> usize {
+        let previous = (0..row).rev().find(|&row| {
+            self.lines
+                .get(RowIndex::new(row))
+                .is_some_and(|line| line.iter().any(|ch| !ch.is_whitespace()))
+        });
+        let mut width = previous.map_or(0, |previous| {
+            let line = self.lines.get(RowIndex::new(previous));
+            let opens = line.is_some_and(|line| opener(line).is_some());
+            self.indent_width(previous) + if opens { self.shift_width() } else { 0 }
+        });
+        if let Some(line) = self.lines.get(RowIndex::new(row)) {
+            if starts_with_closer(line) {
+                width = width.saturating_sub(self.shift_width());
+            }
+        }
+        width
+    }
+
+    /// Returns whitespace that indents by `width` columns.
+    fn indent_text(&self, width: usize) -> Vec<char> {
+        if self.indent.expand_tab {
+            return vec![' '; width];
+        }
+        let tab_width = self.view.tab_width.max(1);
+        let mut text = vec!['\t'; width / tab_width];
+        text.extend(std::iter::repeat_n(' ', width % tab_width));
+        text
+    }
+}
+
+/// Returns the last non-blank character of a line if it opens a block.
+pub(crate) fn opener(line: &[char]) -> Option<char> {
+    line.iter()
+        .rev()
+        .find(|ch| !ch.is_whitespace())
+        .copied()
+        .filter(|ch| matches!(ch, '{' | '(' | '[' | ':'))
+}
+
+/// Returns the bracket that closes an opening bracket.
+pub(crate) fn closer(opener: char) -> Option<char> {
+    match opener {
+        '{' => Some('}'),
+        '(' => Some(')'),
+        '[' => Some(']'),
+        _ => None,
+    }
+}
+
+/// Returns whether the first non-blank character of a line is a closing
+/// bracket.
+fn starts_with_closer(line: &[char]) -> bool {
+    line.iter()
+        .find(|ch| !ch.is_whitespace())
+        .is_some_and(|ch| matches!(ch, '}' | ')' | ']'))
+}
+
+fn leading_whitespace(line: &[char]) -> Vec<char> {
+    line.iter()
+        .take_while(|ch| ch.is_whitespace())
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lines;
+
+    #[test]
+    fn test_indent_width() {
+        let mut state = EditorState::new(Lines::from("\t x\n  \ty\n{\n}"));
+        state.view.tab_width = 4;
+        assert_eq!(state.indent_width(0), 5);
+        assert_eq!(state.indent_width(1), 4);
+
+        state.set_indent(0, 6);
+        assert_eq!(state.lines, Lines::from("\t  x\n  \ty\n{\n}"));
+
+        state.indent.expand_tab = true;
+        state.indent.shift_width = 2;
+        state.set_indent(1, 2);
+        assert_eq!(state.lines, Lines::from("\t  x\n  y\n{\n}"));
+        assert_eq!(state.reindent_width(1), 6);
+        assert_eq!(state.reindent_width(3), 0);
+    }
+
+    #[test]
+    fn test_indent_new_lines() {
+        let mut state = EditorState::new(Lines::from("  a:\n  }"));
+        assert_eq!(state.indent_below(0), None);
+
+        state.indent.auto_indent = true;
+        assert_eq!(state.indent_below(0), Some(vec![' ', ' ']));
+
+        state.indent.expand_tab = true;
+        state.indent.smart_indent = true;
+        assert_eq!(state.indent_below(0), Some(vec![' '; 4]));
+        assert_eq!(state.indent_above(1), Some(vec![' '; 4]));
+    }
+}
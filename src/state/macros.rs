@@ -0,0 +1,181 @@
+//! Macros: recorded key sequences that are replayed by the key handler.
+//!
+//! `q` followed by a register name starts recording the keys into that
+//! register, another `q` stops it. `@` followed by the name replays them,
+//! `@@` replays the last played macro. Macros are read and written with
+//! [`EditorState::macro_register`] and [`EditorState::set_macro_register`],
+//! e.g. to keep them across sessions.
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::events::{KeyInput, KeyInputSequence};
+use crate::EditorState;
+
+/// The maximum number of keys replayed for a single key, which stops a
+/// macro that replays itself.
+pub(crate) const MAX_REPLAYED_KEYS: usize = 100_000;
+
+/// The macro registers, the recording and the keys waiting to be replayed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Macros {
+    registers: BTreeMap<char, KeyInputSequence>,
+    /// The register being recorded into and the keys recorded so far.
+    recording: Option<(char, Vec<KeyInput>)>,
+    /// The keys that the key handler replays next.
+    replay: VecDeque<KeyInput>,
+    /// The register of the last played macro, for `@@`.
+    last: Option<char>,
+}
+
+impl Macros {
+    /// Starts recording into a register. An uppercase name appends to the
+    /// macro of its lowercase register.
+    pub(crate) fn start(&mut self, name: char) {
+        let keys = if name.is_ascii_uppercase() {
+            self.registers
+                .get(&name.to_ascii_lowercase())
+                .map(|keys| keys.0.clone())
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        self.recording = Some((name.to_ascii_lowercase(), keys));
+    }
+
+    /// Stops recording and stores the recorded keys.
+    pub(crate) fn stop(&mut self) {
+        if let Some((name, keys)) = self.recording.take() {
+            self.registers.insert(name, KeyInputSequence::new(keys));
+        }
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Adds a key to the recording, if any.
+    pub(crate) fn record(&mut self, key: KeyInput) {
+        if let Some((_, keys)) = &mut self.recording {
+            keys.push(key);
+        }
+    }
+
+    /// Queues the keys of a macro `count` times, in front of the keys that
+    /// are still waiting, so that a macro played by a macro runs first. The
+    /// name `@` stands for the last played macro. No more than
+    /// [`MAX_REPLAYED_KEYS`] keys are queued, however large the count.
+    pub(crate) fn play(&mut self, name: char, count: usize) {
+        let name = match name {
+            '@' => match self.last {
+                Some(last) => last,
+                None => return,
+            },
+            _ => name.to_ascii_lowercase(),
+        };
+        let Some(keys) = self.registers.get(&name) else {
+            return;
+        };
+        self.last = Some(name);
+        let room = MAX_REPLAYED_KEYS.saturating_sub(self.replay.len());
+        let len = keys.0.len().saturating_mul(count).min(room);
+        let queued: Vec<KeyInput> = keys.0.iter().copied().cycle().take(len).collect();
+        for key in queued.into_iter().rev() {
+            self.replay.push_front(key);
+        }
+    }
+
+    /// Returns the next key to replay.
+    pub(crate) fn next_key(&mut self) -> Option<KeyInput> {
+        self.replay.pop_front()
+    }
+
+    /// Drops the keys waiting to be replayed.
+    pub(crate) fn clear_replay(&mut self) {
+        self.replay.clear();
+    }
+}
+
+impl EditorState {
+    /// Returns the keys of a macro, or `None` if the register holds none.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crossterm::event::KeyCode;
+    /// use edtui::events::{KeyInput, KeyInputSequence};
+    /// use edtui::{EditorEventHandler, EditorState, Lines};
+    ///
+    /// let mut state = EditorState::new(Lines::from("a\nb"));
+    /// let keys = vec![KeyInput::shift('A'), KeyInput::new(';'), KeyInput::new(KeyCode::Esc)];
+    /// state.set_macro_register('q', KeyInputSequence::new(keys));
+    ///
+    /// let mut handler = EditorEventHandler::default();
+    /// for c in "2@q".chars() {
+    ///     handler.on_key_event(KeyInput::new(c), &mut state);
+    /// }
+    /// assert_eq!(state.lines, Lines::from("a;;\nb"));
+    /// ```
+    #[must_use]
+    pub fn macro_register(&self, name: char) -> Option<&KeyInputSequence> {
+        self.macros.registers.get(&name.to_ascii_lowercase())
+    }
+
+    /// Writes the keys of a macro to a register, replacing its macro.
+    pub fn set_macro_register(&mut self, name: char, keys: impl Into<KeyInputSequence>) {
+        let name = name.to_ascii_lowercase();
+        self.macros.registers.insert(name, keys.into());
+    }
+
+    /// Returns the macros, ordered by register name.
+    pub fn macro_registers(&self) -> impl Iterator<Item = (char, &KeyInputSequence)> {
+        self.macros
+            .registers
+            .iter()
+            .map(|(name, keys)| (*name, keys))
+    }
+
+    /// Returns the register a macro is being recorded into, if any.
+    #[must_use]
+    pub fn recording_macro(&self) -> Option<char> {
+        self.macros.recording.as_ref().map(|(name, _)| *name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_play() {
+        let mut macros = Macros::default();
+        macros.start('a');
+        macros.record(KeyInput::new('x'));
+        macros.stop();
+        macros.record(KeyInput::new('y'));
+
+        macros.start('A');
+        macros.record(KeyInput::new('j'));
+        macros.stop();
+
+        macros.play('a', 2);
+        macros.play('@', 1);
+        macros.play('b', 1);
+        let keys: Vec<KeyInput> = std::iter::from_fn(|| macros.next_key()).collect();
+        let expected: Vec<KeyInput> = "xjxjxj".chars().map(KeyInput::new).collect();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn test_play_limits_queued_keys() {
+        let mut macros = Macros::default();
+        macros.start('a');
+        macros.record(KeyInput::new('x'));
+        macros.record(KeyInput::new('y'));
+        macros.stop();
+
+        macros.play('a', usize::MAX);
+        assert_eq!(macros.replay.len(), MAX_REPLAYED_KEYS);
+        macros.play('a', 1);
+        assert_eq!(macros.replay.len(), MAX_REPLAYED_KEYS);
+        assert_eq!(macros.next_key(), Some(KeyInput::new('x')));
+    }
+}
@@ -0,0 +1,190 @@
+//! Marks and the jump list, which remember positions to jump back to.
+//!
+//! A mark is set by name with `m{a-z}` or [`EditorState::set_mark`] and
+//! jumped to with `'` or `` ` ``. Long-distance motions like `G`, `%` or a
+//! search hit add the position they leave to the jump list, which is walked
+//! with `Ctrl+o` and `Ctrl+i`. Both move along with the edits.
+use std::collections::BTreeMap;
+
+use super::{
+    extmark::{shift, Gravity},
+    undo::Edit,
+};
+use crate::{EditorState, Index2};
+
+/// The maximum number of positions kept in the jump list.
+const MAX_JUMPS: usize = 100;
+
+/// The named marks and the jump list of an editor.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Marks {
+    marks: BTreeMap<char, Index2>,
+    /// The position before the latest jump, the mark `'`.
+    previous: Option<Index2>,
+    jumps: Vec<Index2>,
+    /// The position in the jump list. Equal to its length unless the list
+    /// is being walked.
+    index: usize,
+}
+
+impl Marks {
+    /// Returns the position of a mark. The marks `'` and `` ` `` are the
+    /// position before the latest jump.
+    pub(crate) fn get(&self, name: char) -> Option<Index2> {
+        match name {
+            '\'' | '`' => self.previous,
+            _ => self.marks.get(&name).copied(),
+        }
+    }
+
+    /// Adds the position a jump leaves to the jump list. An older entry on
+    /// the same row is dropped.
+    pub(crate) fn push_jump(&mut self, pos: Index2) {
+        self.previous = Some(pos);
+        self.jumps.retain(|jump| jump.row != pos.row);
+        self.jumps.push(pos);
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.index = self.jumps.len();
+    }
+
+    /// Returns the position `count` entries back in the jump list. When
+    /// leaving the end of the list, `cursor` is added so that
+    /// [`forward`](Self::forward) can return to it.
+    pub(crate) fn back(&mut self, cursor: Index2, count: usize) -> Option<Index2> {
+        if self.index >= self.jumps.len() {
+            let previous = self.previous;
+            self.push_jump(cursor);
+            self.previous = previous;
+            self.index = self.jumps.len() - 1;
+        }
+        let index = self.index.checked_sub(count)?;
+        self.index = index;
+        self.jumps.get(index).copied()
+    }
+
+    /// Returns the position `count` entries forward in the jump list.
+    pub(crate) fn forward(&mut self, count: usize) -> Option<Index2> {
+        let index = self.index.saturating_add(count);
+        let pos = self.jumps.get(index).copied()?;
+        self.index = index;
+        Some(pos)
+    }
+
+    /// Moves the marks and the jump list along with an edit.
+    pub(crate) fn shift(&mut self, edit: &Edit) {
+        let positions = self
+            .marks
+            .values_mut()
+            .chain(self.previous.as_mut())
+            .chain(self.jumps.iter_mut());
+        for pos in positions {
+            *pos = shift(*pos, Gravity::Right, edit);
+        }
+    }
+}
+
+impl EditorState {
+    /// Sets a mark, like `m` followed by its name. Setting the mark `'` or
+    /// `` ` `` adds the position to the jump list instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Index2, Lines};
+    /// use edtui::actions::{Execute, InsertNewline};
+    ///
+    /// let mut state = EditorState::new(Lines::from("fn main() {}"));
+    /// state.set_mark('a', Index2::new(0, 3));
+    ///
+    /// state.execute(InsertNewline(1));
+    /// assert_eq!(state.mark('a'), Some(Index2::new(1, 3)));
+    /// ```
+    pub fn set_mark(&mut self, name: char, pos: Index2) {
+        let marks = &mut self.changes.marks;
+        match name {
+            '\'' | '`' => marks.push_jump(pos),
+            _ => {
+                marks.marks.insert(name, pos);
+            }
+        }
+    }
+
+    /// Returns the position of a mark, moved along with the edits since it
+    /// was set, or `None` if it is not set.
+    #[must_use]
+    pub fn mark(&self, name: char) -> Option<Index2> {
+        self.changes.marks.get(name)
+    }
+
+    /// Returns the named marks, ordered by name.
+    pub fn marks(&self) -> impl Iterator<Item = (char, Index2)> + '_ {
+        self.changes
+            .marks
+            .marks
+            .iter()
+            .map(|(name, pos)| (*name, *pos))
+    }
+
+    /// Removes a mark and returns its position.
+    pub fn remove_mark(&mut self, name: char) -> Option<Index2> {
+        self.changes.marks.marks.remove(&name)
+    }
+
+    /// Returns the positions in the jump list, oldest first.
+    #[must_use]
+    pub fn jump_list(&self) -> &[Index2] {
+        &self.changes.marks.jumps
+    }
+
+    /// Adds the cursor position to the jump list, before a long-distance
+    /// motion moves it.
+    pub(crate) fn push_jump(&mut self) {
+        self.changes.marks.push_jump(self.cursor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jump_list() {
+        let mut marks = Marks::default();
+        marks.push_jump(Index2::new(0, 0));
+        marks.push_jump(Index2::new(5, 2));
+
+        let cursor = Index2::new(9, 0);
+        assert_eq!(marks.back(cursor, 1), Some(Index2::new(5, 2)));
+        assert_eq!(marks.back(cursor, 1), Some(Index2::new(0, 0)));
+        assert_eq!(marks.back(cursor, 1), None);
+        assert_eq!(marks.forward(2), Some(cursor));
+        assert_eq!(marks.forward(1), None);
+
+        // A new jump drops the older entry on its row and goes to the end.
+        marks.push_jump(Index2::new(5, 4));
+        assert_eq!(
+            marks.jumps,
+            vec![Index2::new(0, 0), cursor, Index2::new(5, 4)]
+        );
+        assert_eq!(marks.forward(1), None);
+        assert_eq!(marks.get('\''), Some(Index2::new(5, 4)));
+    }
+
+    #[test]
+    fn test_marks_follow_edits() {
+        let mut marks = Marks::default();
+        marks.marks.insert('a', Index2::new(1, 2));
+        marks.push_jump(Index2::new(2, 0));
+
+        marks.shift(&Edit {
+            start: Index2::new(0, 0),
+            deleted: String::new(),
+            inserted: String::from("x\n"),
+        });
+        assert_eq!(marks.get('a'), Some(Index2::new(2, 2)));
+        assert_eq!(marks.get('`'), Some(Index2::new(3, 0)));
+        assert_eq!(marks.back(Index2::new(0, 0), 1), Some(Index2::new(3, 0)));
+    }
+}
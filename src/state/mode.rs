@@ -5,7 +5,15 @@ pub enum EditorMode {
     Normal,
     Insert,
     Visual,
+    /// Visual mode that selects whole lines (Vim `V`).
+    VisualLine,
+    /// Visual mode that selects a rectangular block (Vim `ctrl+v`).
+    VisualBlock,
     Search,
+    /// Typing a command line after `:`.
+    Command,
+    /// Typed characters overwrite the text (Vim `R`).
+    Replace,
 }
 
 impl EditorMode {
@@ -16,7 +24,17 @@ impl EditorMode {
             Self::Normal => "Normal".to_string(),
             Self::Insert => "Insert".to_string(),
             Self::Visual => "Visual".to_string(),
+            Self::VisualLine => "Visual Line".to_string(),
+            Self::VisualBlock => "Visual Block".to_string(),
             Self::Search => "Search".to_string(),
+            Self::Command => "Command".to_string(),
+            Self::Replace => "Replace".to_string(),
         }
     }
+
+    /// Returns true for any of the visual modes.
+    #[must_use]
+    pub fn is_visual(&self) -> bool {
+        matches!(self, Self::Visual | Self::VisualLine | Self::VisualBlock)
+    }
 }
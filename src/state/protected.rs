@@ -0,0 +1,120 @@
+//! Protected ranges of text that edits must not touch, e.g. the fixed header
+//! of a form-like editor.
+
+use super::{
+    changes::end_of,
+    extmark::{shift, Gravity},
+    undo::Edit,
+};
+use crate::Index2;
+
+/// A range of text that can not be edited.
+///
+/// Inserts, deletes, pastes and replaces that would change the text from
+/// `start` to `end` (inclusive) are rejected. Text inserted at `start` goes
+/// in front of the range, and the range moves with the edits before it. A
+/// column equal to the length of its row stands for the line break after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectedRange {
+    /// Start position (row, column).
+    pub start: Index2,
+    /// End position (row, column), inclusive.
+    pub end: Index2,
+}
+
+impl ProtectedRange {
+    #[must_use]
+    pub fn new(start: Index2, end: Index2) -> Self {
+        if end < start {
+            Self {
+                start: end,
+                end: start,
+            }
+        } else {
+            Self { start, end }
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, pos: &Index2) -> bool {
+        self.start <= *pos && *pos <= self.end
+    }
+
+    /// Returns true if `edit` changes protected text.
+    fn is_touched_by(&self, edit: &Edit) -> bool {
+        if !edit.deleted.is_empty() {
+            let end = end_of(edit.start, &edit.deleted);
+            if edit.start <= self.end && end > self.start {
+                return true;
+            }
+        }
+        !edit.inserted.is_empty() && self.start < edit.start && edit.start <= self.end
+    }
+
+    /// Moves the range along with an edit.
+    pub(crate) fn shift(&mut self, edit: &Edit) {
+        self.start = shift(self.start, Gravity::Right, edit);
+        self.end = shift(self.end, Gravity::Right, edit);
+    }
+}
+
+/// Returns true if `edit` changes text in any of the `ranges`.
+pub(crate) fn is_protected(ranges: &[ProtectedRange], edit: &Edit) -> bool {
+    ranges.iter().any(|range| range.is_touched_by(edit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(start: Index2, deleted: &str, inserted: &str) -> Edit {
+        Edit {
+            start,
+            deleted: deleted.to_string(),
+            inserted: inserted.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_touched_by() {
+        let range = ProtectedRange::new(Index2::new(0, 2), Index2::new(0, 4));
+
+        assert!(!range.is_touched_by(&edit(Index2::new(0, 2), "", "x")));
+        assert!(range.is_touched_by(&edit(Index2::new(0, 3), "", "x")));
+        assert!(!range.is_touched_by(&edit(Index2::new(0, 5), "", "x")));
+        assert!(range.is_touched_by(&edit(Index2::new(0, 1), "ab", "")));
+        assert!(!range.is_touched_by(&edit(Index2::new(0, 0), "ab", "")));
+        assert!(range.is_touched_by(&edit(Index2::new(0, 4), "e\n", "")));
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut range = ProtectedRange::new(Index2::new(1, 2), Index2::new(1, 4));
+
+        range.shift(&edit(Index2::new(1, 0), "", "ab"));
+        assert_eq!(range.start, Index2::new(1, 4));
+        assert_eq!(range.end, Index2::new(1, 6));
+
+        range.shift(&edit(Index2::new(0, 3), "\n", ""));
+        assert_eq!(range.start, Index2::new(0, 7));
+
+        range.shift(&edit(Index2::new(0, 1), "", "x\ny"));
+        assert_eq!(range.start, Index2::new(1, 7));
+        assert_eq!(range.end, Index2::new(1, 9));
+
+        // Edits after the range do not move it.
+        range.shift(&edit(Index2::new(1, 10), "", "z"));
+        assert_eq!(range.end, Index2::new(1, 9));
+    }
+
+    #[test]
+    fn test_protected_next_to_equal_chars() {
+        let ranges = [ProtectedRange::new(Index2::new(0, 1), Index2::new(0, 3))];
+
+        // An `a` typed in front of the protected `aaa` of "xaaab".
+        assert!(!is_protected(&ranges, &edit(Index2::new(0, 1), "", "a")));
+        assert!(is_protected(&ranges, &edit(Index2::new(0, 2), "", "a")));
+        assert!(!is_protected(&ranges, &edit(Index2::new(0, 0), "x", "")));
+        assert!(is_protected(&ranges, &edit(Index2::new(0, 1), "a", "")));
+    }
+}
@@ -0,0 +1,341 @@
+//! Find and replace, either all at once with [`EditorState::replace_all`]
+//! or one match at a time with [`EditorState::start_replace`] and
+//! [`EditorState::confirm_replace`].
+//!
+//! Each run is a single undo step. Nothing is replaced in read-only mode.
+use std::ops::RangeInclusive;
+
+use jagged::Index2;
+
+use super::changes::ChangeLog;
+use super::search::{find_matches, SearchMatch};
+use crate::{EditorState, Lines, SearchOptions};
+
+/// Options of a replace run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReplaceFlags {
+    /// Replace only the first match in each line, like `:s` without `g`.
+    pub first_per_line: bool,
+
+    /// How the pattern is matched. The replacement is always literal text.
+    pub search: SearchOptions,
+}
+
+/// The answer to a pending replace, like vim's `y/n/a/q` prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplaceChoice {
+    /// Replace the current match and move to the next one.
+    Yes,
+    /// Skip the current match and move to the next one.
+    No,
+    /// Replace the current match and all remaining ones.
+    All,
+    /// Stop without replacing the current match.
+    Quit,
+}
+
+/// State of a replace that waits for the confirmation of each match.
+/// The pending matches are held in the search state.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct ReplaceState {
+    pub(crate) active: bool,
+    pub(crate) replacement: String,
+    captured: bool,
+}
+
+impl EditorState {
+    /// Replaces every match of `pattern` in the buffer with `replacement`
+    /// and returns the number of replacements. A match never spans lines.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Lines, ReplaceFlags, SearchOptions};
+    ///
+    /// let mut state = EditorState::new(Lines::from("foo bar\nFoo fooo"));
+    /// let search = SearchOptions { regex: true, ignore_case: true, ..Default::default() };
+    /// let flags = ReplaceFlags { search, ..Default::default() };
+    /// assert_eq!(state.replace_all("fo+", "baz", flags), 3);
+    /// assert_eq!(state.lines, Lines::from("baz bar\nbaz baz"));
+    /// ```
+    pub fn replace_all(&mut self, pattern: &str, replacement: &str, flags: ReplaceFlags) -> usize {
+        let rows = 0..=self.lines.len().saturating_sub(1);
+        let count = self.replace_rows(rows, pattern, replacement, flags).len();
+        self.clamp_column();
+        count
+    }
+
+    /// Starts a replace that asks for confirmation of each match and returns
+    /// the number of matches. The cursor moves to the first match at or after
+    /// it, which is highlighted like a search match.
+    ///
+    /// Answer with [`confirm_replace`](Self::confirm_replace) until
+    /// [`is_replacing`](Self::is_replacing) returns false. In the editor,
+    /// the keys `y`, `n`, `a` and `q` (or `Esc`) answer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Lines, ReplaceChoice, ReplaceFlags};
+    ///
+    /// let mut state = EditorState::new(Lines::from("a a a"));
+    /// assert_eq!(state.start_replace("a", "b", ReplaceFlags::default()), 3);
+    /// state.confirm_replace(ReplaceChoice::No);
+    /// state.confirm_replace(ReplaceChoice::All);
+    /// assert_eq!(state.lines, Lines::from("a b b"));
+    /// assert!(!state.is_replacing());
+    /// ```
+    pub fn start_replace(
+        &mut self,
+        pattern: &str,
+        replacement: &str,
+        flags: ReplaceFlags,
+    ) -> usize {
+        let rows = 0..=self.lines.len().saturating_sub(1);
+        self.start_replace_rows(rows, pattern, replacement, flags)
+    }
+
+    /// Answers the pending replace at the current match. Returns the number
+    /// of matches left to confirm.
+    pub fn confirm_replace(&mut self, choice: ReplaceChoice) -> usize {
+        if !self.replace.active || self.read_only {
+            return 0;
+        }
+        match choice {
+            ReplaceChoice::Yes => {
+                self.replace_current();
+                self.skip_current();
+            }
+            ReplaceChoice::No => self.skip_current(),
+            ReplaceChoice::All => {
+                while self.search.current().is_some() {
+                    self.replace_current();
+                    self.skip_current();
+                }
+            }
+            ReplaceChoice::Quit => self.search.matches.clear(),
+        }
+
+        if let Some(&index) = self.search.current() {
+            self.cursor = index;
+        } else {
+            self.stop_replace();
+        }
+        self.search.matches.len()
+    }
+
+    /// Returns true while a replace waits for confirmation.
+    #[must_use]
+    pub fn is_replacing(&self) -> bool {
+        self.replace.active
+    }
+
+    /// Returns the text that replaces the current match, while a replace
+    /// waits for confirmation.
+    #[must_use]
+    pub fn replace_prompt(&self) -> Option<String> {
+        self.replace
+            .active
+            .then(|| format!("replace with {} (y/n/a/q)?", self.replace.replacement))
+    }
+
+    /// Replaces the matches of `pattern` within `rows` as one undo step.
+    /// Returns the position of each replacement before it was made.
+    pub(crate) fn replace_rows(
+        &mut self,
+        rows: RangeInclusive<usize>,
+        pattern: &str,
+        replacement: &str,
+        flags: ReplaceFlags,
+    ) -> Vec<Index2> {
+        if self.read_only {
+            return Vec::new();
+        }
+        let matches = find_matches(
+            &self.lines,
+            rows,
+            pattern,
+            &flags.search,
+            flags.first_per_line,
+        );
+        if matches.is_empty() {
+            return Vec::new();
+        }
+        self.capture();
+        let replacement: Vec<char> = replacement.chars().collect();
+        let mut replaced: Vec<Index2> = matches
+            .iter()
+            .rev()
+            .filter(|m| replace_at(&mut self.lines, &mut self.changes, m, &replacement))
+            .map(|m| m.start)
+            .collect();
+        replaced.reverse();
+        replaced
+    }
+
+    /// Starts a confirmed replace of the matches within `rows`.
+    pub(crate) fn start_replace_rows(
+        &mut self,
+        rows: RangeInclusive<usize>,
+        pattern: &str,
+        replacement: &str,
+        flags: ReplaceFlags,
+    ) -> usize {
+        if self.read_only {
+            return 0;
+        }
+        self.search.start(self.cursor);
+        self.search.pattern = pattern.to_string();
+        self.search.matches = find_matches(
+            &self.lines,
+            rows,
+            pattern,
+            &flags.search,
+            flags.first_per_line,
+        );
+        self.replace = ReplaceState {
+            active: true,
+            replacement: replacement.to_string(),
+            ..Default::default()
+        };
+
+        let count = self.search.matches.len();
+        if let Some(&index) = self.search.first() {
+            self.cursor = index;
+        } else {
+            self.stop_replace();
+        }
+        count
+    }
+
+    /// Replaces the current match. The first replacement of a run captures
+    /// the undo state.
+    fn replace_current(&mut self) {
+        let Some(&current) = self.search.current_match() else {
+            return;
+        };
+        if !self.replace.captured {
+            self.capture();
+            self.replace.captured = true;
+        }
+        let replacement: Vec<char> = self.replace.replacement.chars().collect();
+        if !replace_at(&mut self.lines, &mut self.changes, &current, &replacement) {
+            return;
+        }
+
+        // Later matches in the same row move with the replaced text.
+        for other in &mut self.search.matches {
+            if other.start.row == current.start.row && other.start.col > current.start.col {
+                other.start.col = other.start.col + replacement.len() - current.len();
+                other.end.col = other.end.col + replacement.len() - current.len();
+            }
+        }
+    }
+
+    /// Drops the current match from the pending ones and selects the next.
+    fn skip_current(&mut self) {
+        let Some(selected) = self.search.selected_index else {
+            return;
+        };
+        if selected < self.search.matches.len() {
+            self.search.matches.remove(selected);
+        }
+        if selected >= self.search.matches.len() {
+            self.search.selected_index = Some(0);
+        }
+    }
+
+    pub(crate) fn stop_replace(&mut self) {
+        self.search.clear();
+        self.search.selected_index = None;
+        self.replace.active = false;
+        self.clamp_column();
+    }
+}
+
+/// Replaces a match, unless it is protected. Returns false if it is.
+fn replace_at(
+    lines: &mut Lines,
+    changes: &mut ChangeLog,
+    m: &SearchMatch,
+    replacement: &[char],
+) -> bool {
+    let end = Index2::new(m.end.row, m.end.col + 1);
+    let text: String = replacement.iter().collect();
+    changes.replace(lines, m.start, end, &text).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_all() {
+        let mut state = EditorState::new(Lines::from("foo foo\nbar\nfoo"));
+        state.cursor = Index2::new(2, 2);
+
+        assert_eq!(state.replace_all("foo", "x", ReplaceFlags::default()), 3);
+        assert_eq!(state.lines, Lines::from("x x\nbar\nx"));
+        assert_eq!(state.cursor, Index2::new(2, 0));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("foo foo\nbar\nfoo"));
+
+        let flags = ReplaceFlags {
+            first_per_line: true,
+            ..Default::default()
+        };
+        assert_eq!(state.replace_all("o", "0", flags), 2);
+        assert_eq!(state.lines, Lines::from("f0o foo\nbar\nf0o"));
+
+        assert_eq!(state.replace_all("baz", "x", ReplaceFlags::default()), 0);
+        assert_eq!(state.replace_all("", "x", ReplaceFlags::default()), 0);
+    }
+
+    #[test]
+    fn test_confirm_replace() {
+        let mut state = EditorState::new(Lines::from("ab ab\nab"));
+        state.cursor = Index2::new(0, 1);
+
+        // Starts at the match after the cursor and wraps around.
+        assert_eq!(state.start_replace("ab", "xyz", ReplaceFlags::default()), 3);
+        assert_eq!(state.cursor, Index2::new(0, 3));
+
+        assert_eq!(state.confirm_replace(ReplaceChoice::Yes), 2);
+        assert_eq!(state.lines, Lines::from("ab xyz\nab"));
+        assert_eq!(state.cursor, Index2::new(1, 0));
+
+        assert_eq!(state.confirm_replace(ReplaceChoice::No), 1);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        assert_eq!(state.confirm_replace(ReplaceChoice::Yes), 0);
+        assert_eq!(state.lines, Lines::from("xyz xyz\nab"));
+        assert!(!state.is_replacing());
+
+        // The whole run is undone at once.
+        state.undo();
+        assert_eq!(state.lines, Lines::from("ab ab\nab"));
+    }
+
+    #[test]
+    fn test_confirm_replace_shifts_matches() {
+        let mut state = EditorState::new(Lines::from("aaa"));
+        state.start_replace("a", "bb", ReplaceFlags::default());
+        state.confirm_replace(ReplaceChoice::Yes);
+        assert_eq!(state.cursor, Index2::new(0, 2));
+
+        assert_eq!(state.confirm_replace(ReplaceChoice::All), 0);
+        assert_eq!(state.lines, Lines::from("bbbbbb"));
+
+        state.cursor = Index2::new(0, 0);
+        state.start_replace("b", "c", ReplaceFlags::default());
+        assert_eq!(
+            state.replace_prompt(),
+            Some("replace with c (y/n/a/q)?".into())
+        );
+        state.confirm_replace(ReplaceChoice::Yes);
+        state.confirm_replace(ReplaceChoice::Quit);
+        assert_eq!(state.lines, Lines::from("cbbbbb"));
+        assert_eq!(state.replace_prompt(), None);
+    }
+}
@@ -1,27 +1,139 @@
-use jagged::Index2;
+use std::ops::RangeInclusive;
+
+use jagged::{index::RowIndex, Index2};
+use regex::{Regex, RegexBuilder};
 
 use crate::Lines;
 
 use super::selection::Selection;
 
+/// Options of how the search pattern is matched.
+///
+/// # Example
+///
+/// ```
+/// use edtui::{EditorState, Lines, SearchOptions};
+///
+/// let mut state = EditorState::new(Lines::from("Hello World"));
+/// state.set_search_options(SearchOptions {
+///     regex: true,
+///     smart_case: true,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchOptions {
+    /// Interpret the pattern as a regular expression instead of literal text.
+    pub regex: bool,
+
+    /// Match regardless of case.
+    pub ignore_case: bool,
+
+    /// Match regardless of case, unless the pattern contains an uppercase
+    /// character. Overrides [`ignore_case`](Self::ignore_case).
+    pub smart_case: bool,
+
+    /// Match only whole words.
+    pub whole_word: bool,
+}
+
+/// A search option that can be toggled, e.g. with a keybinding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOption {
+    /// Toggles [`SearchOptions::regex`].
+    Regex,
+    /// Toggles [`SearchOptions::ignore_case`].
+    IgnoreCase,
+    /// Toggles [`SearchOptions::smart_case`].
+    SmartCase,
+    /// Toggles [`SearchOptions::whole_word`].
+    WholeWord,
+}
+
+impl SearchOptions {
+    /// Turns the given option on or off.
+    pub fn toggle(&mut self, option: SearchOption) {
+        let value = match option {
+            SearchOption::Regex => &mut self.regex,
+            SearchOption::IgnoreCase => &mut self.ignore_case,
+            SearchOption::SmartCase => &mut self.smart_case,
+            SearchOption::WholeWord => &mut self.whole_word,
+        };
+        *value = !*value;
+    }
+
+    /// Compiles the pattern with these options. Returns `None` if the pattern
+    /// is empty or an invalid regular expression.
+    fn compile(&self, pattern: &str) -> Option<Regex> {
+        if pattern.is_empty() {
+            return None;
+        }
+        let ignore_case = if self.smart_case {
+            !pattern.chars().any(char::is_uppercase)
+        } else {
+            self.ignore_case
+        };
+        let mut pattern = if self.regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        if self.whole_word {
+            pattern = format!(r"\b(?:{pattern})\b");
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .ok()
+    }
+}
+
+/// A match of the search pattern within a single line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SearchMatch {
+    /// The position of the first character.
+    pub(crate) start: Index2,
+    /// The position of the last character.
+    pub(crate) end: Index2,
+}
+
+impl SearchMatch {
+    /// The number of characters of the match.
+    pub(crate) fn len(&self) -> usize {
+        self.end.col + 1 - self.start.col
+    }
+}
+
+/// The maximum number of patterns kept in the search history.
+const MAX_HISTORY: usize = 100;
+
 /// Represents the state of a search operation
 /// Including the search pattern, matched indices and selected index.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub(crate) struct SearchState {
     pub(crate) start_cursor: Index2,
     pub(crate) pattern: String,
-    pub(crate) matches: Vec<Index2>,
+    pub(crate) options: SearchOptions,
+    pub(crate) matches: Vec<SearchMatch>,
     pub(crate) selected_index: Option<usize>,
+    /// Whether the search goes backward (`?`, `#`) rather than forward.
+    pub(crate) backward: bool,
+    /// Whether only whole words match, regardless of the options (`*`, `#`).
+    pub(crate) whole_word: bool,
+    /// The committed patterns, oldest first.
+    history: Vec<String>,
+    /// The history entry shown while browsing, and the pattern typed before.
+    history_index: Option<usize>,
+    draft: String,
 }
 
 impl SearchState {
-    pub(crate) fn len(&self) -> usize {
-        self.pattern.len()
-    }
-
     pub(crate) fn start(&mut self, start_cursor: Index2) {
         self.clear();
         self.start_cursor = start_cursor;
+        self.backward = false;
+        self.whole_word = false;
+        self.history_index = None;
     }
 
     pub(crate) fn clear(&mut self) {
@@ -30,11 +142,10 @@ impl SearchState {
     }
 
     pub(crate) fn trigger_search(&mut self, lines: &Lines) {
-        let pattern: Vec<char> = self.pattern.chars().collect();
-        self.matches = lines
-            .match_indices(&pattern)
-            .map(|(_, index)| index)
-            .collect();
+        let rows = 0..=lines.len().saturating_sub(1);
+        let mut options = self.options;
+        options.whole_word |= self.whole_word;
+        self.matches = find_matches(lines, rows, &self.pattern, &options, false);
     }
 
     pub(crate) fn push_char(&mut self, ch: char) {
@@ -45,26 +156,115 @@ impl SearchState {
         self.pattern.pop();
     }
 
+    /// Selects the first match in the search direction from the start
+    /// cursor, wrapping around the buffer.
     pub(crate) fn first(&mut self) -> Option<&Index2> {
-        for (i, index) in self.matches.iter().enumerate() {
-            if index >= &self.start_cursor {
+        if self.backward {
+            let i = self
+                .matches
+                .iter()
+                .rposition(|m| m.start < self.start_cursor)
+                .or_else(|| self.matches.len().checked_sub(1))?;
+            self.selected_index = Some(i);
+            return Some(&self.matches[i].start);
+        }
+        for (i, m) in self.matches.iter().enumerate() {
+            if m.start >= self.start_cursor {
                 self.selected_index = Some(i);
-                return Some(index);
+                return Some(&m.start);
             }
         }
         match self.matches.first() {
-            Some(index) => {
+            Some(m) => {
                 self.selected_index = Some(0);
-                Some(index)
+                Some(&m.start)
             }
             None => None,
         }
     }
 
     pub(crate) fn current(&self) -> Option<&Index2> {
+        self.current_match().map(|m| &m.start)
+    }
+
+    pub(crate) fn current_match(&self) -> Option<&SearchMatch> {
         self.selected_index.and_then(|i| self.matches.get(i))
     }
 
+    /// Returns the 1-based position of the selected match and the number
+    /// of matches, or `None` if the pattern is empty.
+    pub(crate) fn counter(&self) -> Option<(usize, usize)> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        let position = self
+            .selected_index
+            .filter(|&i| i < self.matches.len())
+            .map_or(0, |i| i + 1);
+        Some((position, self.matches.len()))
+    }
+
+    /// Selects the next match in the search direction.
+    pub(crate) fn forward(&mut self) -> Option<&Index2> {
+        if self.backward {
+            self.previous()
+        } else {
+            self.next()
+        }
+    }
+
+    /// Selects the next match against the search direction.
+    pub(crate) fn reverse(&mut self) -> Option<&Index2> {
+        if self.backward {
+            self.next()
+        } else {
+            self.previous()
+        }
+    }
+
+    /// Adds the pattern to the end of the history, removing older copies.
+    pub(crate) fn push_history(&mut self) {
+        self.history_index = None;
+        if self.pattern.is_empty() {
+            return;
+        }
+        self.history.retain(|pattern| pattern != &self.pattern);
+        self.history.push(self.pattern.clone());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    /// Replaces the pattern with the previous (older) history entry.
+    pub(crate) fn history_previous(&mut self) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(i) => i - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.pattern.clone();
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        self.pattern = self.history[index].clone();
+    }
+
+    /// Replaces the pattern with the next (newer) history entry, or with the
+    /// typed pattern after the newest entry.
+    pub(crate) fn history_next(&mut self) {
+        let Some(index) = self.history_index else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.history_index = Some(index + 1);
+            self.pattern = self.history[index + 1].clone();
+        } else {
+            self.history_index = None;
+            self.pattern = std::mem::take(&mut self.draft);
+        }
+    }
+
     pub(crate) fn next(&mut self) -> Option<&Index2> {
         if let Some(selected) = self.selected_index {
             let new_selected = if selected + 1 >= self.matches.len() {
@@ -73,7 +273,7 @@ impl SearchState {
                 selected + 1
             };
             self.selected_index = Some(new_selected);
-            return self.matches.get(new_selected);
+            return self.matches.get(new_selected).map(|m| &m.start);
         }
         None
     }
@@ -90,18 +290,130 @@ impl SearchState {
         };
 
         self.selected_index = Some(new_selected);
-        self.matches.get(new_selected)
+        self.matches.get(new_selected).map(|m| &m.start)
     }
 }
 
 impl From<&SearchState> for Option<Selection> {
     fn from(value: &SearchState) -> Self {
         value
-            .selected_index
-            .and_then(|index| value.matches.get(index))
-            .map(|&start| {
-                let end = Index2::new(start.row, start.col + value.len().saturating_sub(1));
-                Selection::new(start, end)
-            })
+            .current_match()
+            .map(|m| Selection::new(m.start, m.end))
+    }
+}
+
+/// Finds the non-overlapping, non-empty matches of `pattern` within `rows`,
+/// in order. A match never spans lines.
+pub(crate) fn find_matches(
+    lines: &Lines,
+    rows: RangeInclusive<usize>,
+    pattern: &str,
+    options: &SearchOptions,
+    first_per_line: bool,
+) -> Vec<SearchMatch> {
+    let mut matches = Vec::new();
+    let Some(regex) = options.compile(pattern) else {
+        return matches;
+    };
+    for row in rows {
+        let Some(line) = lines.get(RowIndex::new(row)) else {
+            break;
+        };
+        let text: String = line.iter().collect();
+        let char_col = |byte: usize| text[..byte].chars().count();
+        for found in regex.find_iter(&text).filter(|m| !m.is_empty()) {
+            let start = char_col(found.start());
+            let end = char_col(found.end()) - 1;
+            matches.push(SearchMatch {
+                start: Index2::new(row, start),
+                end: Index2::new(row, end),
+            });
+            if first_per_line {
+                break;
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(text: &str, pattern: &str, options: SearchOptions) -> Vec<(usize, usize, usize)> {
+        let rows = 0..=text.lines().count();
+        find_matches(&Lines::from(text), rows, pattern, &options, false)
+            .into_iter()
+            .map(|m| (m.start.row, m.start.col, m.end.col))
+            .collect()
+    }
+
+    #[test]
+    fn test_search_options() {
+        let text = "Foo föo foobar\nfoo";
+        let literal = SearchOptions::default();
+        assert_eq!(search(text, "foo", literal), vec![(0, 8, 10), (1, 0, 2)]);
+        assert_eq!(search(text, "f.o", literal), vec![]);
+
+        let ignore_case = SearchOptions {
+            ignore_case: true,
+            ..literal
+        };
+        assert_eq!(
+            search(text, "FOO", ignore_case),
+            vec![(0, 0, 2), (0, 8, 10), (1, 0, 2)]
+        );
+
+        let smart_case = SearchOptions {
+            smart_case: true,
+            ..literal
+        };
+        assert_eq!(search(text, "foo", smart_case).len(), 3);
+        assert_eq!(search(text, "Foo", smart_case), vec![(0, 0, 2)]);
+        let both = SearchOptions {
+            ignore_case: true,
+            ..smart_case
+        };
+        assert_eq!(search(text, "Foo", both), vec![(0, 0, 2)]);
+
+        let regex = SearchOptions {
+            regex: true,
+            ..literal
+        };
+        assert_eq!(
+            search(text, "f.o", regex),
+            vec![(0, 4, 6), (0, 8, 10), (1, 0, 2)]
+        );
+        assert_eq!(
+            search(text, "o+b?", regex),
+            vec![(0, 1, 2), (0, 6, 6), (0, 9, 11), (1, 1, 2)]
+        );
+        assert_eq!(search(text, "(", regex), vec![]);
+
+        let whole_word = SearchOptions {
+            whole_word: true,
+            ..regex
+        };
+        assert_eq!(search(text, "f.o", whole_word), vec![(0, 4, 6), (1, 0, 2)]);
+    }
+
+    #[test]
+    fn test_search_selection() {
+        let mut state = SearchState {
+            pattern: String::from("o+"),
+            options: SearchOptions {
+                regex: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        state.trigger_search(&Lines::from("a foo"));
+        state.first();
+
+        let selection: Option<Selection> = (&state).into();
+        assert_eq!(
+            selection,
+            Some(Selection::new(Index2::new(0, 3), Index2::new(0, 4)))
+        );
     }
 }
@@ -1,14 +1,20 @@
 use std::cmp::Ordering;
+use std::ops::Range;
 
 use jagged::index::RowIndex;
 
-use crate::{Index2, Lines};
+use crate::{clipboard::RegisterKind, Index2, Lines};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Selection {
     pub start: Index2,
     pub end: Index2,
     pub line_mode: bool,
+    /// Selects the rectangle spanned by `start` and `end` (visual block mode).
+    pub block_mode: bool,
+    /// Extends every row of a block selection to the end of its line (Vim `$`
+    /// in visual block mode).
+    pub to_line_end: bool,
     pub anchor: Option<Index2>,
 }
 
@@ -19,10 +25,44 @@ impl Selection {
             start,
             end,
             line_mode: false,
+            block_mode: false,
+            to_line_end: false,
             anchor: None,
         }
     }
 
+    /// Returns how text copied from the selection is pasted.
+    #[must_use]
+    pub fn kind(&self) -> RegisterKind {
+        if self.line_mode {
+            RegisterKind::Linewise
+        } else if self.block_mode {
+            RegisterKind::Blockwise
+        } else {
+            RegisterKind::Charwise
+        }
+    }
+
+    #[must_use]
+    pub fn block_mode(mut self) -> Self {
+        self.block_mode = true;
+        self
+    }
+
+    /// Returns the top-left and bottom-right corner of a block selection.
+    #[must_use]
+    pub fn block_corners(&self) -> (Index2, Index2) {
+        let (top, bottom) = (
+            self.start.row.min(self.end.row),
+            self.start.row.max(self.end.row),
+        );
+        let (left, right) = (
+            self.start.col.min(self.end.col),
+            self.start.col.max(self.end.col),
+        );
+        (Index2::new(top, left), Index2::new(bottom, right))
+    }
+
     pub fn line_mode(mut self) -> Self {
         self.line_mode = true;
         self.anchor = Some(self.start);
@@ -34,6 +74,12 @@ impl Selection {
         if self.line_mode {
             return self.contains_row(pos.row);
         }
+        if self.block_mode {
+            let (top_left, bottom_right) = self.block_corners();
+            return self.contains_row(pos.row)
+                && pos.col >= top_left.col
+                && (self.to_line_end || pos.col <= bottom_right.col);
+        }
 
         let (start, end) = if self.start < self.end {
             (&self.start, &self.end)
@@ -102,10 +148,16 @@ impl Selection {
             st.col = 0;
             en.col = lines.last_col_index(en.row);
 
-            let mut lines = lines.copy_range(st..=en);
-            lines.insert(RowIndex::new(0), vec![]);
-
-            return lines;
+            return lines.copy_range(st..=en);
+        }
+        if self.block_mode {
+            let ranges = self.block_ranges(lines);
+            let block = ranges.into_iter().map(|(row, range)| {
+                lines
+                    .get(RowIndex::new(row))
+                    .map_or_else(Vec::new, |chars| chars[range].to_vec())
+            });
+            return Lines::new(block.collect::<Vec<_>>());
         }
 
         lines.copy_range(self.start()..=self.end())
@@ -118,15 +170,39 @@ impl Selection {
             let st = Index2::new(self.start().row, 0);
             let en = Index2::new(self.end().row, lines.last_col_index(self.end().row));
 
-            let mut lines = lines.extract(st..=en);
-            lines.insert(RowIndex::new(0), vec![]);
-
-            return lines;
+            return lines.extract(st..=en);
+        }
+        if self.block_mode {
+            let ranges = self.block_ranges(lines);
+            let block = ranges.into_iter().map(|(row, range)| {
+                lines
+                    .get_mut(RowIndex::new(row))
+                    .map_or_else(Vec::new, |chars| chars.drain(range).collect())
+            });
+            return Lines::new(block.collect::<Vec<_>>());
         }
 
         lines.extract(self.start()..=self.end())
     }
 
+    /// Returns the column range covered by a block selection in each of its
+    /// rows, clamped to the row lengths.
+    pub(crate) fn block_ranges(&self, lines: &Lines) -> Vec<(usize, Range<usize>)> {
+        let (top_left, bottom_right) = self.block_corners();
+        (top_left.row..=bottom_right.row)
+            .filter_map(|row| {
+                let len = lines.len_col(row)?;
+                let end = if self.to_line_end {
+                    len
+                } else {
+                    (bottom_right.col + 1).min(len)
+                };
+                let range = top_left.col.min(len)..end;
+                Some((row, range))
+            })
+            .collect()
+    }
+
     /// Returns the start and end column of the selection in the given row.
     /// If the selection does not intersect with the row, the function returns None.
     #[must_use]
@@ -135,6 +211,17 @@ impl Selection {
         row_index: usize,
         row_len: usize,
     ) -> Option<(usize, usize)> {
+        if self.block_mode {
+            let (top_left, bottom_right) = self.block_corners();
+            if !self.contains_row(row_index) || top_left.col > row_len {
+                return None;
+            }
+            if self.to_line_end {
+                return Some((top_left.col, row_len));
+            }
+            return Some((top_left.col, bottom_right.col.min(row_len)));
+        }
+
         let (start, end) = (self.start(), self.end());
 
         let start_col = match start.row.cmp(&row_index) {
@@ -181,6 +268,8 @@ pub(crate) fn set_selection_with_lines(
             let end_col = lines.len_col(bottom_row).unwrap_or(0).saturating_sub(1);
             sel.end = Index2::new(bottom_row, end_col);
         } else {
+            // Only vertical motions keep a block extended to the line ends.
+            sel.to_line_end &= sel.end.col == index.col;
             sel.end = index;
         }
     }
@@ -209,6 +298,23 @@ mod tests {
         assert_eq!(selection.copy_from(&data), Lines::from("\nWo"));
     }
 
+    #[test]
+    fn test_block_selection() {
+        let mut data = Lines::from("Hello\nWo\nWorld");
+        let selection = Selection::new(Index2::new(2, 3), Index2::new(0, 1)).block_mode();
+
+        assert!(selection.contains(&Index2::new(1, 2)));
+        assert!(!selection.contains(&Index2::new(1, 4)));
+        assert_eq!(selection.get_selected_columns_in_row(1, 2), Some((1, 2)));
+        assert_eq!(selection.copy_from(&data), Lines::from("ell\no\norl"));
+
+        assert_eq!(
+            selection.extract_from(&mut data),
+            Lines::from("ell\no\norl")
+        );
+        assert_eq!(data, Lines::from("Ho\nW\nWd"));
+    }
+
     #[test]
     fn test_selection_columns_in_row() {
         // given
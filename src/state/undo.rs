@@ -1,80 +1,973 @@
-//! This module contains a "brute-force" implementation of undo and redo.
-//! It stores the entire editor state at each action.
+//! Undo and redo based on recorded edits.
 //!
-//! This approach works for basic undo/redo needs, but its not the most efficient.
-//! In the long run, this should be replaced with an action-based mechanism.
+//! Instead of cloning the whole buffer, each undo step records the
+//! [`Edit`]s made during it: the text deleted and inserted at a position.
+//! They are collected by the [`ChangeLog`] as the text changes, and edits
+//! that adjoin are merged, so that typing a word is a single edit.
+//!
+//! The steps form a tree with a timestamp on each node: a change after an
+//! undo starts a new branch, and the undone changes stay reachable with
+//! [`EditorState::undo_to`] or chronologically with
+//! [`EditorState::undo_earlier`] and [`EditorState::undo_later`].
+//!
+//! A step starts with [`EditorState::capture`] and ends at the next one,
+//! unless it is merged into an open group. Groups are opened explicitly with
+//! [`EditorState::begin_undo_group`], by a [`Composed`] action and by a whole
+//! insert session.
+//!
+//! [`Composed`]: crate::actions::Composed
+//! [`ChangeLog`]: super::changes::ChangeLog
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use jagged::index::RowIndex;
+
+use super::{changes::end_of, mode::EditorMode};
 use crate::{EditorState, Index2, Lines};
 
-#[derive(Debug, Clone)]
-pub(crate) struct Stack {
-    inner: Vec<UndoState>,
-    max_size: usize,
+#[cfg(feature = "serde")]
+mod persist;
+#[cfg(feature = "serde")]
+pub use persist::{UndoRestoreError, UndoSnapshot};
+
+/// The default maximum number of undo steps.
+const DEFAULT_DEPTH: usize = 100;
+
+/// A change of the text: `deleted` is replaced by `inserted` at `start`.
+/// Line breaks are `'\n'`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Edit {
+    pub(crate) start: Index2,
+    pub(crate) deleted: String,
+    pub(crate) inserted: String,
 }
 
-impl Stack {
-    pub(crate) fn new() -> Self {
+impl Edit {
+    /// Returns the edit that turns `old` into `new`, or `None` if the texts
+    /// are equal.
+    pub(crate) fn between(old: &Lines, new: &Lines) -> Option<Self> {
+        let (first, old_end, new_end) = changed_rows(old, new)?;
+        let old_text = join_rows(old, first, old_end);
+        let new_text = join_rows(new, first, new_end);
+
+        let prefix = common_len(old_text.iter(), new_text.iter());
+        let max_suffix = old_text.len().min(new_text.len()) - prefix;
+        let suffix = common_len(old_text.iter().rev(), new_text.iter().rev()).min(max_suffix);
+        if old_text.len() == new_text.len() && prefix == old_text.len() {
+            return None;
+        }
+
+        let before = &old_text[..prefix];
+        let row = first + before.iter().filter(|&&c| c == '\n').count();
+        let col = match before.iter().rposition(|&c| c == '\n') {
+            Some(i) => prefix - i - 1,
+            None => prefix,
+        };
+        Some(Self {
+            start: Index2::new(row, col),
+            deleted: old_text[prefix..old_text.len() - suffix].iter().collect(),
+            inserted: new_text[prefix..new_text.len() - suffix].iter().collect(),
+        })
+    }
+
+    /// Returns the edit that reverts this one.
+    pub(crate) fn inverse(&self) -> Self {
         Self {
-            inner: Vec::new(),
-            max_size: 100,
+            start: self.start,
+            deleted: self.inserted.clone(),
+            inserted: self.deleted.clone(),
+        }
+    }
+
+    /// Merges `next`, an edit made right after this one, into it if `next`
+    /// changes the inserted text or adjoins it. Returns false if the edits
+    /// are apart.
+    pub(crate) fn merge(&mut self, next: &Edit) -> bool {
+        let inserted_end = end_of(self.start, &self.inserted);
+        let next_end = end_of(next.start, &next.deleted);
+        if self.start <= next.start && next_end <= inserted_end {
+            let offset = offset_in(&self.inserted, self.start, next.start);
+            let mut chars: Vec<char> = self.inserted.chars().collect();
+            let deleted = next.deleted.chars().count();
+            chars.splice(offset..offset + deleted, next.inserted.chars());
+            self.inserted = chars.into_iter().collect();
+        } else if next_end == self.start {
+            self.start = next.start;
+            self.deleted.insert_str(0, &next.deleted);
+            self.inserted.insert_str(0, &next.inserted);
+        } else if next.start == inserted_end {
+            self.deleted.push_str(&next.deleted);
+            self.inserted.push_str(&next.inserted);
+        } else {
+            return false;
         }
+        true
     }
 
-    pub(crate) fn pop(&mut self) -> Option<UndoState> {
-        self.inner.pop()
+    /// Returns true if the edit does not change the text.
+    pub(crate) fn is_noop(&self) -> bool {
+        self.deleted == self.inserted
     }
 
-    pub(crate) fn push(&mut self, value: UndoState) {
-        self.inner.push(value);
-        if self.len() > self.max_size {
-            self.remove(0);
+    /// Applies the edit to the lines.
+    pub(crate) fn apply(&self, lines: &mut Lines) {
+        let deleted: Vec<char> = self.deleted.chars().collect();
+        let deleted_rows = deleted.iter().filter(|&&c| c == '\n').count();
+        let end_col = match deleted.iter().rposition(|&c| c == '\n') {
+            Some(i) => deleted.len() - i - 1,
+            None => self.start.col + deleted.len(),
+        };
+
+        let mut tail = lines.extract_rows(self.start.row.min(lines.len())..);
+        let removed = tail.extract_rows(..(deleted_rows + 1).min(tail.len()));
+        let mut text: Vec<char> = match removed.get(RowIndex::new(0)) {
+            Some(row) => row[..self.start.col.min(row.len())].to_vec(),
+            None => Vec::new(),
+        };
+        text.extend(self.inserted.chars());
+        if let Some(row) = removed.get(RowIndex::new(deleted_rows)) {
+            text.extend_from_slice(&row[end_col.min(row.len())..]);
         }
+        for row in text.split(|&c| c == '\n') {
+            lines.push(row.to_vec());
+        }
+        lines.append(&mut tail);
     }
 
-    fn len(&mut self) -> usize {
-        self.inner.len()
+    /// The number of bytes of text held by the edit.
+    fn size(&self) -> usize {
+        self.deleted.len() + self.inserted.len()
     }
+}
 
-    fn remove(&mut self, index: usize) {
-        self.inner.remove(index);
+/// Returns the offset in characters of `pos` in `text`, which starts at
+/// `start`.
+fn offset_in(text: &str, start: Index2, pos: Index2) -> usize {
+    let Some(rows) = (pos.row - start.row).checked_sub(1) else {
+        return pos.col - start.col;
+    };
+    let line_start = text
+        .chars()
+        .enumerate()
+        .filter(|&(_, ch)| ch == '\n')
+        .nth(rows)
+        .map_or(0, |(i, _)| i + 1);
+    line_start + pos.col
+}
+
+/// Returns the first changed row and the ends of the changed rows in `old`
+/// and `new`. If either range is empty, it is widened by a neighbouring row,
+/// so that the changed text can be joined with line breaks.
+fn changed_rows(old: &Lines, new: &Lines) -> Option<(usize, usize, usize)> {
+    let min = old.len().min(new.len());
+    let mut first = (0..min)
+        .find(|&row| row_of(old, row) != row_of(new, row))
+        .unwrap_or(min);
+    if first == min && old.len() == new.len() {
+        return None;
+    }
+    let mut suffix = (0..min - first)
+        .take_while(|i| row_of(old, old.len() - 1 - i) == row_of(new, new.len() - 1 - i))
+        .count();
+    if first == old.len() - suffix || first == new.len() - suffix {
+        if first > 0 {
+            first -= 1;
+        } else {
+            suffix = suffix.saturating_sub(1);
+        }
+    }
+    Some((first, old.len() - suffix, new.len() - suffix))
+}
+
+fn row_of(lines: &Lines, row: usize) -> &[char] {
+    lines.get(RowIndex::new(row)).map_or(&[], Vec::as_slice)
+}
+
+fn join_rows(lines: &Lines, start: usize, end: usize) -> Vec<char> {
+    let mut text = Vec::new();
+    for row in start..end {
+        if row > start {
+            text.push('\n');
+        }
+        text.extend_from_slice(row_of(lines, row));
     }
+    text
 }
 
+fn common_len<'a>(a: impl Iterator<Item = &'a char>, b: impl Iterator<Item = &'a char>) -> usize {
+    a.zip(b).take_while(|(a, b)| a == b).count()
+}
+
+/// The cursor around an undo step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Checkpoint {
+    pub(crate) cursor: Index2,
+}
+
+impl Checkpoint {
+    fn new(state: &EditorState) -> Self {
+        Self {
+            cursor: state.cursor,
+        }
+    }
+
+    fn restore(&self, state: &mut EditorState) {
+        state.cursor = self.cursor;
+    }
+}
+
+/// A single undo step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UndoStep {
+    /// The edits of the step, in the order they were made.
+    pub(crate) edits: Vec<Edit>,
+    /// The state before the edit, restored by undo.
+    pub(crate) before: Checkpoint,
+    /// The state after the edit, restored by redo.
+    pub(crate) after: Checkpoint,
+}
+
+impl UndoStep {
+    /// The approximate number of bytes held by the step.
+    fn size(&self) -> usize {
+        std::mem::size_of::<UndoNode>() + self.edits.iter().map(Edit::size).sum::<usize>()
+    }
+}
+
+/// A state of the text in the undo tree. Every node but the root holds the
+/// step from the text of its parent to its own.
 #[derive(Debug, Clone)]
-pub(crate) struct UndoState {
-    lines: Lines,
-    cursor: Index2,
+pub(crate) struct UndoNode {
+    pub(crate) parent: Option<usize>,
+    pub(crate) children: Vec<usize>,
+    /// The child that redo moves to, the one visited last.
+    pub(crate) redo_child: Option<usize>,
+    pub(crate) step: Option<UndoStep>,
+    pub(crate) time: SystemTime,
+}
+
+impl UndoNode {
+    fn new(parent: Option<usize>, step: Option<UndoStep>) -> Self {
+        Self {
+            parent,
+            children: Vec::new(),
+            redo_child: None,
+            step,
+            time: SystemTime::now(),
+        }
+    }
+}
+
+/// A state in the undo history, as listed by [`EditorState::undo_history`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoEntry {
+    /// The number of the change that led to this state. Changes are numbered
+    /// in the order they were made, starting at 1 for the first change of
+    /// the original text.
+    pub seq: usize,
+
+    /// The change this one was made on top of, or `None` for the oldest
+    /// state kept in the history.
+    pub parent: Option<usize>,
+
+    /// When the change was made.
+    pub time: SystemTime,
+
+    /// Where the changed text starts, or `None` for the oldest state.
+    pub position: Option<Index2>,
+}
+
+/// The undo history as a tree: making a change after an undo starts a new
+/// branch instead of dropping the undone changes. Nodes are keyed by their
+/// number, which orders them chronologically.
+#[derive(Debug, Clone)]
+pub(crate) struct UndoHistory {
+    /// The state before the step in progress, if any.
+    pending: Option<Checkpoint>,
+    /// Whether captures are merged into the step in progress.
+    merging: bool,
+    /// The number of open undo groups.
+    groups: usize,
+    /// Whether an insert session is merged into one step.
+    insert_session: bool,
+    pub(crate) nodes: BTreeMap<usize, UndoNode>,
+    pub(crate) root: usize,
+    /// The node of the current text.
+    pub(crate) current: usize,
+    /// The node of the saved text, or `None` if undo can not return to it.
+    pub(crate) saved: Option<usize>,
+    next_seq: usize,
+    max_depth: usize,
+    max_memory: Option<usize>,
+    memory: usize,
+}
+
+impl UndoHistory {
+    pub(crate) fn new() -> Self {
+        Self {
+            pending: None,
+            merging: false,
+            groups: 0,
+            insert_session: false,
+            nodes: BTreeMap::from([(0, UndoNode::new(None, None))]),
+            root: 0,
+            current: 0,
+            saved: Some(0),
+            next_seq: 1,
+            max_depth: DEFAULT_DEPTH,
+            max_memory: None,
+            memory: 0,
+        }
+    }
+
+    /// Starts or ends an insert session, which is a single undo step.
+    pub(crate) fn set_insert_session(&mut self, active: bool) {
+        if active == self.insert_session {
+            return;
+        }
+        self.insert_session = active;
+        if active {
+            self.merging = self.pending.is_some();
+        } else if self.groups == 0 {
+            self.merging = false;
+        }
+    }
+
+    /// Adds a step as a child of the current node and moves to it.
+    fn push(&mut self, step: UndoStep) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.memory += step.size();
+        self.nodes
+            .insert(seq, UndoNode::new(Some(self.current), Some(step)));
+        if let Some(parent) = self.nodes.get_mut(&self.current) {
+            parent.children.push(seq);
+            parent.redo_child = Some(seq);
+        }
+        self.current = seq;
+        self.trim();
+    }
+
+    /// Returns the node and its ancestors up to the root.
+    fn path(&self, seq: usize) -> Vec<usize> {
+        let mut path = vec![seq];
+        let mut seq = seq;
+        while let Some(parent) = self.nodes.get(&seq).and_then(|node| node.parent) {
+            path.push(parent);
+            seq = parent;
+        }
+        path
+    }
+
+    /// Drops the oldest changes until the history fits its limits.
+    fn trim(&mut self) {
+        while self.nodes.len() - 1 > self.max_depth
+            || self.max_memory.is_some_and(|max| self.memory > max)
+        {
+            let root = self.root;
+            let Some(&oldest) = self.nodes[&root].children.iter().min() else {
+                break;
+            };
+            if !self.path(self.current).contains(&oldest) {
+                self.remove_subtree(oldest);
+                if let Some(node) = self.nodes.get_mut(&root) {
+                    node.children.retain(|&child| child != oldest);
+                    node.redo_child = node.redo_child.filter(|&child| child != oldest);
+                }
+                continue;
+            }
+
+            // The oldest change leads to the current text, so its text becomes
+            // the oldest state kept.
+            let siblings = self.nodes[&root].children.clone();
+            for sibling in siblings.into_iter().filter(|&child| child != oldest) {
+                self.remove_subtree(sibling);
+            }
+            self.nodes.remove(&root);
+            if let Some(node) = self.nodes.get_mut(&oldest) {
+                node.parent = None;
+                if let Some(step) = node.step.take() {
+                    self.memory -= step.size();
+                }
+            }
+            self.root = oldest;
+        }
+    }
+
+    fn remove_subtree(&mut self, seq: usize) {
+        let mut stack = vec![seq];
+        while let Some(seq) = stack.pop() {
+            if let Some(node) = self.nodes.remove(&seq) {
+                if let Some(step) = node.step {
+                    self.memory -= step.size();
+                }
+                stack.extend(node.children);
+            }
+        }
+    }
 }
 
 impl EditorState {
+    /// Starts a new undo step, ending the one in progress. Undo restores the
+    /// cursor at this point, so call it before the change moves the cursor.
     pub(crate) fn capture(&mut self) {
-        let editor_state = UndoState {
-            lines: self.lines.clone(),
-            cursor: self.cursor,
+        if self.history.merging {
+            return;
+        }
+        self.commit_undo_step();
+        self.history.pending = Some(Checkpoint::new(self));
+        self.history.merging = self.history.groups > 0;
+    }
+
+    /// Ends the undo step in progress, unless it is merged into a group or
+    /// text is being inserted or replaced.
+    pub(crate) fn finish_undo_step(&mut self) {
+        let history = &self.history;
+        let typing = matches!(self.mode, EditorMode::Insert | EditorMode::Replace);
+        if history.pending.is_none() {
+            self.drop_edits();
+        } else if !history.merging && !typing {
+            self.commit_undo_step();
+        }
+    }
+
+    /// Ends the undo step in progress. Changes made outside a step are not
+    /// recorded.
+    fn commit_undo_step(&mut self) {
+        self.history.merging = false;
+        let Some(before) = self.history.pending.take() else {
+            self.drop_edits();
+            return;
         };
-        self.undo.push(editor_state);
+        let edits = std::mem::take(&mut self.changes.edits);
+        if edits.is_empty() {
+            return;
+        }
+        let after = Checkpoint::new(self);
+        self.history.push(UndoStep {
+            edits,
+            before,
+            after,
+        });
     }
 
-    pub fn undo(&mut self) {
-        if let Some(prev) = self.undo.pop() {
-            let current = UndoState {
-                lines: self.lines.clone(),
-                cursor: self.cursor,
-            };
-            self.lines = prev.lines;
-            self.cursor = prev.cursor;
-            self.redo.push(current);
+    /// Forgets the edits made outside an undo step. Undo can not return to
+    /// the saved text anymore if there were any.
+    fn drop_edits(&mut self) {
+        if !self.changes.edits.is_empty() {
+            self.changes.edits.clear();
+            self.history.saved = None;
+        }
+    }
+
+    /// Undoes the change of the current node and moves to its parent.
+    fn undo_node(&mut self) {
+        let checkpoint = Checkpoint::new(self);
+        let seq = self.history.current;
+        let Some(node) = self.history.nodes.get_mut(&seq) else {
+            return;
+        };
+        let (Some(parent), Some(step)) = (node.parent, node.step.as_mut()) else {
+            return;
+        };
+        step.after = checkpoint;
+        let edits = step.edits.clone();
+        let before = step.before.clone();
+        for edit in edits.iter().rev().map(Edit::inverse) {
+            edit.apply(&mut self.lines);
+            self.changes.record(&edit);
+        }
+        if let Some(parent) = self.history.nodes.get_mut(&parent) {
+            parent.redo_child = Some(seq);
         }
+        self.history.current = parent;
+        before.restore(self);
     }
 
+    /// Redoes the change of a child of the current node and moves to it.
+    fn redo_node(&mut self, seq: usize) {
+        let Some(step) = self.history.nodes.get(&seq).and_then(|n| n.step.as_ref()) else {
+            return;
+        };
+        let (edits, after) = (step.edits.clone(), step.after.clone());
+        for edit in &edits {
+            edit.apply(&mut self.lines);
+            self.changes.record(edit);
+        }
+        if let Some(parent) = self.history.nodes.get_mut(&self.history.current) {
+            parent.redo_child = Some(seq);
+        }
+        self.history.current = seq;
+        after.restore(self);
+    }
+
+    /// Undoes the last change and restores the cursor from before it.
+    pub fn undo(&mut self) {
+        self.commit_undo_step();
+        self.undo_node();
+    }
+
+    /// Redoes the last undone change and restores the cursor from before the
+    /// undo. After undoing into a branch point, this redoes
+    /// the branch visited last.
     pub fn redo(&mut self) {
-        if let Some(prev) = self.redo.pop() {
-            let current = UndoState {
-                lines: self.lines.clone(),
-                cursor: self.cursor,
-            };
-            self.lines = prev.lines;
-            self.cursor = prev.cursor;
-            self.undo.push(current);
+        self.commit_undo_step();
+        let Some(node) = self.history.nodes.get(&self.history.current) else {
+            return;
+        };
+        if let Some(child) = node
+            .redo_child
+            .or_else(|| node.children.iter().max().copied())
+        {
+            self.redo_node(child);
+        }
+    }
+
+    /// Returns true if there is a change to undo.
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        self.history.current != self.history.root
+            || (self.history.pending.is_some() && !self.changes.edits.is_empty())
+    }
+
+    /// Returns true if there is an undone change to redo.
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        self.history
+            .nodes
+            .get(&self.history.current)
+            .is_some_and(|node| !node.children.is_empty())
+    }
+
+    /// Returns the number of the change that led to the current text, or 0
+    /// for the original text.
+    #[must_use]
+    pub fn undo_seq(&self) -> usize {
+        self.history.current
+    }
+
+    /// Returns every state in the undo history, in the order the changes
+    /// were made, e.g. to show a history panel. Together with
+    /// [`undo_seq`](Self::undo_seq) and [`undo_to`](Self::undo_to) this
+    /// allows to browse all branches of the history.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Lines};
+    /// use edtui::actions::{DeleteLine, Execute};
+    ///
+    /// let mut state = EditorState::new(Lines::from("a\nb\nc"));
+    /// state.execute(DeleteLine(1));
+    /// state.undo();
+    /// state.cursor.row = 2;
+    /// state.execute(DeleteLine(1));
+    ///
+    /// // The undone change is kept on its own branch.
+    /// let history = state.undo_history();
+    /// assert_eq!(history.len(), 3);
+    /// assert_eq!(history[2].parent, Some(0));
+    /// assert_eq!(state.undo_seq(), 2);
+    ///
+    /// assert!(state.undo_to(1));
+    /// assert_eq!(state.lines, Lines::from("b\nc"));
+    /// ```
+    #[must_use]
+    pub fn undo_history(&self) -> Vec<UndoEntry> {
+        self.history
+            .nodes
+            .iter()
+            .map(|(&seq, node)| UndoEntry {
+                seq,
+                parent: node.parent,
+                time: node.time,
+                position: node
+                    .step
+                    .as_ref()
+                    .and_then(|step| step.edits.first())
+                    .map(|edit| edit.start),
+            })
+            .collect()
+    }
+
+    /// Moves to the text after change `seq`, undoing and redoing along the
+    /// branches of the history. Returns false if the change is not in the
+    /// history.
+    pub fn undo_to(&mut self, seq: usize) -> bool {
+        self.commit_undo_step();
+        if !self.history.nodes.contains_key(&seq) {
+            return false;
+        }
+        let up = self.history.path(self.history.current);
+        let down = self.history.path(seq);
+        let Some(&common) = up.iter().find(|seq| down.contains(seq)) else {
+            return false;
+        };
+        while self.history.current != common {
+            self.undo_node();
+        }
+        let down: Vec<usize> = down.into_iter().take_while(|&s| s != common).collect();
+        for &seq in down.iter().rev() {
+            self.redo_node(seq);
+        }
+        true
+    }
+
+    /// Moves `count` changes back in time, regardless of the branch they
+    /// were made on (`g-`).
+    pub fn undo_earlier(&mut self, count: usize) {
+        self.commit_undo_step();
+        let nodes = &self.history.nodes;
+        let target = nodes
+            .range(..self.history.current)
+            .rev()
+            .take(count)
+            .last()
+            .map(|(&seq, _)| seq);
+        if let Some(seq) = target {
+            self.undo_to(seq);
+        }
+    }
+
+    /// Moves `count` changes forward in time, regardless of the branch they
+    /// were made on (`g+`).
+    pub fn undo_later(&mut self, count: usize) {
+        self.commit_undo_step();
+        let nodes = &self.history.nodes;
+        let target = nodes
+            .range(self.history.current + 1..)
+            .take(count)
+            .last()
+            .map(|(&seq, _)| seq);
+        if let Some(seq) = target {
+            self.undo_to(seq);
+        }
+    }
+
+    /// Moves to the text as it was at `time`, or to the oldest state kept.
+    pub fn undo_to_time(&mut self, time: SystemTime) {
+        self.commit_undo_step();
+        let target = self
+            .history
+            .nodes
+            .iter()
+            .rev()
+            .find(|(_, node)| node.time <= time)
+            .map_or(self.history.root, |(&seq, _)| seq);
+        self.undo_to(target);
+    }
+
+    /// Returns when the change that led to the current text was made.
+    pub(crate) fn undo_time(&self) -> SystemTime {
+        self.history
+            .nodes
+            .get(&self.history.current)
+            .map_or_else(SystemTime::now, |node| node.time)
+    }
+
+    /// Opens an undo group. All changes until the matching
+    /// [`end_undo_group`](Self::end_undo_group) are undone as one step.
+    /// Groups can be nested.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Lines};
+    /// use edtui::actions::{DeleteLine, Execute};
+    ///
+    /// let mut state = EditorState::new(Lines::from("a\nb\nc"));
+    /// state.begin_undo_group();
+    /// state.execute(DeleteLine(1));
+    /// state.execute(DeleteLine(1));
+    /// state.end_undo_group();
+    ///
+    /// state.undo();
+    /// assert_eq!(state.lines, Lines::from("a\nb\nc"));
+    /// ```
+    pub fn begin_undo_group(&mut self) {
+        self.history.groups += 1;
+    }
+
+    /// Closes the innermost undo group.
+    pub fn end_undo_group(&mut self) {
+        self.history.groups = self.history.groups.saturating_sub(1);
+        if self.history.groups == 0 && !self.history.insert_session {
+            self.history.merging = false;
+        }
+    }
+
+    /// Sets the maximum number of changes kept in the undo history.
+    /// Defaults to 100.
+    pub fn set_undo_depth(&mut self, depth: usize) {
+        self.history.max_depth = depth;
+        self.history.trim();
+    }
+
+    /// Sets the approximate maximum number of bytes held by the undo
+    /// history. The oldest changes are dropped first. Defaults to no limit.
+    pub fn set_undo_memory_limit(&mut self, bytes: Option<usize>) {
+        self.history.max_memory = bytes;
+        self.history.trim();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::operator::{OperateSelection, Operator};
+    use crate::actions::{
+        Composed, DeleteChar, DeleteLine, DeleteSelection, Execute, InsertChar, LineBreak,
+        SwitchMode,
+    };
+    use crate::state::selection::Selection;
+
+    fn roundtrip(old: &str, new: &str) {
+        let (old, new) = (Lines::from(old), Lines::from(new));
+        let edit = Edit::between(&old, &new).unwrap();
+        let mut lines = old.clone();
+        edit.apply(&mut lines);
+        assert_eq!(lines, new);
+        edit.inverse().apply(&mut lines);
+        assert_eq!(lines, old);
+    }
+
+    #[test]
+    fn test_edit_between() {
+        let edit = Edit::between(&Lines::from("hello\nworld"), &Lines::from("hello\nwold"));
+        assert_eq!(
+            edit,
+            Some(Edit {
+                start: Index2::new(1, 2),
+                deleted: "r".into(),
+                inserted: String::new(),
+            })
+        );
+        assert_eq!(Edit::between(&Lines::from("a"), &Lines::from("a")), None);
+
+        roundtrip("a\nb\nc", "a\nc");
+        roundtrip("a", "a\nb");
+        roundtrip("a\nb", "b");
+        roundtrip("ab\ncd", "a\nb\nc\nd");
+        roundtrip("x\nx\nx", "x\nx");
+        roundtrip("x", "abc\ndef");
+        roundtrip("foo bar", "bar foo");
+    }
+
+    #[test]
+    fn test_edit_merge() {
+        let edit = |row, col, deleted: &str, inserted: &str| Edit {
+            start: Index2::new(row, col),
+            deleted: deleted.into(),
+            inserted: inserted.into(),
+        };
+        let mut typed = edit(0, 1, "", "ab");
+        assert!(typed.merge(&edit(0, 3, "", "\n")));
+        assert!(typed.merge(&edit(1, 0, "", "c")));
+        assert!(typed.merge(&edit(1, 0, "c", "")));
+        assert_eq!(typed, edit(0, 1, "", "ab\n"));
+
+        // Deletes right in front of and behind the inserted text.
+        assert!(typed.merge(&edit(0, 0, "x", "")));
+        assert!(typed.merge(&edit(1, 0, "y", "")));
+        assert_eq!(typed, edit(0, 0, "xy", "ab\n"));
+        assert!(!typed.merge(&edit(1, 1, "", "z")));
+    }
+
+    #[test]
+    fn test_undo_step_holds_recorded_edits() {
+        let mut state = EditorState::new(Lines::from("aab"));
+        state.execute(SwitchMode(EditorMode::Insert));
+        state.history.set_insert_session(true);
+        state.execute(InsertChar('a'));
+        state.execute(InsertChar('x'));
+        state.execute(DeleteChar(1));
+        state.execute(SwitchMode(EditorMode::Normal));
+        state.history.set_insert_session(false);
+        state.finish_undo_step();
+
+        let step = state.history.nodes[&state.history.current].step.as_ref();
+        let edit = Edit {
+            start: Index2::new(0, 0),
+            deleted: String::new(),
+            inserted: "a".into(),
+        };
+        assert_eq!(step.map(|step| step.edits.clone()), Some(vec![edit]));
+        state.undo();
+        assert_eq!(state.lines, Lines::from("aab"));
+        state.redo();
+        assert_eq!(state.lines, Lines::from("aaab"));
+
+        // Edits that cancel out leave no step.
+        let mut state = EditorState::new(Lines::from("aab"));
+        state.execute(SwitchMode(EditorMode::Insert));
+        state.history.set_insert_session(true);
+        state.execute(InsertChar('a'));
+        state.execute(DeleteChar(1));
+        state.execute(SwitchMode(EditorMode::Normal));
+        state.history.set_insert_session(false);
+        assert!(!state.can_undo());
+    }
+
+    #[test]
+    fn test_undo_restores_cursor() {
+        let mut state = EditorState::new(Lines::from("abc\ndef"));
+        state.selection = Some(Selection::new(Index2::new(1, 1), Index2::new(1, 2)));
+        state.mode = EditorMode::Visual;
+        state.cursor = Index2::new(1, 2);
+        state.execute(Composed::new(DeleteSelection).chain(SwitchMode(EditorMode::Normal)));
+        assert_eq!(state.lines, Lines::from("abc\nd"));
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("abc\ndef"));
+        assert_eq!(state.cursor, Index2::new(1, 2));
+        assert_eq!(state.selection, None);
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        state.redo();
+        assert_eq!(state.lines, Lines::from("abc\nd"));
+        assert_eq!(state.cursor, Index2::new(1, 0));
+        assert!(!state.can_redo());
+
+        // The selection an operator was applied to is not selected again.
+        let mut state = EditorState::new(Lines::from("a\nb"));
+        state.selection = Some(Selection::new(Index2::new(0, 0), Index2::new(1, 0)).line_mode());
+        state.mode = EditorMode::VisualLine;
+        state.execute(OperateSelection(Operator::IndentRight));
+        assert_eq!(state.lines, Lines::from("\ta\n\tb"));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("a\nb"));
+        assert_eq!(state.mode, EditorMode::Normal);
+        assert_eq!(state.selection, None);
+    }
+
+    #[test]
+    fn test_undo_groups() {
+        let mut state = EditorState::new(Lines::from("a\nb\nc\nd"));
+        Composed::new(DeleteLine(1))
+            .chain(DeleteLine(1))
+            .execute(&mut state);
+        state.execute(DeleteLine(1));
+        assert_eq!(state.lines, Lines::from("d"));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("c\nd"));
+        state.undo();
+        assert_eq!(state.lines, Lines::from("a\nb\nc\nd"));
+        assert!(!state.can_undo());
+    }
+
+    #[test]
+    fn test_insert_session_is_one_step() {
+        let mut state = EditorState::new(Lines::from("ab"));
+        state.execute(SwitchMode(EditorMode::Insert));
+        state.history.set_insert_session(true);
+        state.execute(InsertChar('x'));
+        state.execute(LineBreak(1));
+        state.execute(InsertChar('y'));
+        state.execute(DeleteChar(1));
+        state.execute(InsertChar('y'));
+        state.execute(SwitchMode(EditorMode::Normal));
+        state.history.set_insert_session(false);
+        assert_eq!(state.lines, Lines::from("x\nyab"));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("ab"));
+        assert!(!state.can_undo());
+    }
+
+    #[test]
+    fn test_undo_limits() {
+        let mut state = EditorState::new(Lines::from("abcdef"));
+        state.cursor = Index2::new(0, 6);
+        for _ in 0..4 {
+            state.execute(DeleteChar(1));
         }
+        state.set_undo_depth(2);
+        state.undo();
+        state.undo();
+        state.undo();
+        assert_eq!(state.lines, Lines::from("abcd"));
+
+        state.set_undo_memory_limit(Some(0));
+        assert!(!state.can_undo());
+        state.execute(DeleteChar(1));
+        state.undo();
+        assert_eq!(state.lines, Lines::from("abc"));
+    }
+
+    #[test]
+    fn test_undo_tree() {
+        let mut state = EditorState::new(Lines::from("abc"));
+        state.cursor = Index2::new(0, 3);
+        state.execute(DeleteChar(1));
+        state.execute(DeleteChar(1));
+        state.undo();
+        state.undo();
+        state.cursor = Index2::new(0, 0);
+        state.capture();
+        state.execute(InsertChar('x'));
+
+        // Undone changes stay reachable on their own branch.
+        state.undo();
+        state.redo();
+        assert_eq!(state.lines, Lines::from("xabc"));
+        assert_eq!(state.undo_seq(), 3);
+        assert!(state.undo_to(2));
+        assert_eq!(state.lines, Lines::from("a"));
+        assert!(!state.undo_to(4));
+
+        // Moving in time crosses branches.
+        state.undo_later(1);
+        assert_eq!(state.lines, Lines::from("xabc"));
+        state.undo_earlier(1);
+        assert_eq!(state.lines, Lines::from("a"));
+        state.undo_earlier(5);
+        assert_eq!(state.lines, Lines::from("abc"));
+        assert_eq!(state.undo_seq(), 0);
+
+        let entries: Vec<_> = state
+            .undo_history()
+            .iter()
+            .map(|entry| (entry.seq, entry.parent, entry.position))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (0, None, None),
+                (1, Some(0), Some(Index2::new(0, 2))),
+                (2, Some(1), Some(Index2::new(0, 1))),
+                (3, Some(0), Some(Index2::new(0, 0))),
+            ]
+        );
+
+        let time = state.undo_history()[3].time;
+        state.undo_to_time(time);
+        assert_eq!(state.lines, Lines::from("xabc"));
+    }
+
+    #[test]
+    fn test_undo_tree_limits() {
+        let mut state = EditorState::new(Lines::from("abc"));
+        state.cursor = Index2::new(0, 3);
+        state.execute(DeleteChar(1));
+        state.undo();
+        state.execute(DeleteChar(1));
+        state.execute(DeleteChar(1));
+        state.undo();
+        state.redo();
+
+        // The branch of the oldest change is dropped first, then the oldest
+        // change on the current branch becomes the oldest state.
+        state.set_undo_depth(2);
+        assert_eq!(state.undo_history().len(), 3);
+        state.set_undo_depth(1);
+        let entries = state.undo_history();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].seq, entries[0].parent), (2, None));
+        state.undo();
+        state.undo();
+        assert_eq!(state.lines, Lines::from("ab"));
     }
 }
@@ -0,0 +1,289 @@
+//! Saving and restoring the undo history of a buffer, e.g. to keep undo
+//! across sessions. Requires the `serde` feature.
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use super::{row_of, Checkpoint, Edit, UndoHistory, UndoNode, UndoStep};
+use crate::{EditorState, Index2, Lines};
+
+/// The undo history of a buffer with all its branches, the redo states and
+/// the cursor positions they restore.
+///
+/// A snapshot is taken with [`EditorState::undo_snapshot`], can be
+/// serialized with any serde format and is restored with
+/// [`EditorState::restore_undo_snapshot`]. It holds a hash of the text and
+/// is only restored onto the same text.
+///
+/// # Example
+///
+/// ```
+/// use edtui::{EditorState, Lines, UndoRestoreError};
+/// use edtui::actions::{DeleteLine, Execute};
+///
+/// let mut state = EditorState::new(Lines::from("a\nb"));
+/// state.execute(DeleteLine(1));
+/// let snapshot = state.undo_snapshot();
+///
+/// // After a restart, with the text loaded again.
+/// let mut state = EditorState::new(Lines::from("b"));
+/// state.restore_undo_snapshot(snapshot.clone()).unwrap();
+/// state.undo();
+/// assert_eq!(state.lines, Lines::from("a\nb"));
+///
+/// let mut state = EditorState::new(Lines::from("changed"));
+/// assert_eq!(state.restore_undo_snapshot(snapshot), Err(UndoRestoreError::Stale));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UndoSnapshot {
+    /// The hash of the text at the current state.
+    hash: u64,
+    current: usize,
+    nodes: Vec<SnapshotNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SnapshotNode {
+    seq: usize,
+    parent: Option<usize>,
+    redo_child: Option<usize>,
+    time: SystemTime,
+    step: Option<SnapshotStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SnapshotStep {
+    edits: Vec<SnapshotEdit>,
+    cursor_before: (usize, usize),
+    cursor_after: (usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SnapshotEdit {
+    start: (usize, usize),
+    deleted: String,
+    inserted: String,
+}
+
+/// An error from restoring an [`UndoSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoRestoreError {
+    /// The text changed since the snapshot was taken.
+    Stale,
+    /// The snapshot is not a valid undo history.
+    Invalid,
+}
+
+impl std::fmt::Display for UndoRestoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stale => write!(f, "The undo history does not match the text"),
+            Self::Invalid => write!(f, "Invalid undo history"),
+        }
+    }
+}
+
+impl std::error::Error for UndoRestoreError {}
+
+impl EditorState {
+    /// Returns the undo history of the buffer, to be saved with the text.
+    /// Ends the change in progress first.
+    pub fn undo_snapshot(&mut self) -> UndoSnapshot {
+        self.commit_undo_step();
+        let nodes = self
+            .history
+            .nodes
+            .iter()
+            .map(|(&seq, node)| SnapshotNode {
+                seq,
+                parent: node.parent,
+                redo_child: node.redo_child,
+                time: node.time,
+                step: node.step.as_ref().map(|step| SnapshotStep {
+                    edits: step.edits.iter().map(SnapshotEdit::from).collect(),
+                    cursor_before: (step.before.cursor.row, step.before.cursor.col),
+                    cursor_after: (step.after.cursor.row, step.after.cursor.col),
+                }),
+            })
+            .collect();
+        UndoSnapshot {
+            hash: content_hash(&self.lines),
+            current: self.history.current,
+            nodes,
+        }
+    }
+
+    /// Replaces the undo history with a saved one. The limits set with
+    /// [`set_undo_depth`](Self::set_undo_depth) and
+    /// [`set_undo_memory_limit`](Self::set_undo_memory_limit) still apply.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UndoRestoreError::Stale`] if the text is not the one the
+    /// snapshot was taken of, and [`UndoRestoreError::Invalid`] if the
+    /// snapshot is malformed. The history is unchanged then.
+    pub fn restore_undo_snapshot(
+        &mut self,
+        snapshot: UndoSnapshot,
+    ) -> Result<(), UndoRestoreError> {
+        if snapshot.hash != content_hash(&self.lines) {
+            return Err(UndoRestoreError::Stale);
+        }
+
+        let mut history = UndoHistory::new();
+        history.max_depth = self.history.max_depth;
+        history.max_memory = self.history.max_memory;
+        history.nodes = BTreeMap::new();
+        let mut roots = Vec::new();
+        for node in snapshot.nodes {
+            // Parents are older than their children, so the tree has no cycles.
+            let parent = match node.parent {
+                Some(parent) if parent < node.seq && history.nodes.contains_key(&parent) => {
+                    Some(parent)
+                }
+                Some(_) => return Err(UndoRestoreError::Invalid),
+                None => {
+                    roots.push(node.seq);
+                    None
+                }
+            };
+            let step: Option<UndoStep> = match (parent, node.step) {
+                (Some(_), Some(step)) => Some(step.into()),
+                (None, None) => None,
+                _ => return Err(UndoRestoreError::Invalid),
+            };
+            if let Some(step) = &step {
+                history.memory += step.size();
+            }
+            let mut restored = UndoNode::new(parent, step);
+            restored.time = node.time;
+            restored.redo_child = node.redo_child;
+            history.nodes.insert(node.seq, restored);
+            if let Some(parent) = parent.and_then(|parent| history.nodes.get_mut(&parent)) {
+                parent.children.push(node.seq);
+            }
+        }
+
+        let [root] = roots[..] else {
+            return Err(UndoRestoreError::Invalid);
+        };
+        if !history.nodes.contains_key(&snapshot.current) {
+            return Err(UndoRestoreError::Invalid);
+        }
+        history.root = root;
+        history.current = snapshot.current;
+        history.saved = (!self.is_modified()).then_some(snapshot.current);
+        history.next_seq = history.nodes.keys().last().map_or(0, |seq| seq + 1);
+        history.trim();
+        self.history = history;
+        Ok(())
+    }
+}
+
+impl From<SnapshotStep> for UndoStep {
+    fn from(step: SnapshotStep) -> Self {
+        let checkpoint = |(row, col)| Checkpoint {
+            cursor: Index2::new(row, col),
+        };
+        Self {
+            edits: step.edits.into_iter().map(Edit::from).collect(),
+            before: checkpoint(step.cursor_before),
+            after: checkpoint(step.cursor_after),
+        }
+    }
+}
+
+impl From<&Edit> for SnapshotEdit {
+    fn from(edit: &Edit) -> Self {
+        Self {
+            start: (edit.start.row, edit.start.col),
+            deleted: edit.deleted.clone(),
+            inserted: edit.inserted.clone(),
+        }
+    }
+}
+
+impl From<SnapshotEdit> for Edit {
+    fn from(edit: SnapshotEdit) -> Self {
+        Self {
+            start: Index2::new(edit.start.0, edit.start.1),
+            deleted: edit.deleted,
+            inserted: edit.inserted,
+        }
+    }
+}
+
+/// A 64-bit FNV-1a hash of the text, which is stable across builds.
+fn content_hash(lines: &Lines) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut write = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    for row in 0..lines.len() {
+        if row > 0 {
+            write(b"\n");
+        }
+        for c in row_of(lines, row) {
+            write(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::DeleteChar;
+
+    #[test]
+    fn test_undo_snapshot_roundtrip() {
+        let mut state = EditorState::new(Lines::from("abc\nd"));
+        state.cursor = Index2::new(0, 3);
+        state.execute(DeleteChar(2));
+        state.undo();
+        state.cursor = Index2::new(1, 1);
+        state.execute(DeleteChar(1));
+        state.undo();
+        let snapshot = state.undo_snapshot();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: UndoSnapshot = serde_json::from_str(&json).unwrap();
+        let mut restored = EditorState::new(Lines::from("abc\nd"));
+        restored.restore_undo_snapshot(snapshot).unwrap();
+        assert_eq!(restored.undo_history(), state.undo_history());
+
+        restored.redo();
+        assert_eq!(restored.lines, Lines::from("abc\n"));
+        assert_eq!(restored.cursor, Index2::new(1, 0));
+        restored.undo_to(1);
+        assert_eq!(restored.lines, Lines::from("a\nd"));
+        restored.undo();
+        assert_eq!(restored.cursor, Index2::new(0, 3));
+    }
+
+    #[test]
+    fn test_restore_rejects_stale_and_invalid() {
+        let mut state = EditorState::new(Lines::from("ab"));
+        state.cursor = Index2::new(0, 2);
+        state.execute(DeleteChar(1));
+        let mut snapshot = state.undo_snapshot();
+
+        let mut other = EditorState::new(Lines::from("ab"));
+        assert_eq!(
+            other.restore_undo_snapshot(snapshot.clone()),
+            Err(UndoRestoreError::Stale)
+        );
+
+        snapshot.nodes[1].parent = Some(5);
+        assert_eq!(
+            state.restore_undo_snapshot(snapshot),
+            Err(UndoRestoreError::Invalid)
+        );
+        state.undo();
+        assert_eq!(state.lines, Lines::from("ab"));
+    }
+}
@@ -25,6 +25,8 @@ pub(crate) struct ViewState {
     pub(crate) tab_width: usize,
     /// Line numbers configuration.
     pub(crate) line_numbers: LineNumbers,
+    /// Whether the marks are shown in a gutter column.
+    pub(crate) show_marks: bool,
     /// The cursor's screen position, computed during the last render.
     /// This is the absolute position in terminal coordinates where the cursor should be displayed.
     pub(crate) cursor_screen_position: Option<Position>,
@@ -41,6 +43,7 @@ impl Default for ViewState {
             wrap: true,
             tab_width: 2,
             line_numbers: LineNumbers::None,
+            show_marks: false,
             cursor_screen_position: None,
             single_line: false,
         }
@@ -10,10 +10,12 @@ use render_line::RenderLine;
 #[cfg(feature = "syntax-highlighting")]
 use syntax_higlighting::SyntaxHighlighter;
 
+use std::ops::Range;
+
 use crate::{
     helper::{max_col, rect_indent_y},
     state::{highlight::Highlight, selection::Selection, EditorState},
-    EditorMode, Index2,
+    EditorMode, IndentOptions, Index2,
 };
 
 #[cfg(feature = "syntax-highlighting")]
@@ -160,6 +162,13 @@ impl<'a, 'b> EditorView<'a, 'b> {
         self.state.view.tab_width
     }
 
+    /// Sets how lines are indented, e.g. with spaces and auto-indent.
+    #[must_use]
+    pub fn indent_options(self, options: IndentOptions) -> Self {
+        self.state.set_indent_options(options);
+        self
+    }
+
     /// Configures line numbers. Disabled by default.
     ///
     /// # Example
@@ -186,6 +195,24 @@ impl<'a, 'b> EditorView<'a, 'b> {
         self.state.view.line_numbers
     }
 
+    /// Shows the marks in a gutter column left of the line numbers.
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use edtui::{EditorState, EditorView};
+    ///
+    /// let mut state = EditorState::default();
+    ///
+    /// EditorView::new(&mut state).show_marks(true);
+    /// ```
+    #[must_use]
+    pub fn show_marks(self, show_marks: bool) -> Self {
+        self.state.view.show_marks = show_marks;
+        self
+    }
+
     /// Returns a reference to the [`EditorState`].
     #[must_use]
     pub fn get_state(&'a self) -> &'a EditorState {
@@ -198,6 +225,11 @@ impl<'a, 'b> EditorView<'a, 'b> {
         self.state
     }
 
+    /// Calculate the width needed for the marks in the gutter.
+    fn marks_width(&self) -> u16 {
+        u16::from(self.state.view.show_marks)
+    }
+
     /// Calculate the width needed for the line number gutter.
     fn line_number_width(&self) -> u16 {
         if self.state.view.line_numbers == LineNumbers::None {
@@ -208,6 +240,26 @@ impl<'a, 'b> EditorView<'a, 'b> {
         let digits = total_lines.to_string().len();
         (digits + 1) as u16
     }
+
+    /// Returns the highlights of the search matches within `rows`, with the
+    /// selected match in a distinct style.
+    fn search_highlights(&self, rows: Range<usize>) -> Vec<Highlight> {
+        let search = &self.state.search;
+        search
+            .matches
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| rows.contains(&m.start.row))
+            .map(|(i, m)| {
+                let style = if Some(i) == search.selected_index {
+                    self.theme.current_match_style
+                } else {
+                    self.theme.search_match_style
+                };
+                Highlight::new(m.start, m.end, style)
+            })
+            .collect()
+    }
 }
 
 impl Widget for EditorView<'_, '_> {
@@ -231,12 +283,14 @@ impl Widget for EditorView<'_, '_> {
         ])
         .areas(area);
 
-        // Calculate line number gutter width and split area
+        // Calculate the gutter width for marks and line numbers and split area
+        let marks_width = self.marks_width();
         let line_number_width = self.line_number_width();
         let line_numbers_style = self.theme.line_numbers_style;
-        let (gutter_area, content_main) = if line_number_width > 0 {
+        let gutter_width = marks_width + line_number_width;
+        let (gutter_area, content_main) = if gutter_width > 0 {
             let [gutter, content] =
-                Layout::horizontal([Constraint::Length(line_number_width), Constraint::Min(0)])
+                Layout::horizontal([Constraint::Length(gutter_width), Constraint::Min(0)])
                     .areas(main);
             // Fill the entire gutter with the line numbers style
             buf.set_style(gutter, line_numbers_style);
@@ -278,12 +332,19 @@ impl Widget for EditorView<'_, '_> {
             )
         };
 
-        // Predetermine highlighted sections.
-        let mut search_selection: Option<Selection> = None;
-        if self.state.mode == EditorMode::Search {
-            search_selection = (&self.state.search).into();
-        };
-        let selections = vec![&self.state.selection, &search_selection];
+        // Predetermine highlighted sections. Search matches take priority
+        // over custom highlights.
+        let selections = vec![&self.state.selection];
+        let mut highlights = Vec::new();
+        if self.state.mode == EditorMode::Search || self.state.is_replacing() {
+            highlights = self.search_highlights(offset_y..offset_y + height);
+        }
+        highlights.extend(
+            self.state
+                .highlights
+                .iter()
+                .filter_map(|highlight| self.state.resolve_highlight(highlight)),
+        );
 
         let mut cursor_position: Option<Position> = None;
         let mut content_area = content_main;
@@ -292,6 +353,11 @@ impl Widget for EditorView<'_, '_> {
 
         let line_numbers_enabled = line_numbers != LineNumbers::None;
         let is_relative = line_numbers == LineNumbers::Relative;
+        let marks: Vec<(char, Index2)> = if marks_width > 0 {
+            self.state.marks().collect()
+        } else {
+            Vec::new()
+        };
 
         let row_index = offset_y;
         for (row_index, line) in (offset_y..).zip(lines.iter_row().skip(row_index)) {
@@ -305,7 +371,7 @@ impl Widget for EditorView<'_, '_> {
             let spans = generate_spans(
                 line,
                 &selections,
-                &self.state.highlights,
+                &highlights,
                 row_index,
                 col_skips,
                 &self.theme.base,
@@ -320,9 +386,15 @@ impl Widget for EditorView<'_, '_> {
                 RenderLine::Single(spans)
             };
 
-            // Render line number in the gutter
-            if line_numbers_enabled {
-                if let Some(gutter) = gutter_row_area {
+            if let Some(gutter) = gutter_row_area {
+                // Render the first mark of the row in the gutter
+                if let Some((name, _)) = marks.iter().find(|(_, pos)| pos.row == row_index) {
+                    let mark_span = Span::styled(name.to_string(), self.theme.marks_style);
+                    buf.set_span(gutter.x, gutter.y, &mark_span, marks_width);
+                }
+
+                // Render line number in the gutter
+                if line_numbers_enabled {
                     let is_cursor_line = row_index == cursor.row;
                     let line_num = if is_relative {
                         if is_cursor_line {
@@ -350,22 +422,23 @@ impl Widget for EditorView<'_, '_> {
                     };
                     let num_span = Span::styled(num_str, line_numbers_style);
 
-                    let line_num_area = Rect::new(gutter.x, gutter.y, gutter.width, 1);
+                    let line_num_area =
+                        Rect::new(gutter.x + marks_width, gutter.y, line_number_width, 1);
                     buf.set_span(
                         line_num_area.x,
                         line_num_area.y,
                         &num_span,
                         line_num_area.width,
                     );
-
-                    let num_lines = render_line.num_lines() as u16;
-                    gutter_row_area = Some(Rect::new(
-                        gutter.x,
-                        gutter.y.saturating_add(num_lines),
-                        gutter.width,
-                        gutter.height.saturating_sub(num_lines),
-                    ));
                 }
+
+                let num_lines = render_line.num_lines() as u16;
+                gutter_row_area = Some(Rect::new(
+                    gutter.x,
+                    gutter.y.saturating_add(num_lines),
+                    gutter.width,
+                    gutter.height.saturating_sub(num_lines),
+                ));
             }
 
             // Determine the cursor position.
@@ -411,6 +484,25 @@ impl Widget for EditorView<'_, '_> {
                 } else {
                     None
                 })
+                .search_backward(self.state.search.backward)
+                .search_counter(if self.state.mode == EditorMode::Search {
+                    self.state.search_counter()
+                } else {
+                    None
+                })
+                .command(if self.state.mode == EditorMode::Command {
+                    Some(self.state.command_line())
+                } else {
+                    None
+                })
+                .message(
+                    self.state
+                        .command_error()
+                        .map(ToString::to_string)
+                        .or_else(|| self.state.replace_prompt()),
+                )
+                .read_only(self.state.read_only)
+                .recording(self.state.recording_macro())
                 .render(status, buf);
         }
     }
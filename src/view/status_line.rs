@@ -10,6 +10,18 @@ pub struct EditorStatusLine {
     mode: String,
     /// The current search buffer. Shown only in search mode.
     search: Option<String>,
+    /// The position of the selected search match and the number of matches.
+    search_counter: Option<(usize, usize)>,
+    /// Whether the search goes backward, shown with a `?` instead of a `/`.
+    search_backward: bool,
+    /// The current command line. Shown only in command mode.
+    command: Option<String>,
+    /// A message such as the error of the last command.
+    message: Option<String>,
+    /// Whether the editor is read-only, shown as `[RO]`.
+    read_only: bool,
+    /// The register a macro is recorded into, shown as `recording @a`.
+    recording: Option<char>,
     /// The style for the mode of the status line
     style_mode: Option<Style>,
     /// The style for the search of the status line
@@ -28,6 +40,12 @@ impl Default for EditorStatusLine {
         Self {
             mode: String::new(),
             search: None,
+            search_counter: None,
+            search_backward: false,
+            command: None,
+            message: None,
+            read_only: false,
+            recording: None,
             style_mode: Some(Style::default().fg(WHITE).bg(DARK_GRAY).bold()),
             style_search: Some(Style::default().fg(WHITE).bg(DARK_GRAY)),
             style_line: Style::default().fg(WHITE).bg(DARK_GRAY),
@@ -100,6 +118,64 @@ impl EditorStatusLine {
         self
     }
 
+    /// Overwrite the search match counter, shown as `[3/17]` after the search.
+    ///
+    /// This method is used internally to show the selected search match.
+    #[must_use]
+    pub fn search_counter(mut self, counter: Option<(usize, usize)>) -> Self {
+        self.search_counter = counter;
+        self
+    }
+
+    /// Overwrite the direction of the search, shown as `?` for backward and
+    /// `/` for forward searches.
+    ///
+    /// This method is used internally to show the direction of the search.
+    #[must_use]
+    pub fn search_backward(mut self, backward: bool) -> Self {
+        self.search_backward = backward;
+        self
+    }
+
+    /// Overwrite the command line content for the status line.
+    ///
+    /// This method is used internally to show the command line in command mode.
+    #[must_use]
+    pub fn command<S: Into<String>>(mut self, command: Option<S>) -> Self {
+        self.command = command.map(Into::into);
+        self
+    }
+
+    /// Overwrite the message for the status line, shown when there is no
+    /// search or command line.
+    ///
+    /// This method is used internally to show the error of the last command.
+    #[must_use]
+    pub fn message<S: Into<String>>(mut self, message: Option<S>) -> Self {
+        self.message = message.map(Into::into);
+        self
+    }
+
+    /// Overwrite whether the editor is read-only, shown as `[RO]` in front
+    /// of the message.
+    ///
+    /// This method is used internally to show the read-only mode.
+    #[must_use]
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Overwrite the register a macro is recorded into, shown as
+    /// `recording @a` when there is no message.
+    ///
+    /// This method is used internally to show the recording of a macro.
+    #[must_use]
+    pub fn recording(mut self, recording: Option<char>) -> Self {
+        self.recording = recording;
+        self
+    }
+
     #[deprecated(
         since = "0.10.4",
         note = "Please use `alignment(HorizontalAlignment::Left)` or `alignment(HorizontalAlignment::Right)` instead"
@@ -134,9 +210,22 @@ impl Widget for EditorStatusLine {
 
         let layout = Layout::horizontal(constraints).split(area);
 
-        let search_text = match self.search {
-            None => String::new(),
-            Some(search) => format!("/{search}"),
+        let message = self
+            .message
+            .or_else(|| self.recording.map(|name| format!("recording @{name}")));
+        let search_text = match (self.search, self.command, message) {
+            (Some(search), _, _) => {
+                let prefix = if self.search_backward { '?' } else { '/' };
+                match self.search_counter {
+                    Some((position, total)) => format!("{prefix}{search} [{position}/{total}]"),
+                    None => format!("{prefix}{search}"),
+                }
+            }
+            (None, Some(command), _) => format!(":{command}"),
+            (None, None, Some(message)) if self.read_only => format!("[RO] {message}"),
+            (None, None, Some(message)) => message,
+            (None, None, None) if self.read_only => String::from("[RO]"),
+            (None, None, None) => String::new(),
         };
 
         let mode_span = Span::raw(format!("{:^10}", self.mode))
@@ -10,12 +10,18 @@ pub struct EditorTheme<'a> {
     pub cursor_style: Style,
     /// The text style in visual mode when a text is selected
     pub selection_style: Style,
+    /// The style of the search matches in search mode
+    pub search_match_style: Style,
+    /// The style of the selected search match in search mode
+    pub current_match_style: Style,
     /// The surrounding block
     pub block: Option<Block<'a>>,
     /// An optional [`StatusLine`] displaying the editor mode
     pub status_line: Option<EditorStatusLine>,
     /// Style for line numbers (subdued by default)
     pub line_numbers_style: Style,
+    /// Style for the marks in the gutter
+    pub marks_style: Style,
 }
 
 impl Default for EditorTheme<'_> {
@@ -28,8 +34,11 @@ impl Default for EditorTheme<'_> {
             block: None,
             cursor_style: Style::default().bg(WHITE).fg(BLACK),
             selection_style: Style::default().bg(YELLOW).fg(BLACK),
+            search_match_style: Style::default().bg(DARK_YELLOW).fg(WHITE),
+            current_match_style: Style::default().bg(YELLOW).fg(BLACK),
             status_line: Some(EditorStatusLine::default()),
             line_numbers_style: Style::default().bg(BLACK).fg(GRAY),
+            marks_style: Style::default().bg(BLACK).fg(YELLOW),
         }
     }
 }
@@ -143,6 +152,42 @@ impl<'a> EditorTheme<'a> {
         self
     }
 
+    /// This method allows you to customize the style of the search matches
+    /// in search mode. The selected match uses `current_match_style`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use edtui::EditorTheme;
+    /// use ratatui::style::{Style, Color};
+    ///
+    /// let theme = EditorTheme::default()
+    ///     .search_match_style(Style::default().bg(Color::DarkGray));
+    /// ```
+    #[must_use]
+    pub fn search_match_style(mut self, style: Style) -> Self {
+        self.search_match_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the selected
+    /// search match in search mode.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use edtui::EditorTheme;
+    /// use ratatui::style::{Style, Color};
+    ///
+    /// let theme = EditorTheme::default()
+    ///     .current_match_style(Style::default().bg(Color::Yellow));
+    /// ```
+    #[must_use]
+    pub fn current_match_style(mut self, style: Style) -> Self {
+        self.current_match_style = style;
+        self
+    }
+
     /// This method allows you to customize the style of the [`StatusLine`]
     /// of the Editor. See [`StatusLine`] on how to modify its appearance.
     /// Use `hide_status_line` to hide the status line.
@@ -196,10 +241,29 @@ impl<'a> EditorTheme<'a> {
         self.line_numbers_style = style;
         self
     }
+
+    /// Customize the style of the marks in the gutter.
+    /// By default, marks are displayed in yellow.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use edtui::EditorTheme;
+    /// use ratatui::style::{Style, Color};
+    ///
+    /// let theme = EditorTheme::default()
+    ///     .marks_style(Style::default().fg(Color::Cyan));
+    /// ```
+    #[must_use]
+    pub fn marks_style(mut self, style: Style) -> Self {
+        self.marks_style = style;
+        self
+    }
 }
 
 pub(crate) const WHITE: Color = Color::Rgb(255, 255, 255);
 pub(crate) const BLACK: Color = Color::Rgb(0, 0, 0);
 pub(crate) const DARK_GRAY: Color = Color::Rgb(16, 17, 22);
 pub(crate) const YELLOW: Color = Color::Rgb(250, 204, 21);
+pub(crate) const DARK_YELLOW: Color = Color::Rgb(133, 77, 14);
 pub(crate) const GRAY: Color = Color::Rgb(100, 100, 100);